@@ -1,3 +1,5 @@
+#![allow(clippy::ptr_arg)]
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use dashmap::DashMap;
 use htcache::db::cmap::CMap;
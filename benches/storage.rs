@@ -0,0 +1,94 @@
+//! Compares the storage backends through the StorageBackend trait.
+//! Backends behind a feature are only measured when the feature is enabled, for instance with
+//! `cargo bench --bench storage --features rocksdb,sled`.
+//! Throughputs are reported in operations per second.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use htcache::db::cmap::CMap;
+use htcache::db::StorageBackend;
+use rand::distributions::{Alphanumeric, DistString};
+
+const KEY_COUNT: usize = 100_000;
+
+fn generate_keys() -> Vec<String> {
+    (0..KEY_COUNT)
+        .map(|_| Alphanumeric.sample_string(&mut rand::thread_rng(), 30))
+        .collect()
+}
+
+fn fill(backend: &dyn StorageBackend, keys: &[String]) {
+    for key in keys {
+        backend.set(key, key);
+    }
+}
+
+/// read_heavy reads the keys, overwriting one of them out of ten.
+fn read_heavy(backend: &dyn StorageBackend, keys: &[String]) {
+    for (i, key) in keys.iter().enumerate() {
+        if i % 10 == 0 {
+            backend.set(key, key);
+        } else {
+            black_box(backend.get(key));
+        }
+    }
+}
+
+/// write_heavy only writes: it overwrites every key.
+fn write_heavy(backend: &dyn StorageBackend, keys: &[String]) {
+    for key in keys {
+        backend.set(key, key);
+    }
+}
+
+pub fn criterion_read_heavy_benchmark(c: &mut Criterion) {
+    let keys = generate_keys();
+    let mut group = c.benchmark_group("read-heavy");
+    group.throughput(Throughput::Elements(keys.len() as u64));
+
+    let cmap = CMap::new(32, KEY_COUNT / 32).unwrap();
+    fill(&cmap, &keys);
+    group.bench_function("cmap", |b| b.iter(|| read_heavy(&cmap, black_box(&keys))));
+
+    #[cfg(feature = "rocksdb")]
+    {
+        let path = std::env::temp_dir().join("htcache-bench-rocksdb");
+        let backend = htcache::db::storage::RocksDbBackend::open(&path).unwrap();
+        fill(&backend, &keys);
+        group.bench_function("rocksdb", |b| {
+            b.iter(|| read_heavy(&backend, black_box(&keys)))
+        });
+        drop(backend);
+        rocksdb::DB::destroy(&rocksdb::Options::default(), &path).unwrap();
+    }
+
+    group.finish();
+}
+
+pub fn criterion_write_heavy_benchmark(c: &mut Criterion) {
+    let keys = generate_keys();
+    let mut group = c.benchmark_group("write-heavy");
+    group.throughput(Throughput::Elements(keys.len() as u64));
+
+    let cmap = CMap::new(32, KEY_COUNT / 32).unwrap();
+    group.bench_function("cmap", |b| b.iter(|| write_heavy(&cmap, black_box(&keys))));
+
+    #[cfg(feature = "sled")]
+    {
+        let path = std::env::temp_dir().join("htcache-bench-sled");
+        let backend = htcache::db::storage::SledBackend::open(&path).unwrap();
+        group.bench_function("sled", |b| {
+            b.iter(|| write_heavy(&backend, black_box(&keys)))
+        });
+        drop(backend);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    criterion_read_heavy_benchmark,
+    criterion_write_heavy_benchmark
+);
+criterion_main!(benches);
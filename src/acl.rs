@@ -0,0 +1,393 @@
+//! Access control lists: the users allowed to connect and the commands, keys and channels
+//! each of them can access. Rules are expressed with the Redis ACL SETUSER syntax.
+
+use crate::acl_log::{AclLog, ACL_LOG_MAX_LEN};
+use crate::error::AclError;
+use crate::glob;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter, Write};
+use std::sync::RwLock;
+
+/// Name of the user every connection is authenticated as until AUTH is used.
+pub const DEFAULT_USER: &str = "default";
+
+/// AclCommandRules records which commands a user can run. As in Redis, rules are applied in order,
+/// so `+@all -del` allows everything but DEL while `-@all +get` only allows GET.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AclCommandRules {
+    all: bool,
+    // commands allowed on top of the base rule, only used when all is false
+    allowed: BTreeSet<String>,
+    // commands denied on top of the base rule, only used when all is true
+    denied: BTreeSet<String>,
+}
+
+impl AclCommandRules {
+    fn allow_all(&mut self, all: bool) {
+        self.all = all;
+        self.allowed.clear();
+        self.denied.clear();
+    }
+
+    fn allow(&mut self, command: &str, allowed: bool) {
+        let command = command.to_lowercase();
+        match (self.all, allowed) {
+            (true, true) => self.denied.remove(&command),
+            (true, false) => self.denied.insert(command),
+            (false, true) => self.allowed.insert(command),
+            (false, false) => self.allowed.remove(&command),
+        };
+    }
+
+    /// is_allowed checks if a command, given by its name, can be run.
+    pub fn is_allowed(&self, command: &str) -> bool {
+        let command = command.to_lowercase();
+        if self.all {
+            !self.denied.contains(&command)
+        } else {
+            self.allowed.contains(&command)
+        }
+    }
+}
+
+impl Display for AclCommandRules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.all {
+            write!(f, "+@all")?;
+            for command in &self.denied {
+                write!(f, " -{}", command)?;
+            }
+        } else {
+            write!(f, "-@all")?;
+            for command in &self.allowed {
+                write!(f, " +{}", command)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// AclRule holds the permissions of a user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclRule {
+    pub username: String,
+    /// SHA-256 of the password.
+    pub password_hash: Option<[u8; 32]>,
+    /// nopass users can authenticate with any password.
+    pub nopass: bool,
+    pub enabled: bool,
+    pub key_patterns: Vec<String>,
+    pub channel_patterns: Vec<String>,
+    pub commands: AclCommandRules,
+}
+
+impl AclRule {
+    /// new creates a user with no permission, as ACL SETUSER does for unknown users.
+    pub fn new(username: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password_hash: None,
+            nopass: false,
+            enabled: false,
+            key_patterns: Vec::new(),
+            channel_patterns: Vec::new(),
+            commands: AclCommandRules::default(),
+        }
+    }
+
+    /// default_user creates the user connections start with: it can run everything without password.
+    pub fn default_user() -> Self {
+        let mut rule = Self::new(DEFAULT_USER);
+        for modifier in ["on", "nopass", "~*", "&*", "+@all"] {
+            // the modifiers are known to be valid
+            let _ = rule.apply(modifier);
+        }
+        rule
+    }
+
+    /// apply updates the rule with a single ACL SETUSER modifier, like `on`, `>password` or `~key:*`.
+    pub fn apply(&mut self, modifier: &str) -> Result<(), AclError> {
+        let lowercase = modifier.to_lowercase();
+        match lowercase.as_str() {
+            "on" => self.enabled = true,
+            "off" => self.enabled = false,
+            "nopass" => {
+                self.nopass = true;
+                self.password_hash = None;
+            }
+            "resetpass" => {
+                self.nopass = false;
+                self.password_hash = None;
+            }
+            "allkeys" => self.key_patterns = vec!["*".to_string()],
+            "resetkeys" => self.key_patterns.clear(),
+            "allchannels" => self.channel_patterns = vec!["*".to_string()],
+            "resetchannels" => self.channel_patterns.clear(),
+            "allcommands" | "+@all" => self.commands.allow_all(true),
+            "nocommands" | "-@all" => self.commands.allow_all(false),
+            "reset" => *self = Self::new(&self.username),
+            _ => return self.apply_with_argument(modifier),
+        }
+        Ok(())
+    }
+
+    fn apply_with_argument(&mut self, modifier: &str) -> Result<(), AclError> {
+        let invalid = || AclError::InvalidModifier(modifier.to_string());
+        let mut chars = modifier.chars();
+        let prefix = chars.next().ok_or_else(invalid)?;
+        let argument = chars.as_str();
+        match prefix {
+            '>' => {
+                self.password_hash = Some(hash_password(argument));
+                self.nopass = false;
+            }
+            '<' => {
+                if self.password_hash == Some(hash_password(argument)) {
+                    self.password_hash = None;
+                }
+            }
+            '#' => {
+                self.password_hash = Some(decode_hash(argument).ok_or_else(invalid)?);
+                self.nopass = false;
+            }
+            '~' if !argument.is_empty() => self.key_patterns.push(argument.to_string()),
+            '&' if !argument.is_empty() => self.channel_patterns.push(argument.to_string()),
+            // command categories other than @all are not supported yet
+            '+' | '-' if argument.is_empty() || argument.starts_with('@') => return Err(invalid()),
+            '+' => self.commands.allow(argument, true),
+            '-' => self.commands.allow(argument, false),
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+
+    /// can_access_key checks if a key matches one of the key patterns of the user.
+    pub fn can_access_key(&self, key: &str) -> bool {
+        self.key_patterns
+            .iter()
+            .any(|pattern| glob::matches(pattern, key))
+    }
+
+    /// password_hex returns the hash of the password in hexadecimal, as shown by ACL LIST.
+    pub fn password_hex(&self) -> Option<String> {
+        self.password_hash.as_ref().map(encode_hash)
+    }
+
+    /// check_password tells if the user can authenticate with the password.
+    pub fn check_password(&self, password: &str) -> bool {
+        self.nopass || self.password_hash == Some(hash_password(password))
+    }
+}
+
+/// The compact format used by ACL LIST, which can be given back to ACL SETUSER.
+impl Display for AclRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "user {}", self.username)?;
+        write!(f, " {}", if self.enabled { "on" } else { "off" })?;
+        if self.nopass {
+            write!(f, " nopass")?;
+        }
+        if let Some(hex) = self.password_hex() {
+            write!(f, " #{}", hex)?;
+        }
+        if self.key_patterns.is_empty() {
+            write!(f, " resetkeys")?;
+        }
+        for pattern in &self.key_patterns {
+            write!(f, " ~{}", pattern)?;
+        }
+        if self.channel_patterns.is_empty() {
+            write!(f, " resetchannels")?;
+        }
+        for pattern in &self.channel_patterns {
+            write!(f, " &{}", pattern)?;
+        }
+        write!(f, " {}", self.commands)
+    }
+}
+
+/// Acl is the registry of users, shared by all the connections.
+#[derive(Debug)]
+pub struct Acl {
+    users: RwLock<HashMap<String, AclRule>>,
+    log: AclLog,
+}
+
+impl Default for Acl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Acl {
+    /// new creates a registry holding only the default user.
+    pub fn new() -> Self {
+        let mut users = HashMap::new();
+        users.insert(DEFAULT_USER.to_string(), AclRule::default_user());
+        Self {
+            users: RwLock::new(users),
+            log: AclLog::new(ACL_LOG_MAX_LEN),
+        }
+    }
+
+    /// log returns the log of the commands denied by the ACL.
+    pub fn log(&self) -> &AclLog {
+        &self.log
+    }
+
+    /// set_user creates or updates a user. Either all the modifiers are valid and applied, or none is.
+    pub fn set_user(&self, username: &str, modifiers: &[&str]) -> Result<(), AclError> {
+        let mut users = self.users.write().unwrap();
+        let mut rule = users
+            .get(username)
+            .cloned()
+            .unwrap_or_else(|| AclRule::new(username));
+        for modifier in modifiers {
+            rule.apply(modifier)?;
+        }
+        users.insert(username.to_string(), rule);
+        Ok(())
+    }
+
+    pub fn get_user(&self, username: &str) -> Option<AclRule> {
+        self.users.read().unwrap().get(username).cloned()
+    }
+
+    /// delete_users removes users and returns how many existed. The default user cannot be deleted.
+    pub fn delete_users(&self, usernames: &[String]) -> Result<usize, AclError> {
+        if usernames.iter().any(|username| username == DEFAULT_USER) {
+            return Err(AclError::DefaultUserDeletion);
+        }
+        let mut users = self.users.write().unwrap();
+        Ok(usernames
+            .iter()
+            .filter(|username| users.remove(username.as_str()).is_some())
+            .count())
+    }
+
+    /// list returns all the users, sorted by name.
+    pub fn list(&self) -> Vec<AclRule> {
+        let mut rules: Vec<AclRule> = self.users.read().unwrap().values().cloned().collect();
+        rules.sort_by(|a, b| a.username.cmp(&b.username));
+        rules
+    }
+
+    /// check verifies that a user can run a command on the given keys.
+    pub fn check(&self, username: &str, command: &str, keys: &[&str]) -> Result<(), AclError> {
+        // as in Redis, any user can authenticate as another one
+        if command.eq_ignore_ascii_case("auth") {
+            return Ok(());
+        }
+        let users = self.users.read().unwrap();
+        let rule = users
+            .get(username)
+            .filter(|rule| rule.enabled && rule.commands.is_allowed(command))
+            .ok_or_else(|| AclError::CommandDenied {
+                username: username.to_string(),
+                command: command.to_lowercase(),
+            })?;
+        match keys.iter().find(|key| !rule.can_access_key(key)) {
+            Some(key) => Err(AclError::KeyDenied(key.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn hash_password(password: &str) -> [u8; 32] {
+    Sha256::digest(password.as_bytes()).into()
+}
+
+fn encode_hash(hash: &[u8; 32]) -> String {
+    let mut hex = String::with_capacity(64);
+    for byte in hash {
+        // writing to a String cannot fail
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+fn decode_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_user() {
+        let acl = Acl::new();
+        assert_eq!(
+            acl.list()[0].to_string(),
+            "user default on nopass ~* &* +@all"
+        );
+        assert!(acl.check(DEFAULT_USER, "GET", &["any"]).is_ok());
+        assert!(acl.get_user(DEFAULT_USER).unwrap().check_password("any"));
+        assert!(matches!(
+            acl.delete_users(&[DEFAULT_USER.to_string()]),
+            Err(AclError::DefaultUserDeletion)
+        ));
+    }
+
+    #[test]
+    fn test_set_user() {
+        let acl = Acl::new();
+        acl.set_user(
+            "alice",
+            &["on", ">secret", "~cache:*", "-@all", "+get", "+SET"],
+        )
+        .unwrap();
+        let alice = acl.get_user("alice").unwrap();
+        assert!(alice.check_password("secret"));
+        assert!(!alice.check_password("guess"));
+        assert!(acl.check("alice", "get", &["cache:1"]).is_ok());
+        assert!(matches!(
+            acl.check("alice", "get", &["other"]),
+            Err(AclError::KeyDenied(_))
+        ));
+        assert!(matches!(
+            acl.check("alice", "del", &["cache:1"]),
+            Err(AclError::CommandDenied { .. })
+        ));
+
+        // the listed rule can be used to recreate the user
+        let listed = alice.to_string();
+        let modifiers: Vec<&str> = listed.split(' ').skip(2).collect();
+        acl.set_user("bob", &modifiers).unwrap();
+        let bob = acl.get_user("bob").unwrap();
+        assert_eq!(bob.password_hash, alice.password_hash);
+        assert_eq!(bob.commands, alice.commands);
+        assert!(bob
+            .to_string()
+            .ends_with("~cache:* resetchannels -@all +get +set"));
+
+        acl.set_user("alice", &["off"]).unwrap();
+        assert!(acl.check("alice", "get", &["cache:1"]).is_err());
+        assert_eq!(
+            acl.delete_users(&["alice".to_string(), "eve".to_string()])
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_set_user_is_all_or_nothing() {
+        let acl = Acl::new();
+        assert!(matches!(
+            acl.set_user("alice", &["on", "+@admin"]),
+            Err(AclError::InvalidModifier(_))
+        ));
+        assert!(acl.get_user("alice").is_none());
+        acl.set_user("default", &["+@all", "-del"]).unwrap();
+        assert!(acl.check(DEFAULT_USER, "DEL", &[]).is_err());
+        assert!(acl.check(DEFAULT_USER, "GET", &[]).is_ok());
+    }
+}
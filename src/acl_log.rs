@@ -0,0 +1,111 @@
+//! Log of the commands denied by the access control lists, read with ACL LOG.
+
+use crate::error::AclError;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Number of entries kept in the log. The oldest entries are dropped first.
+pub const ACL_LOG_MAX_LEN: usize = 128;
+
+/// AclLogEntry describes a command denied by the ACL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AclLogEntry {
+    pub client_id: u64,
+    pub username: String,
+    /// What was denied: `command`, `key` or `auth`.
+    pub reason: &'static str,
+    pub command: String,
+    pub key: Option<String>,
+    pub channel: Option<String>,
+    pub timestamp: SystemTime,
+}
+
+impl AclLogEntry {
+    pub fn new(client_id: u64, username: &str, command: &str, error: &AclError) -> Self {
+        let (reason, key) = match error {
+            AclError::KeyDenied(key) => ("key", Some(key.clone())),
+            AclError::AuthFailed => ("auth", None),
+            _ => ("command", None),
+        };
+        Self {
+            client_id,
+            username: username.to_string(),
+            reason,
+            command: command.to_lowercase(),
+            key,
+            channel: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+/// AclLog keeps the most recent access violations, newest first.
+#[derive(Debug)]
+pub struct AclLog {
+    entries: Mutex<VecDeque<AclLogEntry>>,
+    max_len: usize,
+}
+
+impl AclLog {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(max_len)),
+            max_len,
+        }
+    }
+
+    pub fn push(&self, entry: AclLogEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.max_len {
+            entries.pop_back();
+        }
+        entries.push_front(entry);
+    }
+
+    /// latest returns up to count entries, newest first.
+    pub fn latest(&self, count: usize) -> Vec<AclLogEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().take(count).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_is_bounded() {
+        let log = AclLog::new(2);
+        for client_id in 1..=3 {
+            log.push(AclLogEntry::new(
+                client_id,
+                "alice",
+                "GET",
+                &AclError::KeyDenied("k".to_string()),
+            ));
+        }
+        let entries = log.latest(10);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].client_id, 3);
+        assert_eq!(entries[1].client_id, 2);
+        assert_eq!(entries[0].reason, "key");
+        assert_eq!(entries[0].key.as_deref(), Some("k"));
+        assert_eq!(log.latest(1).len(), 1);
+
+        log.reset();
+        assert!(log.is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+//! Unix socket for the operators, accepting a restricted set of admin commands.
+//!
+//! The socket is only readable and writable by the user running the server, which stands for the
+//! authentication: the commands run as the default user, without the middlewares of the TCP
+//! connections. Each admin connection gets a thread of its own instead of a worker of the pool,
+//! so the socket keeps answering when the pool is busy with the clients of the main port.
+
+use crate::clients::ClientRegistry;
+use crate::cmd::{self, parse_frame};
+use crate::db::State;
+use crate::error::FrameError;
+use crate::frame::{self, Frame};
+use crate::session::{ServerState, Session};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, ErrorKind};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tracing::debug;
+
+/// Path of the admin socket suggested in the documentation.
+pub const DEFAULT_ADMIN_SOCKET_PATH: &str = "/var/run/htcache/admin.sock";
+
+/// is_admin_command tells whether a command can be run on the admin socket.
+pub fn is_admin_command(cmd_name: &str, frames: &[Frame]) -> bool {
+    let sub_cmd = match frames.get(1) {
+        Some(Frame::Bulk(sub_cmd)) => sub_cmd.to_uppercase(),
+        _ => String::new(),
+    };
+    match cmd_name {
+        "SHUTDOWN" | "INFO" | "BGSAVE" | "BGREWRITEAOF" => true,
+        "CONFIG" => matches!(sub_cmd.as_str(), "SET" | "REWRITE"),
+        "DEBUG" => sub_cmd == "RELOAD",
+        _ => false,
+    }
+}
+
+/// AdminSocket answers the admin commands sent on a Unix socket.
+#[derive(Debug)]
+pub struct AdminSocket {
+    listener: UnixListener,
+    path: PathBuf,
+    databases: Arc<Vec<Arc<State>>>,
+    clients: Arc<ClientRegistry>,
+    server: ServerState,
+}
+
+impl AdminSocket {
+    /// bind creates the socket at `path`, with permission mode 0600. A file left at `path` by a
+    /// server which stopped is replaced, but binding fails if another server still answers on it.
+    pub fn bind(
+        path: impl Into<PathBuf>,
+        databases: Arc<Vec<Arc<State>>>,
+        clients: Arc<ClientRegistry>,
+        server: ServerState,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    ErrorKind::AddrInUse,
+                    "the admin socket is used by another instance",
+                ));
+            }
+            fs::remove_file(&path)?;
+        }
+        let listener = bind_private(&path)?;
+        Ok(Self {
+            listener,
+            path,
+            databases,
+            clients,
+            server,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// spawn accepts the admin connections in a dedicated thread.
+    pub fn spawn(self: Arc<Self>) -> io::Result<JoinHandle<()>> {
+        thread::Builder::new()
+            .name("htcache-admin".to_string())
+            .spawn(move || self.serve())
+    }
+
+    fn serve(&self) {
+        for stream in self.listener.incoming() {
+            let result = stream.and_then(|stream| {
+                let session = Session::new(
+                    self.databases.clone(),
+                    self.clients.register_admin(),
+                    self.server.clone(),
+                );
+                thread::Builder::new()
+                    .name("htcache-admin-client".to_string())
+                    .spawn(move || handle(stream, session))
+            });
+            if let Err(err) = result {
+                debug!(error_message = err.to_string(), "admin connection failed");
+            }
+        }
+    }
+}
+
+/// bind_private binds a Unix socket at `path` which only the user running the server can connect
+/// to. The socket is created in a directory of its own, with permission mode 0700, and only moved
+/// to `path` once its permission mode is 0600: nobody else can connect in between.
+fn bind_private(path: &Path) -> io::Result<UnixListener> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidInput,
+            "the admin socket needs a file name",
+        )
+    })?;
+    let mut dir_name = std::ffi::OsString::from(".");
+    dir_name.push(file_name);
+    dir_name.push(format!(".{}", std::process::id()));
+    let dir = path.with_file_name(dir_name);
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+    let socket = dir.join("admin.sock");
+    let result = UnixListener::bind(&socket).and_then(|listener| {
+        fs::set_permissions(&socket, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&socket, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&socket);
+    fs::remove_dir(&dir)?;
+    result
+}
+
+impl Drop for AdminSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// handle runs the commands of an admin connection until the client closes it.
+fn handle(stream: UnixStream, mut session: Session) {
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(err) => {
+            debug!(error_message = err.to_string(), "admin connection failed");
+            return;
+        }
+    };
+    let mut writer = BufWriter::new(stream);
+    loop {
+        let frame = match frame::decode(&mut reader) {
+            Ok(frame) => frame,
+            Err(FrameError::EOF) => break,
+            Err(err) => {
+                debug!(error_message = err.to_string(), "invalid admin frame");
+                break;
+            }
+        };
+        let response = match parse_frame(frame) {
+            Ok((cmd_name, frames)) if is_admin_command(&cmd_name, &frames) => {
+                cmd::execute(&cmd_name, &frames, &mut session)
+            }
+            Ok(_) => Some(Frame::Error(
+                "ERR command not allowed on admin socket".to_string(),
+            )),
+            Err(err) => Some(Frame::Error(err.to_string())),
+        };
+        // SHUTDOWN replies nothing
+        let Some(response) = response else {
+            continue;
+        };
+        if let Err(err) = response.write_to(&mut writer) {
+            debug!(error_message = err.to_string(), "admin connection closed");
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+    use std::io::{BufRead, Write};
+
+    #[test]
+    fn test_is_admin_command() {
+        let frames = |args: &[&str]| -> Vec<Frame> {
+            args.iter()
+                .map(|arg| Frame::Bulk(arg.to_string()))
+                .collect()
+        };
+        assert!(is_admin_command("INFO", &frames(&["INFO"])));
+        assert!(is_admin_command(
+            "CONFIG",
+            &frames(&["config", "set", "hz", "20"])
+        ));
+        assert!(!is_admin_command(
+            "CONFIG",
+            &frames(&["CONFIG", "GET", "hz"])
+        ));
+        assert!(is_admin_command("DEBUG", &frames(&["DEBUG", "RELOAD"])));
+        assert!(!is_admin_command(
+            "DEBUG",
+            &frames(&["DEBUG", "OBJECT", "key"])
+        ));
+        assert!(!is_admin_command("GET", &frames(&["GET", "key"])));
+    }
+
+    #[test]
+    fn test_admin_socket() {
+        let session = test_session();
+        let path = std::env::temp_dir().join(format!("htcache-admin-{}.sock", std::process::id()));
+        let admin = Arc::new(
+            AdminSocket::bind(
+                &path,
+                session.databases().clone(),
+                session.clients().clone(),
+                session.server().clone(),
+            )
+            .unwrap(),
+        );
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // the directory the socket was bound in is gone
+        let entries = fs::read_dir(std::env::temp_dir()).unwrap();
+        let bound_in = format!(".htcache-admin-{}.sock.", std::process::id());
+        assert!(!entries
+            .map(|entry| entry.unwrap().file_name())
+            .any(|name| name.to_string_lossy().starts_with(&bound_in)));
+        admin.clone().spawn().unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        stream
+            .write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n")
+            .unwrap();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "-ERR command not allowed on admin socket\r\n");
+
+        stream
+            .write_all(b"*4\r\n$6\r\nCONFIG\r\n$3\r\nSET\r\n$2\r\nhz\r\n$2\r\n20\r\n")
+            .unwrap();
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "+OK\r\n");
+        assert_eq!(session.db().config().get("hz")[0].1, "20");
+
+        assert!(AdminSocket::bind(
+            &path,
+            session.databases().clone(),
+            session.clients().clone(),
+            session.server().clone(),
+        )
+        .is_err());
+        // the serving thread keeps the socket alive, so its file is not removed by drop
+        fs::remove_file(&path).unwrap();
+    }
+}
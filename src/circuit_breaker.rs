@@ -0,0 +1,166 @@
+//! A circuit breaker protecting the access to the cache state.
+//! When the storage keeps failing, calls are rejected right away for a while instead of piling up
+//! on a broken backend. Every database has one, and the commands accessing keys go through it.
+
+use crate::error::CircuitBreakerError;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// BREAKER_THRESHOLD is the number of failures in a row which opens the circuit of a database.
+pub const BREAKER_THRESHOLD: u32 = 5;
+/// BREAKER_TIMEOUT is how long the circuit of a database stays open.
+pub const BREAKER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// `CircuitBreaker` counts consecutive failed operations. Once `threshold` failures in a row
+/// happened, the circuit opens and every call fails fast until `timeout` has elapsed. The first
+/// call after that is let through: a success closes the circuit, a failure opens it again.
+pub struct CircuitBreaker {
+    failure_count: AtomicU32,
+    threshold: u32,
+    // Milliseconds elapsed since `created_at` until which the circuit stays open.
+    open_until: AtomicU64,
+    timeout: Duration,
+    created_at: Instant,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, timeout: Duration) -> Self {
+        Self {
+            failure_count: AtomicU32::new(0),
+            threshold,
+            open_until: AtomicU64::new(0),
+            timeout,
+            created_at: Instant::now(),
+        }
+    }
+
+    /// is_open returns true while calls are being rejected.
+    pub fn is_open(&self) -> bool {
+        self.elapsed_millis() < self.open_until.load(Ordering::SeqCst)
+    }
+
+    /// call runs `op` unless the circuit is open. A panic in `op` is caught and counted as a failure.
+    pub fn call<T, F: FnOnce() -> T>(&self, op: F) -> Result<T, CircuitBreakerError> {
+        if self.is_open() {
+            return Err(CircuitBreakerError::Open);
+        }
+        match panic::catch_unwind(AssertUnwindSafe(op)) {
+            Ok(result) => {
+                self.failure_count.store(0, Ordering::SeqCst);
+                Ok(result)
+            }
+            Err(_) => {
+                self.record_failure();
+                Err(CircuitBreakerError::Failed)
+            }
+        }
+    }
+
+    fn record_failure(&self) {
+        let failures = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures >= self.threshold {
+            let open_until = self.elapsed_millis() + self.timeout.as_millis() as u64;
+            self.open_until.store(open_until, Ordering::SeqCst);
+            warn!(failures = failures, "circuit breaker opened");
+        }
+    }
+
+    fn elapsed_millis(&self) -> u64 {
+        self.created_at.elapsed().as_millis() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{self, Command, Get};
+    use crate::error::CommandError;
+    use crate::frame::Frame;
+    use crate::session::Session;
+    use std::thread;
+
+    // Broken stands for a command whose storage fails.
+    struct Broken(String);
+
+    impl Command for Broken {
+        fn apply(&self, _session: &mut Session) -> Frame {
+            panic!("storage failure")
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            vec![&self.0]
+        }
+
+        fn from(_frames: &[Frame]) -> Result<Self, CommandError> {
+            Ok(Broken("key".to_string()))
+        }
+    }
+
+    fn fail(breaker: &CircuitBreaker) -> Result<(), CircuitBreakerError> {
+        breaker.call(|| panic!("storage failure"))
+    }
+
+    #[test]
+    fn test_circuit_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        for _ in 0..2 {
+            assert!(matches!(fail(&breaker), Err(CircuitBreakerError::Failed)));
+        }
+        assert!(!breaker.is_open());
+        assert!(matches!(fail(&breaker), Err(CircuitBreakerError::Failed)));
+        assert!(breaker.is_open());
+        // fast-fail without running the operation
+        let result = breaker.call(|| panic!("must not run while the circuit is open"));
+        assert!(matches!(result, Err(CircuitBreakerError::Open)));
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        assert!(fail(&breaker).is_err());
+        assert_eq!(breaker.call(|| 1).unwrap(), 1);
+        assert!(fail(&breaker).is_err());
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_circuit_closes_after_timeout() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        assert!(fail(&breaker).is_err());
+        assert!(breaker.is_open());
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.call(|| 1).unwrap(), 1);
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_commands_trip_the_database_breaker() {
+        let mut session = cmd::test_session();
+        let get = <Get as Command>::from(&[
+            Frame::Bulk("GET".to_string()),
+            Frame::Bulk("key".to_string()),
+        ])
+        .unwrap();
+        let broken = Broken("key".to_string());
+        for _ in 0..BREAKER_THRESHOLD {
+            assert_eq!(
+                cmd::apply("BROKEN", &broken, &mut session),
+                Some(Frame::Error("ERR storage operation failed".to_string()))
+            );
+        }
+        assert!(session.db().circuit_breaker().is_open());
+        assert_eq!(
+            cmd::apply("GET", &get, &mut session),
+            Some(Frame::Error(
+                "ERR circuit breaker is open, operation rejected".to_string()
+            ))
+        );
+
+        // the circuit closes once the storage works again
+        thread::sleep(BREAKER_TIMEOUT + Duration::from_millis(50));
+        assert_eq!(cmd::apply("GET", &get, &mut session), Some(Frame::Null));
+        assert!(!session.db().circuit_breaker().is_open());
+    }
+}
@@ -0,0 +1,149 @@
+//! Book-keeping of the clients connected to the server.
+
+use crate::config::ServerConfig;
+use crate::info::InfoCollector;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// ClientRegistry counts the connected clients and keeps statistics about their connections.
+/// It is shared by the server, which registers new clients, and by the connections.
+#[derive(Debug)]
+pub struct ClientRegistry {
+    config: Arc<ServerConfig>,
+    connected: AtomicUsize,
+    total_connections: AtomicU64,
+    // Largest amount of bytes seen waiting in a connection read buffer once a command was read.
+    max_input_buffer: AtomicUsize,
+}
+
+impl ClientRegistry {
+    pub fn new(config: Arc<ServerConfig>) -> Self {
+        Self {
+            config,
+            connected: AtomicUsize::new(0),
+            total_connections: AtomicU64::new(0),
+            max_input_buffer: AtomicUsize::new(0),
+        }
+    }
+
+    /// register counts a new client. It returns None when the configured maximum number of clients
+    /// are already connected. The client is unregistered when the returned registration is dropped.
+    pub fn register(self: &Arc<Self>) -> Option<ClientRegistration> {
+        let max_clients = self.config.max_clients();
+        self.connected
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |connected| {
+                (connected < max_clients).then_some(connected + 1)
+            })
+            .ok()?;
+        // ids start at 1, as in Redis
+        let id = self.total_connections.fetch_add(1, Ordering::SeqCst) + 1;
+        Some(ClientRegistration {
+            id,
+            registry: self.clone(),
+        })
+    }
+
+    /// register_admin counts a new client of the admin socket. Admin clients are not limited by
+    /// maxclients, so operators can connect to a server which refuses new clients.
+    pub fn register_admin(self: &Arc<Self>) -> ClientRegistration {
+        self.connected.fetch_add(1, Ordering::SeqCst);
+        let id = self.total_connections.fetch_add(1, Ordering::SeqCst) + 1;
+        ClientRegistration {
+            id,
+            registry: self.clone(),
+        }
+    }
+
+    /// connected returns the number of clients currently connected.
+    pub fn connected(&self) -> usize {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    /// record_input_buffer keeps track of the largest read buffer seen on a connection.
+    pub fn record_input_buffer(&self, size: usize) {
+        self.max_input_buffer.fetch_max(size, Ordering::SeqCst);
+    }
+}
+
+impl InfoCollector for ClientRegistry {
+    fn section(&self) -> &'static str {
+        "clients"
+    }
+
+    fn collect(&self) -> Vec<(Cow<'static, str>, String)> {
+        let fields = [
+            ("connected_clients", self.connected().to_string()),
+            // there is no cluster bus
+            ("cluster_connections", "0".to_string()),
+            ("maxclients", self.config.max_clients().to_string()),
+            (
+                "client_recent_max_input_buffer",
+                self.max_input_buffer.load(Ordering::SeqCst).to_string(),
+            ),
+            // responses are flushed as soon as they are written, so no output is ever kept buffered
+            ("client_recent_max_output_buffer", "0".to_string()),
+            // blocking commands, client side caching and client timeouts are not supported
+            ("blocked_clients", "0".to_string()),
+            ("tracking_clients", "0".to_string()),
+            ("clients_in_timeout_table", "0".to_string()),
+            (
+                "total_connections_received",
+                self.total_connections.load(Ordering::SeqCst).to_string(),
+            ),
+        ];
+        fields
+            .into_iter()
+            .map(|(field, value)| (field.into(), value))
+            .collect()
+    }
+}
+
+/// ClientRegistration stands for a connected client. Dropping it unregisters the client.
+#[derive(Debug)]
+pub struct ClientRegistration {
+    id: u64,
+    registry: Arc<ClientRegistry>,
+}
+
+impl ClientRegistration {
+    /// id returns the unique id of the client.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn registry(&self) -> &Arc<ClientRegistry> {
+        &self.registry
+    }
+}
+
+impl Drop for ClientRegistration {
+    fn drop(&mut self) {
+        self.registry.connected.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_respects_max_clients() {
+        let config = Arc::new(ServerConfig::new(80).unwrap());
+        config.set(&[(crate::config::MAX_CLIENTS, "2")]).unwrap();
+        let registry = Arc::new(ClientRegistry::new(config));
+        let first = registry.register().unwrap();
+        let first_id = first.id();
+        let second = registry.register().unwrap();
+        assert!(registry.register().is_none());
+        assert_eq!(registry.connected(), 2);
+
+        drop(first);
+        assert_eq!(registry.connected(), 1);
+        let third = registry.register().unwrap();
+        assert_eq!((first_id, third.id()), (1, 3));
+        drop(second);
+        assert_eq!(registry.connected(), 1);
+        assert_eq!(registry.total_connections.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,128 @@
+//! Cluster routing helpers. htcache runs in standalone mode, where a single node owns all the
+//! slots, but clients need the slots of keys to route commands the way a cluster would.
+
+use sha1::{Digest, Sha1};
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of hash slots of a cluster.
+pub const SLOT_COUNT: u16 = 16384;
+
+// Redis cluster nodes talk to each other on their client port plus this offset.
+const CLUSTER_BUS_PORT_OFFSET: u16 = 10000;
+
+/// Node describes the local node of the cluster, which owns all the slots in standalone mode.
+#[derive(Debug)]
+pub struct Node {
+    id: String,
+    addr: SocketAddr,
+}
+
+impl Node {
+    /// new describes the node listening on `addr`. Its id is the SHA1 of its address, so it stays
+    /// the same across restarts.
+    pub fn new(addr: SocketAddr) -> Self {
+        let digest = Sha1::digest(format!("{}:{}", addr.ip(), addr.port()).as_bytes());
+        let mut id = String::with_capacity(40);
+        for byte in digest {
+            // writing to a String cannot fail
+            let _ = write!(id, "{:02x}", byte);
+        }
+        Self { id, addr }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// nodes_line describes the node the way CLUSTER NODES does.
+    pub fn nodes_line(&self) -> String {
+        // the last pong received is reported as now, the node always answers itself
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        format!(
+            "{} {}:{}@{} myself,master - 0 {} 0 connected 0-{}\n",
+            self.id,
+            self.addr.ip(),
+            self.addr.port(),
+            self.addr.port().saturating_add(CLUSTER_BUS_PORT_OFFSET),
+            now,
+            SLOT_COUNT - 1
+        )
+    }
+}
+
+/// crc16_xmodem computes the CRC16 variant used by Redis cluster (XMODEM: polynomial 0x1021,
+/// initial value 0).
+pub fn crc16_xmodem(key: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in key {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// key_slot returns the slot of a key, between 0 and 16383.
+/// When the key holds a non-empty hash tag, like `{user}` in `{user}.name`, only the tag is
+/// hashed, so that related keys land in the same slot.
+pub fn key_slot(key: &str) -> u16 {
+    crc16_xmodem(hash_tag(key).as_bytes()) % SLOT_COUNT
+}
+
+/// hash_tag returns the part of the key which is hashed: the content of the first `{...}` if it
+/// is not empty, the whole key otherwise.
+fn hash_tag(key: &str) -> &str {
+    if let Some(start) = key.find('{') {
+        if let Some(len) = key[start + 1..].find('}') {
+            if len > 0 {
+                return &key[start + 1..start + 1 + len];
+            }
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_slot() {
+        assert_eq!(crc16_xmodem(b"123456789"), 0x31C3);
+        assert_eq!(key_slot("foo"), 12182);
+        assert_eq!(key_slot("bar"), 5061);
+        assert_eq!(key_slot("{user1000}.following"), key_slot("user1000"));
+        assert_eq!(key_slot("{user1000}.followers"), key_slot("user1000"));
+        // empty tags and unclosed braces do not count as hash tags
+        assert_eq!(key_slot("{}foo"), crc16_xmodem(b"{}foo") % SLOT_COUNT);
+        assert_eq!(key_slot("{foo"), crc16_xmodem(b"{foo") % SLOT_COUNT);
+        // only the first tag counts
+        assert_eq!(key_slot("{a}{b}"), key_slot("a"));
+    }
+
+    #[test]
+    fn test_node() {
+        let node = Node::new("127.0.0.1:6379".parse().unwrap());
+        assert_eq!(node.id(), "11eceb7f355c699a367c5f3e38ec19fca7318355");
+        assert_eq!(node.id(), Node::new("127.0.0.1:6379".parse().unwrap()).id());
+        assert_ne!(node.id(), Node::new("127.0.0.1:6380".parse().unwrap()).id());
+
+        let line = node.nodes_line();
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+        assert_eq!(fields[0], node.id());
+        assert_eq!(fields[1], "127.0.0.1:6379@16379");
+        assert_eq!(fields[2], "myself,master");
+        assert_eq!(fields[7..], ["connected", "0-16383"]);
+        assert!(line.ends_with('\n'));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::acl::AclRule;
+use crate::acl_log::AclLogEntry;
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Number of entries returned by ACL LOG when no count is given, as in Redis.
+const DEFAULT_LOG_COUNT: usize = 10;
+
+enum AclSubCmd {
+    SetUser(String, Vec<String>),
+    GetUser(String),
+    DelUser(Vec<String>),
+    List,
+    Log(LogArg),
+    WhoAmI,
+}
+
+enum LogArg {
+    Latest(usize),
+    Count,
+    Reset,
+}
+
+/// Acl implements the ACL command, which manages the users and their permissions.
+pub struct Acl {
+    sub_cmd: AclSubCmd,
+}
+
+impl Command for Acl {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let acl = session.acl();
+        match &self.sub_cmd {
+            AclSubCmd::SetUser(username, modifiers) => {
+                let modifiers: Vec<&str> = modifiers.iter().map(String::as_str).collect();
+                match acl.set_user(username, &modifiers) {
+                    Ok(()) => Frame::Simple("OK".to_string()),
+                    Err(err) => Frame::Error(format!("ERR {}", err)),
+                }
+            }
+            AclSubCmd::GetUser(username) => match acl.get_user(username) {
+                Some(rule) => user_frame(&rule),
+                None => Frame::Null,
+            },
+            AclSubCmd::DelUser(usernames) => match acl.delete_users(usernames) {
+                Ok(deleted) => Frame::Integer(deleted as i64),
+                Err(err) => Frame::Error(format!("ERR {}", err)),
+            },
+            AclSubCmd::List => Frame::Array(
+                acl.list()
+                    .iter()
+                    .map(|rule| Frame::Bulk(rule.to_string()))
+                    .collect(),
+            ),
+            AclSubCmd::Log(LogArg::Latest(count)) => Frame::Array(
+                acl.log()
+                    .latest(*count)
+                    .iter()
+                    .map(log_entry_frame)
+                    .collect(),
+            ),
+            AclSubCmd::WhoAmI => Frame::Bulk(session.user().to_string()),
+            AclSubCmd::Log(LogArg::Count) => Frame::Integer(acl.log().len() as i64),
+            AclSubCmd::Log(LogArg::Reset) => {
+                acl.log().reset();
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(frames.len());
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.clone()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        if args.is_empty() {
+            return Err(CommandError::Malformed(
+                "ACL command requires a sub-command".to_string(),
+            ));
+        }
+        let sub_cmd_name = args.remove(0).to_uppercase();
+        let sub_cmd = match (sub_cmd_name.as_str(), args.len()) {
+            ("SETUSER", 1..) => {
+                let username = args.remove(0);
+                AclSubCmd::SetUser(username, args)
+            }
+            ("GETUSER", 1) => AclSubCmd::GetUser(args.remove(0)),
+            ("DELUSER", 1..) => AclSubCmd::DelUser(args),
+            ("LIST", 0) => AclSubCmd::List,
+            ("LOG", 0) => AclSubCmd::Log(LogArg::Latest(DEFAULT_LOG_COUNT)),
+            ("LOG", 1) => {
+                let arg = args.remove(0);
+                match arg.to_uppercase().as_str() {
+                    "RESET" => AclSubCmd::Log(LogArg::Reset),
+                    "COUNT" => AclSubCmd::Log(LogArg::Count),
+                    _ => match arg.parse() {
+                        Ok(count) => AclSubCmd::Log(LogArg::Latest(count)),
+                        Err(_) => {
+                            return Err(CommandError::Malformed(
+                                "ACL LOG count must be a positive integer, RESET or COUNT"
+                                    .to_string(),
+                            ))
+                        }
+                    },
+                }
+            }
+            ("WHOAMI", 0) => AclSubCmd::WhoAmI,
+            ("SETUSER" | "GETUSER" | "DELUSER" | "LIST" | "LOG" | "WHOAMI", _) => {
+                return Err(CommandError::Malformed(format!(
+                    "ACL {} wrong number of arguments",
+                    sub_cmd_name
+                )))
+            }
+            _ => return Err(CommandError::Unknown(format!("ACL {}", sub_cmd_name))),
+        };
+        Ok(Acl { sub_cmd })
+    }
+}
+
+/// user_frame describes a user the way ACL GETUSER does in Redis.
+fn user_frame(rule: &AclRule) -> Frame {
+    let bulks = |values: Vec<String>| Frame::Array(values.into_iter().map(Frame::Bulk).collect());
+    let mut flags = vec![if rule.enabled { "on" } else { "off" }.to_string()];
+    if rule.nopass {
+        flags.push("nopass".to_string());
+    }
+    let passwords = rule.password_hex().into_iter().collect();
+    let patterns = |prefix: &str, patterns: &[String]| {
+        patterns
+            .iter()
+            .map(|pattern| format!("{}{}", prefix, pattern))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    Frame::Array(vec![
+        Frame::Bulk("flags".to_string()),
+        bulks(flags),
+        Frame::Bulk("passwords".to_string()),
+        bulks(passwords),
+        Frame::Bulk("commands".to_string()),
+        Frame::Bulk(rule.commands.to_string()),
+        Frame::Bulk("keys".to_string()),
+        Frame::Bulk(patterns("~", &rule.key_patterns)),
+        Frame::Bulk("channels".to_string()),
+        Frame::Bulk(patterns("&", &rule.channel_patterns)),
+    ])
+}
+
+/// log_entry_frame describes an access violation with the field names Redis uses in ACL LOG.
+fn log_entry_frame(entry: &AclLogEntry) -> Frame {
+    let now = SystemTime::now();
+    let age = now.duration_since(entry.timestamp).unwrap_or_default();
+    let created = entry
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let object = entry
+        .key
+        .as_ref()
+        .or(entry.channel.as_ref())
+        .unwrap_or(&entry.command);
+    let fields = [
+        ("reason", Frame::Bulk(entry.reason.to_string())),
+        ("context", Frame::Bulk("toplevel".to_string())),
+        ("object", Frame::Bulk(object.clone())),
+        ("username", Frame::Bulk(entry.username.clone())),
+        (
+            "age-seconds",
+            Frame::Bulk(format!("{:.3}", age.as_secs_f64())),
+        ),
+        (
+            "client-info",
+            Frame::Bulk(format!("id={} cmd={}", entry.client_id, entry.command)),
+        ),
+        (
+            "timestamp-created",
+            Frame::Integer(created.as_millis() as i64),
+        ),
+    ];
+    Frame::Map(
+        fields
+            .into_iter()
+            .map(|(name, value)| (Frame::Bulk(name.to_string()), value))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_acl_users() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Acl>(
+                &mut session,
+                &["ACL", "SETUSER", "alice", "on", ">pass", "~k*", "+get"]
+            ),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "LIST"]),
+            Frame::Array(vec![
+                Frame::Bulk(format!(
+                    "user alice on #{} ~k* resetchannels -@all +get",
+                    "d74ff0ee8da3b9806b18c877dbf29bbde50b5bd8e4dad7a3a725000feb82e8f1"
+                )),
+                Frame::Bulk("user default on nopass ~* &* +@all".to_string()),
+            ])
+        );
+        match run_command::<Acl>(&mut session, &["ACL", "GETUSER", "alice"]) {
+            Frame::Array(fields) => {
+                assert_eq!(fields[4], Frame::Bulk("commands".to_string()));
+                assert_eq!(fields[5], Frame::Bulk("-@all +get".to_string()));
+                assert_eq!(fields[7], Frame::Bulk("~k*".to_string()));
+            }
+            frame => panic!("unexpected response {:?}", frame),
+        }
+        assert!(session.acl().check("alice", "GET", &["key"]).is_ok());
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "DELUSER", "alice", "bob"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "GETUSER", "alice"]),
+            Frame::Null
+        );
+    }
+
+    #[test]
+    fn test_acl_log() {
+        let mut session = test_session();
+        run_command::<Acl>(
+            &mut session,
+            &["ACL", "SETUSER", "default", "resetkeys", "~cache:*"],
+        );
+        assert!(session.check_permission("GET", &["secret"]).is_err());
+        assert!(session.check_permission("GET", &["cache:1"]).is_ok());
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "LOG", "COUNT"]),
+            Frame::Integer(1)
+        );
+
+        match run_command::<Acl>(&mut session, &["ACL", "LOG"]) {
+            Frame::Array(entries) => match &entries[..] {
+                [Frame::Map(fields)] => {
+                    let field = |name: &str| fields[&Frame::Bulk(name.to_string())].clone();
+                    assert_eq!(field("reason"), Frame::Bulk("key".to_string()));
+                    assert_eq!(field("object"), Frame::Bulk("secret".to_string()));
+                    assert_eq!(field("username"), Frame::Bulk("default".to_string()));
+                }
+                entries => panic!("unexpected entries {:?}", entries),
+            },
+            frame => panic!("unexpected response {:?}", frame),
+        }
+
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "LOG", "RESET"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "LOG", "0"]),
+            Frame::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_acl_errors() {
+        let mut session = test_session();
+        let reply = run_command::<Acl>(&mut session, &["ACL", "SETUSER", "alice", "bogus"]);
+        assert!(matches!(reply, Frame::Error(e) if e.starts_with("ERR Error in ACL SETUSER")));
+        let reply = run_command::<Acl>(&mut session, &["ACL", "DELUSER", "default"]);
+        assert!(matches!(reply, Frame::Error(_)));
+        assert!(<Acl as Command>::from(&[
+            Frame::Bulk("ACL".to_string()),
+            Frame::Bulk("GETUSER".to_string()),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Append implements `APPEND key value`, which adds `value` at the end of the value of a key and
+/// returns the length of the new value. A missing key is set to `value`.
+pub struct Append {
+    key: String,
+    value: String,
+}
+
+impl Command for Append {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let db = session.db();
+        if self.key.len() > db.config().max_key_bytes() {
+            return Frame::Error("ERR key too long".to_string());
+        }
+        // the length of the new value is checked against max-value-bytes by the storage
+        match db.append_value(&self.key, &self.value) {
+            Ok(len) => Frame::Integer(len as i64),
+            Err(CommandError::Malformed(message)) => Frame::Error(format!("ERR {}", message)),
+            Err(err) => Frame::Error(err.to_string()),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(value)] => Ok(Append {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "APPEND command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_append() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "key", "Hello"]),
+            Frame::Integer(5)
+        );
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "key", " World"]),
+            Frame::Integer(11)
+        );
+        assert_eq!(
+            session.db().peek_value("key"),
+            Some("Hello World".to_string())
+        );
+    }
+
+    #[test]
+    fn test_append_size_limit() {
+        let mut session = test_session();
+        session
+            .db()
+            .config()
+            .set(&[("max-value-bytes", "8")])
+            .unwrap();
+        for len in [3, 6] {
+            assert_eq!(
+                run_command::<Append>(&mut session, &["APPEND", "key", "abc"]),
+                Frame::Integer(len)
+            );
+        }
+        // each suffix is small, but the value would get past the limit
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "key", "abc"]),
+            Frame::Error("ERR value too large".to_string())
+        );
+        assert_eq!(session.db().peek_value("key"), Some("abcabc".to_string()));
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "key", "ab"]),
+            Frame::Integer(8)
+        );
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "other", "too large"]),
+            Frame::Error("ERR value too large".to_string())
+        );
+        assert_eq!(session.db().peek_value("other"), None);
+    }
+
+    #[test]
+    fn test_append_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "Hello");
+        assert_eq!(
+            run_command::<Append>(&mut session, &["APPEND", "key", "World"]),
+            Frame::Integer(5)
+        );
+        assert_eq!(session.db().key_expiration("key"), None);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::acl::DEFAULT_USER;
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Auth implements `AUTH [username] password`. Without username, the default user is used.
+pub struct Auth {
+    username: String,
+    password: String,
+}
+
+impl Command for Auth {
+    fn apply(&self, session: &mut Session) -> Frame {
+        match session.authenticate(&self.username, &self.password) {
+            Ok(()) => Frame::Simple("OK".to_string()),
+            Err(err) => Frame::Error(format!("WRONGPASS {}", err)),
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(2);
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.clone()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        let (username, password) = match args.len() {
+            1 => (DEFAULT_USER.to_string(), args.remove(0)),
+            2 => (args.remove(0), args.remove(0)),
+            _ => {
+                return Err(CommandError::Malformed(
+                    "AUTH command requires a password and an optional username".to_string(),
+                ))
+            }
+        };
+        Ok(Auth { username, password })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session, Acl};
+
+    #[test]
+    fn test_auth() {
+        let mut session = test_session();
+        run_command::<Acl>(
+            &mut session,
+            &["ACL", "SETUSER", "alice", "on", ">secret", "+get"],
+        );
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "WHOAMI"]),
+            Frame::Bulk("default".to_string())
+        );
+
+        let reply = run_command::<Auth>(&mut session, &["AUTH", "alice", "guess"]);
+        assert!(matches!(reply, Frame::Error(e) if e.starts_with("WRONGPASS")));
+        assert_eq!(session.user(), "default");
+        assert_eq!(session.acl().log().latest(1)[0].reason, "auth");
+
+        assert_eq!(
+            run_command::<Auth>(&mut session, &["AUTH", "alice", "secret"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Acl>(&mut session, &["ACL", "WHOAMI"]),
+            Frame::Bulk("alice".to_string())
+        );
+        assert!(session.check_permission("GET", &[]).is_ok());
+        assert!(session.check_permission("SET", &[]).is_err());
+        // AUTH is always allowed, so the client can switch back
+        assert!(session.check_permission("AUTH", &[]).is_ok());
+        assert_eq!(
+            run_command::<Auth>(&mut session, &["AUTH", "anything"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(session.user(), "default");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::cluster::{key_slot, SLOT_COUNT};
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::collections::BTreeMap;
+
+enum ClusterSubCmd {
+    Info,
+    KeySlot(String),
+    Nodes,
+}
+
+/// Cluster implements the CLUSTER command. htcache runs in standalone mode, so it describes a
+/// cluster made of the local node, which owns all the slots.
+pub struct Cluster {
+    sub_cmd: ClusterSubCmd,
+}
+
+impl Command for Cluster {
+    fn apply(&self, session: &mut Session) -> Frame {
+        match &self.sub_cmd {
+            ClusterSubCmd::Info => info_frame(),
+            ClusterSubCmd::KeySlot(key) => Frame::Integer(key_slot(key) as i64),
+            ClusterSubCmd::Nodes => Frame::Bulk(session.node().nodes_line()),
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(frames.len());
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.as_str()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        let Some(sub_cmd_name) = args.first().map(|name| name.to_uppercase()) else {
+            return Err(CommandError::Malformed(
+                "CLUSTER command requires a sub-command".to_string(),
+            ));
+        };
+        let sub_cmd = match (sub_cmd_name.as_str(), &args[1..]) {
+            ("INFO", []) => ClusterSubCmd::Info,
+            ("KEYSLOT", [key]) => ClusterSubCmd::KeySlot(key.to_string()),
+            ("NODES", []) => ClusterSubCmd::Nodes,
+            ("INFO" | "KEYSLOT" | "NODES", _) => {
+                return Err(CommandError::Malformed(format!(
+                    "wrong number of arguments for CLUSTER {}",
+                    sub_cmd_name
+                )))
+            }
+            _ => return Err(CommandError::Unknown(format!("CLUSTER {}", sub_cmd_name))),
+        };
+        Ok(Cluster { sub_cmd })
+    }
+}
+
+/// info_frame describes the standalone node as a healthy cluster of one node owning all the slots.
+fn info_frame() -> Frame {
+    let slots = Frame::Integer(SLOT_COUNT as i64);
+    let fields = [
+        ("cluster_enabled", Frame::Integer(0)),
+        ("cluster_state", Frame::Bulk("ok".to_string())),
+        ("cluster_slots_assigned", slots.clone()),
+        ("cluster_slots_ok", slots),
+        ("cluster_slots_pfail", Frame::Integer(0)),
+        ("cluster_slots_fail", Frame::Integer(0)),
+        ("cluster_known_nodes", Frame::Integer(1)),
+        ("cluster_size", Frame::Integer(1)),
+        ("cluster_current_epoch", Frame::Integer(0)),
+        ("cluster_my_epoch", Frame::Integer(0)),
+        ("cluster_stats_messages_sent", Frame::Integer(0)),
+        ("cluster_stats_messages_received", Frame::Integer(0)),
+    ];
+    Frame::Map(
+        fields
+            .into_iter()
+            .map(|(name, value)| (Frame::Bulk(name.to_string()), value))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_cluster() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Cluster>(&mut session, &["CLUSTER", "KEYSLOT", "foo"]),
+            Frame::Integer(12182)
+        );
+        match run_command::<Cluster>(&mut session, &["cluster", "info"]) {
+            Frame::Map(fields) => {
+                assert_eq!(
+                    fields[&Frame::Bulk("cluster_state".to_string())],
+                    Frame::Bulk("ok".to_string())
+                );
+                assert_eq!(
+                    fields[&Frame::Bulk("cluster_slots_assigned".to_string())],
+                    Frame::Integer(16384)
+                );
+            }
+            frame => panic!("unexpected response {:?}", frame),
+        }
+        // the line ends with a LF, which the frame decoder stops at, so the raw reply is checked
+        let nodes = <Cluster as Command>::from(&[
+            Frame::Bulk("CLUSTER".to_string()),
+            Frame::Bulk("NODES".to_string()),
+        ])
+        .unwrap();
+        let reply = String::from_utf8(nodes.apply(&mut session).encode()).unwrap();
+        assert!(reply.contains(&format!("\r\n{} 127.0.0.1:6379@16379", session.node().id())));
+        assert!(reply.ends_with("0-16383\n\r\n"));
+        assert!(<Cluster as Command>::from(&[
+            Frame::Bulk("CLUSTER".to_string()),
+            Frame::Bulk("KEYSLOT".to_string()),
+        ])
+        .is_err());
+    }
+}
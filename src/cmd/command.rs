@@ -0,0 +1,400 @@
+use crate::cmd::docs::{ArgSpec, CommandDoc, COMMAND_DOCS};
+use crate::cmd::{self, parse_frame, Command};
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::glob;
+use crate::session::Session;
+use std::collections::BTreeMap;
+
+enum CommandSubCmd {
+    Count,
+    // names of the commands to describe, in lower case, all the commands when empty
+    Docs(Vec<String>),
+    GetKeys(Vec<Frame>),
+    List(Option<ListFilter>),
+}
+
+enum ListFilter {
+    // the module name is not kept: there is no module system, so no command belongs to a module
+    Module,
+    AclCategory(String),
+    Pattern(String),
+}
+
+impl ListFilter {
+    fn matches(&self, doc: &CommandDoc) -> bool {
+        match self {
+            ListFilter::Module => false,
+            ListFilter::AclCategory(category) => doc.acl_categories.contains(&category.as_str()),
+            ListFilter::Pattern(pattern) => glob::matches(pattern, doc.name),
+        }
+    }
+}
+
+/// CommandCmd implements the COMMAND command, which describes the commands supported by the server.
+pub struct CommandCmd {
+    sub_cmd: CommandSubCmd,
+}
+
+impl Command for CommandCmd {
+    fn apply(&self, _: &mut Session) -> Frame {
+        match &self.sub_cmd {
+            CommandSubCmd::Count => Frame::Integer(COMMAND_DOCS.len() as i64),
+            CommandSubCmd::Docs(names) => docs_frame(names),
+            CommandSubCmd::GetKeys(frames) => get_keys_frame(frames.clone()),
+            CommandSubCmd::List(filter) => Frame::Array(
+                COMMAND_DOCS
+                    .iter()
+                    .filter(|doc| filter.as_ref().is_none_or(|filter| filter.matches(doc)))
+                    .map(|doc| Frame::Bulk(doc.name.to_string()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let sub_cmd_name = match frames.get(1) {
+            Some(Frame::Bulk(name)) => name.to_uppercase(),
+            Some(_) => return Err(CommandError::InvalidCmdFrame),
+            None => {
+                return Err(CommandError::Malformed(
+                    "COMMAND command requires a sub-command".to_string(),
+                ))
+            }
+        };
+        let sub_cmd = match sub_cmd_name.as_str() {
+            "COUNT" => {
+                if frames.len() != 2 {
+                    return Err(CommandError::Malformed(
+                        "COMMAND COUNT takes no argument".to_string(),
+                    ));
+                }
+                CommandSubCmd::Count
+            }
+            "DOCS" => {
+                let mut names = Vec::with_capacity(frames.len() - 2);
+                for frame in &frames[2..] {
+                    match frame {
+                        Frame::Bulk(name) => names.push(name.to_lowercase()),
+                        _ => return Err(CommandError::InvalidCmdFrame),
+                    }
+                }
+                CommandSubCmd::Docs(names)
+            }
+            "GETKEYS" => {
+                if frames.len() < 3 {
+                    return Err(CommandError::Malformed(
+                        "COMMAND GETKEYS requires a command".to_string(),
+                    ));
+                }
+                CommandSubCmd::GetKeys(frames[2..].to_vec())
+            }
+            "LIST" => CommandSubCmd::List(parse_list_filter(&frames[2..])?),
+            _ => return Err(CommandError::Unknown(format!("COMMAND {}", sub_cmd_name))),
+        };
+        Ok(CommandCmd { sub_cmd })
+    }
+}
+
+/// parse_list_filter reads the optional `FILTERBY MODULE|ACLCAT|PATTERN value` of COMMAND LIST.
+fn parse_list_filter(args: &[Frame]) -> Result<Option<ListFilter>, CommandError> {
+    let malformed = || {
+        CommandError::Malformed(
+            "COMMAND LIST accepts FILTERBY MODULE, ACLCAT or PATTERN with a value".to_string(),
+        )
+    };
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            Frame::Bulk(value) => values.push(value.as_str()),
+            _ => return Err(CommandError::InvalidCmdFrame),
+        }
+    }
+    match values[..] {
+        [] => Ok(None),
+        [filterby, kind, value] if filterby.eq_ignore_ascii_case("FILTERBY") => {
+            let value = value.to_lowercase();
+            match kind.to_uppercase().as_str() {
+                "MODULE" => Ok(Some(ListFilter::Module)),
+                "ACLCAT" => Ok(Some(ListFilter::AclCategory(value))),
+                "PATTERN" => Ok(Some(ListFilter::Pattern(value))),
+                _ => Err(malformed()),
+            }
+        }
+        _ => Err(malformed()),
+    }
+}
+
+/// docs_frame answers COMMAND DOCS: a map from the name of each requested command to its
+/// documentation. Unknown commands are left out.
+fn docs_frame(names: &[String]) -> Frame {
+    Frame::Map(
+        COMMAND_DOCS
+            .iter()
+            .filter(|doc| names.is_empty() || names.iter().any(|name| name == doc.name))
+            .map(|doc| {
+                let mut fields = BTreeMap::new();
+                if !doc.args.is_empty() {
+                    fields.insert(Frame::Bulk("arguments".to_string()), args_frame(doc.args));
+                }
+                if let Some(version) = doc.deprecated_since_version {
+                    fields.insert(
+                        Frame::Bulk("deprecated_since".to_string()),
+                        Frame::Bulk(version.to_string()),
+                    );
+                }
+                (Frame::Bulk(doc.name.to_string()), Frame::Map(fields))
+            })
+            .collect(),
+    )
+}
+
+/// args_frame describes a list of arguments, nesting the arguments of oneof and block.
+fn args_frame(args: &[ArgSpec]) -> Frame {
+    let describe = |arg: &ArgSpec| {
+        let mut fields = vec![
+            ("name", Frame::Bulk(arg.name.to_string())),
+            ("type", Frame::Bulk(arg.type_.name().to_string())),
+        ];
+        if let Some(token) = arg.token {
+            fields.push(("token", Frame::Bulk(token.to_string())));
+        }
+        let flags: Vec<Frame> = [
+            ("optional", arg.optional),
+            ("multiple", arg.flags.multiple),
+            ("multiple_token", arg.flags.multiple_token),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(flag, _)| Frame::Bulk(flag.to_string()))
+        .collect();
+        if !flags.is_empty() {
+            fields.push(("flags", Frame::Array(flags)));
+        }
+        let nested = arg.type_.nested();
+        if !nested.is_empty() {
+            fields.push(("arguments", args_frame(nested)));
+        }
+        Frame::Map(
+            fields
+                .into_iter()
+                .map(|(name, value)| (Frame::Bulk(name.to_string()), value))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    };
+    Frame::Array(args.iter().map(describe).collect())
+}
+
+/// get_keys_frame answers COMMAND GETKEYS: it parses the given command and returns its keys.
+fn get_keys_frame(frames: Vec<Frame>) -> Frame {
+    let keys = parse_frame(Frame::Array(frames))
+        .map_err(|_| "ERR Invalid command specified".to_string())
+        .and_then(|(cmd_name, frames)| {
+            command_keys(&cmd_name, &frames)
+                .ok_or("ERR Invalid command specified".to_string())?
+                .map_err(|_| "ERR Invalid arguments specified for command".to_string())
+        });
+    match keys {
+        Ok(keys) if keys.is_empty() => {
+            Frame::Error("ERR The command has no key arguments".to_string())
+        }
+        Ok(keys) => Frame::Array(keys.into_iter().map(Frame::Bulk).collect()),
+        Err(message) => Frame::Error(message),
+    }
+}
+
+/// command_keys builds a command from its frames and returns the keys it accesses.
+/// It returns None when the command is unknown.
+fn command_keys(cmd_name: &str, frames: &[Frame]) -> Option<Result<Vec<String>, CommandError>> {
+    match cmd::parse(cmd_name, frames) {
+        Ok(command) => Some(Ok(command.keys().into_iter().map(str::to_string).collect())),
+        // unknown sub-commands are reported with their command name, they are invalid arguments
+        Err(CommandError::Unknown(name)) if name == cmd_name => None,
+        Err(err) => Some(Err(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    fn bulks(values: &[&str]) -> Frame {
+        Frame::Array(values.iter().map(|v| Frame::Bulk(v.to_string())).collect())
+    }
+
+    #[test]
+    fn test_command_getkeys() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<CommandCmd>(&mut session, &["COMMAND", "GETKEYS", "SET", "foo", "bar"]),
+            bulks(&["foo"])
+        );
+        assert_eq!(
+            run_command::<CommandCmd>(&mut session, &["COMMAND", "GETKEYS", "del", "a", "b"]),
+            bulks(&["a", "b"])
+        );
+        assert_eq!(
+            run_command::<CommandCmd>(
+                &mut session,
+                &["COMMAND", "GETKEYS", "OBJECT", "ENCODING", "k"]
+            ),
+            bulks(&["k"])
+        );
+    }
+
+    #[test]
+    fn test_command_list() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<CommandCmd>(&mut session, &["COMMAND", "COUNT"]),
+            Frame::Integer(COMMAND_DOCS.len() as i64)
+        );
+        match run_command::<CommandCmd>(&mut session, &["COMMAND", "LIST"]) {
+            Frame::Array(names) => assert_eq!(names.len(), COMMAND_DOCS.len()),
+            frame => panic!("unexpected response {:?}", frame),
+        }
+        assert_eq!(
+            run_command::<CommandCmd>(
+                &mut session,
+                &["COMMAND", "LIST", "FILTERBY", "ACLCAT", "string"]
+            ),
+            bulks(&[
+                "append", "decr", "decrby", "get", "getrange", "getset", "incr", "incrby", "mget",
+                "mset", "set", "setnx", "substr"
+            ])
+        );
+        assert_eq!(
+            run_command::<CommandCmd>(
+                &mut session,
+                &["COMMAND", "LIST", "filterby", "pattern", "A*"]
+            ),
+            bulks(&["acl", "append", "auth"])
+        );
+        assert_eq!(
+            run_command::<CommandCmd>(
+                &mut session,
+                &["COMMAND", "LIST", "FILTERBY", "MODULE", "json"]
+            ),
+            bulks(&[])
+        );
+        assert!(<CommandCmd as Command>::from(&[
+            Frame::Bulk("COMMAND".to_string()),
+            Frame::Bulk("LIST".to_string()),
+            Frame::Bulk("FILTERBY".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_command_docs() {
+        let mut session = test_session();
+        let map = |fields: Vec<(&str, Frame)>| {
+            Frame::Map(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (Frame::Bulk(name.to_string()), value))
+                    .collect(),
+            )
+        };
+        let key = |flags: Option<Frame>| {
+            let mut fields = vec![
+                ("name", Frame::Bulk("key".to_string())),
+                ("type", Frame::Bulk("key".to_string())),
+            ];
+            fields.extend(flags.map(|flags| ("flags", flags)));
+            map(fields)
+        };
+        assert_eq!(
+            run_command::<CommandCmd>(&mut session, &["COMMAND", "DOCS", "GET", "del", "nope"]),
+            map(vec![
+                (
+                    "del",
+                    map(vec![(
+                        "arguments",
+                        Frame::Array(vec![key(Some(bulks(&["multiple"])))])
+                    )])
+                ),
+                (
+                    "get",
+                    map(vec![("arguments", Frame::Array(vec![key(None)]))])
+                ),
+            ])
+        );
+        match run_command::<CommandCmd>(&mut session, &["COMMAND", "DOCS"]) {
+            Frame::Map(docs) => assert_eq!(docs.len(), COMMAND_DOCS.len()),
+            frame => panic!("unexpected response {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_args_frame_nested() {
+        use crate::cmd::docs::{ArgFlags, ArgType};
+        const CONDITION: &[ArgSpec] = &[
+            ArgSpec {
+                token: Some("NX"),
+                ..ArgSpec::required("nx", ArgType::PureToken)
+            },
+            ArgSpec {
+                token: Some("XX"),
+                ..ArgSpec::required("xx", ArgType::PureToken)
+            },
+        ];
+        let arg = ArgSpec {
+            optional: true,
+            flags: ArgFlags {
+                multiple: false,
+                multiple_token: true,
+            },
+            ..ArgSpec::required("condition", ArgType::OneOf(CONDITION))
+        };
+        let frame = args_frame(&[arg]);
+        let Frame::Array(args) = frame else {
+            panic!("unexpected frame {:?}", frame)
+        };
+        let Frame::Map(fields) = &args[0] else {
+            panic!("unexpected frame {:?}", args[0])
+        };
+        let field = |name: &str| fields.get(&Frame::Bulk(name.to_string())).cloned();
+        assert_eq!(field("type"), Some(Frame::Bulk("oneof".to_string())));
+        assert_eq!(field("flags"), Some(bulks(&["optional", "multiple_token"])));
+        match field("arguments") {
+            Some(Frame::Array(nested)) => assert_eq!(nested.len(), 2),
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn test_command_docs_are_known_commands() {
+        for doc in COMMAND_DOCS {
+            let frames = vec![Frame::Bulk(doc.name.to_string())];
+            assert!(
+                command_keys(&doc.name.to_uppercase(), &frames).is_some(),
+                "{} is documented but not implemented",
+                doc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_getkeys_errors() {
+        let mut session = test_session();
+        let error =
+            |args: &[&str], session: &mut Session| match run_command::<CommandCmd>(session, args) {
+                Frame::Error(message) => message,
+                frame => panic!("unexpected response {:?}", frame),
+            };
+        assert_eq!(
+            error(&["COMMAND", "GETKEYS", "PING"], &mut session),
+            "ERR The command has no key arguments"
+        );
+        assert_eq!(
+            error(&["COMMAND", "GETKEYS", "NOPE", "k"], &mut session),
+            "ERR Invalid command specified"
+        );
+        assert_eq!(
+            error(&["COMMAND", "GETKEYS", "GET"], &mut session),
+            "ERR Invalid arguments specified for command"
+        );
+    }
+}
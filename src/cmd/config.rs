@@ -0,0 +1,167 @@
+use crate::cmd::Command;
+use crate::error::{CommandError, ConfigError};
+use crate::frame::Frame;
+use crate::session::Session;
+use std::collections::BTreeMap;
+
+enum ConfigSubCmd {
+    Get(Vec<String>),
+    Set(Vec<(String, String)>),
+    ResetStat,
+}
+
+/// Config implements CONFIG GET and CONFIG SET on top of the shared `ServerConfig`.
+/// CONFIG RESETSTAT zeroes the statistics reported by INFO.
+pub struct Config {
+    sub_cmd: ConfigSubCmd,
+}
+
+impl Command for Config {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        match &self.sub_cmd {
+            ConfigSubCmd::Get(patterns) => {
+                // several patterns can match the same parameter, which is only returned once
+                let mut params = BTreeMap::new();
+                for pattern in patterns {
+                    params.extend(cache.config().get(pattern));
+                }
+                let mut frames = Vec::with_capacity(params.len() * 2);
+                for (name, value) in params {
+                    frames.push(Frame::Bulk(name.to_string()));
+                    frames.push(Frame::Bulk(value));
+                }
+                Frame::Array(frames)
+            }
+            ConfigSubCmd::Set(params) => {
+                let params: Vec<(&str, &str)> = params
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect();
+                match cache.config().set(&params) {
+                    Ok(()) => Frame::Simple("OK".to_string()),
+                    Err(ConfigError::UnknownParameter(name)) => Frame::Error(format!(
+                        "ERR Unknown option or number of arguments for CONFIG SET - '{}'",
+                        name
+                    )),
+                    Err(ConfigError::InvalidValue { name, reason }) => Frame::Error(format!(
+                        "ERR CONFIG SET failed (possibly related to argument '{}') - {}",
+                        name, reason
+                    )),
+                }
+            }
+            ConfigSubCmd::ResetStat => {
+                session.command_stats().reset();
+                session.thread_pool().reset();
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() < 2 {
+            return Err(CommandError::Malformed(
+                "CONFIG command requires a sub-command".to_string(),
+            ));
+        }
+        let mut args = Vec::with_capacity(frames.len() - 1);
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.clone()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        let sub_cmd_name = args.remove(0).to_uppercase();
+        let sub_cmd = match sub_cmd_name.as_str() {
+            "GET" => {
+                if args.is_empty() {
+                    return Err(CommandError::Malformed(
+                        "CONFIG GET requires at least 1 argument".to_string(),
+                    ));
+                }
+                ConfigSubCmd::Get(args)
+            }
+            "SET" => {
+                if args.is_empty() || args.len() % 2 != 0 {
+                    return Err(CommandError::Malformed(
+                        "CONFIG SET requires parameter and value pairs".to_string(),
+                    ));
+                }
+                let params = args
+                    .chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect();
+                ConfigSubCmd::Set(params)
+            }
+            "RESETSTAT" => {
+                if !args.is_empty() {
+                    return Err(CommandError::Malformed(
+                        "CONFIG RESETSTAT takes no argument".to_string(),
+                    ));
+                }
+                ConfigSubCmd::ResetStat
+            }
+            _ => return Err(CommandError::Unknown(format!("CONFIG {}", sub_cmd_name))),
+        };
+        Ok(Config { sub_cmd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    fn bulks(values: &[&str]) -> Frame {
+        Frame::Array(values.iter().map(|v| Frame::Bulk(v.to_string())).collect())
+    }
+
+    #[test]
+    fn test_config_get_set() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Config>(
+                &mut session,
+                &["CONFIG", "SET", "hz", "100", "maxmemory", "1kb"]
+            ),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Config>(&mut session, &["CONFIG", "GET", "hz", "maxmemory", "h?"]),
+            bulks(&["hz", "100", "maxmemory", "1024"])
+        );
+        assert_eq!(
+            run_command::<Config>(&mut session, &["CONFIG", "SET", "eviction-threshold", "42"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(session.db().config().eviction_threshold(), 42);
+    }
+
+    #[test]
+    fn test_config_set_errors() {
+        let mut session = test_session();
+        let reply = run_command::<Config>(&mut session, &["CONFIG", "SET", "hz", "fast"]);
+        assert!(matches!(reply, Frame::Error(e) if e.starts_with("ERR CONFIG SET failed")));
+        let reply = run_command::<Config>(&mut session, &["CONFIG", "SET", "save", ""]);
+        assert!(matches!(reply, Frame::Error(e) if e.starts_with("ERR Unknown option")));
+        assert!(<Config as Command>::from(&[
+            Frame::Bulk("CONFIG".to_string()),
+            Frame::Bulk("SET".to_string()),
+            Frame::Bulk("hz".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_config_resetstat() {
+        let mut session = test_session();
+        let frames = vec![Frame::Bulk("PING".to_string())];
+        crate::cmd::execute("PING", &frames, &mut session);
+        assert_eq!(session.command_stats().get("PING").unwrap().calls(), 1);
+        assert_eq!(
+            run_command::<Config>(&mut session, &["CONFIG", "RESETSTAT"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(session.command_stats().get("PING").unwrap().calls(), 0);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// CopyCmd implements `COPY source destination [DB db] [REPLACE] [PERSIST]`, which copies a key
+/// to another key, of the current database or of `db`. The copy keeps the remaining time to live
+/// of the source, unless PERSIST is given. It replies 1 when the key was copied, and 0 when the
+/// source does not exist or the destination exists and REPLACE is not given.
+pub struct CopyCmd {
+    source: String,
+    destination: String,
+    db: Option<usize>,
+    replace: bool,
+    persist: bool,
+}
+
+impl Command for CopyCmd {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let db_id = self.db.unwrap_or(session.db().db_id());
+        match session.databases().get(db_id) {
+            None => Frame::Error("ERR DB index is out of range".to_string()),
+            Some(db) if self.destination.len() > db.config().max_key_bytes() => {
+                Frame::Error("ERR key too long".to_string())
+            }
+            Some(db) if db.db_id() == session.db().db_id() && self.source == self.destination => {
+                Frame::Error("ERR source and destination objects are the same".to_string())
+            }
+            Some(db) => Frame::Integer(session.db().copy_with_options(
+                &self.source,
+                &self.destination,
+                db,
+                self.replace,
+                self.persist,
+            ) as i64),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.source, &self.destination]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(frames.len() - 1);
+        for frame in &frames[1..] {
+            match frame {
+                Frame::Bulk(arg) => args.push(arg.as_str()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        let [source, destination, options @ ..] = &args[..] else {
+            return Err(CommandError::Malformed(
+                "COPY command requires a source and a destination".to_string(),
+            ));
+        };
+        let mut cmd = CopyCmd {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            db: None,
+            replace: false,
+            persist: false,
+        };
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            match option.to_uppercase().as_str() {
+                "DB" => {
+                    let db = options.next().and_then(|db| db.parse().ok()).ok_or(
+                        CommandError::Malformed("COPY DB must be a positive integer".to_string()),
+                    )?;
+                    cmd.db = Some(db);
+                }
+                "REPLACE" => cmd.replace = true,
+                "PERSIST" => cmd.persist = true,
+                _ => {
+                    return Err(CommandError::Malformed(format!(
+                        "COPY unknown option '{}'",
+                        option
+                    )))
+                }
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_copy() {
+        let mut session = test_session();
+        session.db().set_kv("key", "value", None);
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(session.db().peek_value("other"), Some("value".to_string()));
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other"]),
+            Frame::Integer(0)
+        );
+        session.db().set_kv("key", "new value", None);
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other", "replace"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            session.db().peek_value("other"),
+            Some("new value".to_string())
+        );
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "key", "DB", "3", "PERSIST"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            session.databases()[3].peek_value("key"),
+            Some("new value".to_string())
+        );
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "missing", "other", "REPLACE"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "key", "DB", "0"]),
+            Frame::Error("ERR source and destination objects are the same".to_string())
+        );
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other", "DB", "16"]),
+            Frame::Error("ERR DB index is out of range".to_string())
+        );
+        session
+            .db()
+            .config()
+            .set(&[("max-key-bytes", "5")])
+            .unwrap();
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "long key"]),
+            Frame::Error("ERR key too long".to_string())
+        );
+        assert!(<CopyCmd as Command>::from(&[
+            Frame::Bulk("COPY".to_string()),
+            Frame::Bulk("key".to_string()),
+            Frame::Bulk("other".to_string()),
+            Frame::Bulk("DB".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_copy_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other"]),
+            Frame::Integer(0)
+        );
+        // an expired destination does not block the copy
+        session.db().set_kv("key", "value", None);
+        set_expired(&session, "other", "old");
+        assert_eq!(
+            run_command::<CopyCmd>(&mut session, &["COPY", "key", "other"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(session.db().key_expiration("other"), None);
+    }
+}
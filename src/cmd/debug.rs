@@ -0,0 +1,272 @@
+use crate::cmd::object::string_encoding;
+use crate::cmd::Command;
+use crate::db::{self, KeyMeta, State};
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use crate::sniffer::Sniffer;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Redis keeps its LRU clock on 24 bits, with a resolution of one second.
+const LRU_CLOCK_MAX: u64 = (1 << 24) - 1;
+
+enum DebugSubCmd {
+    Object(String),
+    CommandHistory,
+    // The name of the sniffer file, None to turn the sniffer off.
+    Sniffer(Option<String>),
+}
+
+/// Debug implements the DEBUG command, used to introspect the server internals.
+/// `DEBUG COMMAND-HISTORY` returns the last commands received on the connection, and
+/// `DEBUG SNIFFER ON file|OFF` copies the bytes it exchanges to a file of the `sniffer-dir`
+/// directory.
+/// As in Redis, the command is refused unless `enable-debug-command` is set when the server starts.
+pub struct Debug {
+    sub_cmd: DebugSubCmd,
+}
+
+impl Command for Debug {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        if !cache.config().enable_debug_command() {
+            return Frame::Error(
+                "ERR DEBUG command not allowed. Set enable-debug-command when the server starts"
+                    .to_string(),
+            );
+        }
+        match &self.sub_cmd {
+            DebugSubCmd::Object(key) => debug_object(cache, key),
+            DebugSubCmd::CommandHistory => Frame::Array(
+                session
+                    .command_history()
+                    .iter()
+                    .map(|entry| Frame::Bulk(entry.clone()))
+                    .collect(),
+            ),
+            DebugSubCmd::Sniffer(Some(file)) => {
+                match sniffer_path(cache, file).and_then(|path| {
+                    Sniffer::create(path)
+                        .map_err(|err| format!("ERR unable to open the sniffer file: {}", err))
+                }) {
+                    Ok(sniffer) => {
+                        session.sniffer().set(Some(sniffer));
+                        Frame::Simple("OK".to_string())
+                    }
+                    Err(message) => Frame::Error(message),
+                }
+            }
+            DebugSubCmd::Sniffer(None) => {
+                session.sniffer().set(None);
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match &self.sub_cmd {
+            DebugSubCmd::Object(key) => vec![key],
+            DebugSubCmd::CommandHistory | DebugSubCmd::Sniffer(_) => vec![],
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() < 2 {
+            return Err(CommandError::Malformed(
+                "DEBUG command requires a sub-command".to_string(),
+            ));
+        }
+        let sub_cmd_name = match &frames[1] {
+            Frame::Bulk(name) => name.to_uppercase(),
+            _ => return Err(CommandError::InvalidCmdFrame),
+        };
+        let sub_cmd = match sub_cmd_name.as_str() {
+            "OBJECT" => {
+                if frames.len() != 3 {
+                    return Err(CommandError::Malformed(
+                        "DEBUG OBJECT requires 1 argument".to_string(),
+                    ));
+                }
+                match &frames[2] {
+                    Frame::Bulk(key) => DebugSubCmd::Object(key.clone()),
+                    _ => return Err(CommandError::InvalidCmdFrame),
+                }
+            }
+            "COMMAND-HISTORY" => {
+                if frames.len() != 2 {
+                    return Err(CommandError::Malformed(
+                        "DEBUG COMMAND-HISTORY takes no argument".to_string(),
+                    ));
+                }
+                DebugSubCmd::CommandHistory
+            }
+            "SNIFFER" => {
+                let malformed =
+                    || CommandError::Malformed("DEBUG SNIFFER requires ON file or OFF".to_string());
+                match &frames[2..] {
+                    [Frame::Bulk(on), Frame::Bulk(file)] if on.eq_ignore_ascii_case("ON") => {
+                        DebugSubCmd::Sniffer(Some(file.clone()))
+                    }
+                    [Frame::Bulk(off)] if off.eq_ignore_ascii_case("OFF") => {
+                        DebugSubCmd::Sniffer(None)
+                    }
+                    _ => return Err(malformed()),
+                }
+            }
+            _ => return Err(CommandError::Unknown(format!("DEBUG {}", sub_cmd_name))),
+        };
+        Ok(Debug { sub_cmd })
+    }
+}
+
+/// sniffer_path returns the path of a sniffer file in the `sniffer-dir` directory. The client
+/// only names the file, paths which could leave the directory are refused.
+fn sniffer_path(cache: &State, file: &str) -> Result<PathBuf, String> {
+    let dir = cache
+        .config()
+        .sniffer_dir()
+        .ok_or("ERR DEBUG SNIFFER is disabled, sniffer-dir is not set".to_string())?;
+    let mut components = Path::new(file).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) => Ok(dir.join(name)),
+        _ => Err("ERR the sniffer file must be a file name, without directory".to_string()),
+    }
+}
+
+/// debug_object describes how a key is stored, in the same format as Redis.
+/// Values do not have a stable address, the hash of the key stands for it.
+fn debug_object(cache: &Arc<State>, key: &str) -> Frame {
+    let (value, meta) = match (cache.peek_value(key), cache.get_meta(key)) {
+        (Some(value), Some(meta)) => (value, meta),
+        _ => return Frame::Error("ERR no such key".to_string()),
+    };
+    Frame::Bulk(format!(
+        "Value at:0x{:x} refcount:1 encoding:{} serializedlength:{} lru:{} lru_seconds_idle:{} type:string",
+        db::calculate_hash(&key),
+        string_encoding(&value),
+        value.len(),
+        lru_clock(&meta),
+        meta.last_access.elapsed().as_secs(),
+    ))
+}
+
+/// lru_clock returns the time of the last access to a key as a Redis LRU clock value.
+fn lru_clock(meta: &KeyMeta) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.saturating_sub(meta.last_access.elapsed()).as_secs() & LRU_CLOCK_MAX
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+    use crate::config::{ENABLE_DEBUG_COMMAND, SNIFFER_DIR};
+
+    /// debug_session returns a test session of a server allowing DEBUG, with `sniffer_dir` as
+    /// the directory of the sniffer files.
+    fn debug_session(sniffer_dir: &str) -> Session {
+        let session = test_session();
+        session
+            .db()
+            .config()
+            .set_at_startup(&[(ENABLE_DEBUG_COMMAND, "yes"), (SNIFFER_DIR, sniffer_dir)])
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_debug_disabled_by_default() {
+        let mut session = test_session();
+        session.db().set_kv("key", "hello", None);
+        let reply = run_command::<Debug>(&mut session, &["DEBUG", "OBJECT", "key"]);
+        assert!(matches!(reply, Frame::Error(e) if e.starts_with("ERR DEBUG command not allowed")));
+    }
+
+    #[test]
+    fn test_debug_object() {
+        let mut session = debug_session("");
+        session.db().set_kv("key", "hello", None);
+        match run_command::<Debug>(&mut session, &["DEBUG", "OBJECT", "key"]) {
+            Frame::Bulk(description) => {
+                assert!(description.starts_with("Value at:0x"));
+                assert!(description.contains(" refcount:1 encoding:embstr serializedlength:5 "));
+                assert!(description.ends_with(" lru_seconds_idle:0 type:string"));
+            }
+            other => panic!("expected a bulk string, got {:?}", other),
+        }
+        assert_eq!(
+            run_command::<Debug>(&mut session, &["DEBUG", "OBJECT", "missing"]),
+            Frame::Error("ERR no such key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_debug_object_expired_key() {
+        let mut session = debug_session("");
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<Debug>(&mut session, &["DEBUG", "OBJECT", "key"]),
+            Frame::Error("ERR no such key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_debug_command_history() {
+        let mut session = debug_session("");
+        session
+            .db()
+            .config()
+            .set(&[("command-history-len", "2")])
+            .unwrap();
+        for entry in ["SET a", "GET a", "DEBUG"] {
+            session.record_command(entry.to_string());
+        }
+        assert_eq!(
+            run_command::<Debug>(&mut session, &["DEBUG", "COMMAND-HISTORY"]),
+            Frame::Array(vec![
+                Frame::Bulk("GET a".to_string()),
+                Frame::Bulk("DEBUG".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_debug_sniffer() {
+        let dir = std::env::temp_dir();
+        let mut session = debug_session(dir.to_str().unwrap());
+        let file = format!("htcache-debug-sniffer-{}", std::process::id());
+        assert_eq!(
+            run_command::<Debug>(&mut session, &["DEBUG", "SNIFFER", "ON", &file]),
+            Frame::Simple("OK".to_string())
+        );
+        assert!(session.sniffer().is_enabled());
+        assert_eq!(
+            run_command::<Debug>(&mut session, &["DEBUG", "sniffer", "off"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert!(!session.sniffer().is_enabled());
+        std::fs::remove_file(dir.join(file)).unwrap();
+        for path in ["/tmp/file", "../file", "sub/file", "..", ""] {
+            let reply = run_command::<Debug>(&mut session, &["DEBUG", "SNIFFER", "ON", path]);
+            assert!(
+                matches!(&reply, Frame::Error(e) if e.starts_with("ERR the sniffer file must be")),
+                "{:?} gave {:?}",
+                path,
+                reply
+            );
+        }
+        assert!(!session.sniffer().is_enabled());
+    }
+
+    #[test]
+    fn test_debug_sniffer_without_dir() {
+        let mut session = debug_session("");
+        let reply = run_command::<Debug>(&mut session, &["DEBUG", "SNIFFER", "ON", "file"]);
+        assert!(matches!(reply, Frame::Error(e) if e.contains("sniffer-dir is not set")));
+        assert!(!session.sniffer().is_enabled());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::cmd::incr::incr_by;
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Decr implements `DECR key`, which subtracts 1 from the integer stored at a key and returns the
+/// new value. A missing key counts as 0.
+pub struct Decr {
+    key: String,
+}
+
+impl Command for Decr {
+    fn apply(&self, session: &mut Session) -> Frame {
+        incr_by(session, &self.key, -1)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key)] => Ok(Decr { key: key.clone() }),
+            [_] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "DECR command requires 1 argument".to_string(),
+            )),
+        }
+    }
+}
+
+/// DecrBy implements `DECRBY key decrement`, which subtracts `decrement` from the integer stored
+/// at a key and returns the new value. A missing key counts as 0.
+pub struct DecrBy {
+    key: String,
+    decrement: i64,
+}
+
+impl Command for DecrBy {
+    fn apply(&self, session: &mut Session) -> Frame {
+        match self.decrement.checked_neg() {
+            Some(delta) => incr_by(session, &self.key, delta),
+            None => Frame::Error("ERR decrement would overflow".to_string()),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(decrement)] => Ok(DecrBy {
+                key: key.clone(),
+                decrement: decrement.parse().map_err(|_| {
+                    CommandError::Malformed("DECRBY decrement must be an integer".to_string())
+                })?,
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "DECRBY command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_decr() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Decr>(&mut session, &["DECR", "counter"]),
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run_command::<DecrBy>(&mut session, &["DECRBY", "counter", "-11"]),
+            Frame::Integer(10)
+        );
+        assert_eq!(
+            run_command::<DecrBy>(&mut session, &["DECRBY", "counter", &i64::MIN.to_string()]),
+            Frame::Error("ERR decrement would overflow".to_string())
+        );
+        assert_eq!(session.db().peek_value("counter"), Some("10".to_string()));
+        assert!(<DecrBy as Command>::from(&[
+            Frame::Bulk("DECRBY".to_string()),
+            Frame::Bulk("counter".to_string()),
+        ])
+        .is_err());
+        assert!(<DecrBy as Command>::from(&[
+            Frame::Bulk("DECRBY".to_string()),
+            Frame::Bulk("counter".to_string()),
+            Frame::Bulk("one".to_string()),
+        ])
+        .is_err());
+    }
+}
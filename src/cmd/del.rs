@@ -1,22 +1,24 @@
 use crate::cmd::Command;
-use crate::db::State;
 use crate::error::CommandError;
 use crate::frame::Frame;
-use std::io::{BufWriter, Write};
-use std::sync::Arc;
+use crate::session::Session;
 
 pub struct Del {
     keys: Vec<String>,
 }
 
 impl Command for Del {
-    fn apply<T: Write>(&self, dest: &mut BufWriter<T>, cache: &Arc<State>) -> std::io::Result<()> {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
         let deleted = cache.delete_entries(&self.keys);
-        let response_frame = Frame::Integer(deleted as i64);
-        response_frame.write_to(dest)
+        Frame::Integer(deleted as i64)
     }
 
-    fn from(frames: Vec<Frame>) -> Result<Self, CommandError> {
+    fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
         if frames.len() < 2 {
             return Err(CommandError::Malformed(
                 "DEL command requires at least one key".to_string(),
@@ -36,3 +38,34 @@ impl Command for Del {
 fn new() -> Del {
     Del { keys: Vec::new() }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+    use std::time::Duration;
+
+    #[test]
+    fn test_del() {
+        let mut session = test_session();
+        session.db().set_kv("a", "1", None);
+        session.db().set_kv("b", "2", Some(Duration::from_secs(60)));
+        assert_eq!(
+            run_command::<Del>(&mut session, &["DEL", "a", "b", "b", "missing"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(session.db().peek_value("a"), None);
+        assert_eq!(session.db().peek_value("b"), None);
+    }
+
+    #[test]
+    fn test_del_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<Del>(&mut session, &["DEL", "key"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(session.db().peek_value("key"), None);
+    }
+}
@@ -0,0 +1,742 @@
+//! Static description of the commands supported by the server, returned by the COMMAND command.
+//! Every command dispatched by the connection needs an entry here.
+
+/// CommandDoc describes a command the way Redis does in COMMAND INFO.
+#[derive(Debug)]
+pub struct CommandDoc {
+    /// Name of the command, in lower case.
+    pub name: &'static str,
+    /// Number of arguments, including the command name. A negative arity is a minimum.
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    /// Position of the first key argument, 0 when the command has no key.
+    pub first_key: i64,
+    /// Position of the last key argument, -1 when keys go up to the last argument.
+    pub last_key: i64,
+    /// Step between two key arguments.
+    pub step: i64,
+    /// ACL categories of the command, without the leading '@'.
+    pub acl_categories: &'static [&'static str],
+    /// Arguments of the command, after its name, returned by COMMAND DOCS. Empty when the
+    /// arguments are not described yet.
+    pub args: &'static [ArgSpec],
+    /// Redis version which deprecated the command, in favor of a newer one.
+    pub deprecated_since_version: Option<&'static str>,
+}
+
+/// ArgSpec describes an argument of a command the way Redis does in COMMAND DOCS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub type_: ArgType,
+    pub flags: ArgFlags,
+    pub optional: bool,
+    /// Constant word preceding the argument, like `EX` in `SET key value EX seconds`.
+    pub token: Option<&'static str>,
+}
+
+impl ArgSpec {
+    /// required creates a mandatory argument without token.
+    pub const fn required(name: &'static str, type_: ArgType) -> Self {
+        ArgSpec {
+            name,
+            type_,
+            flags: ArgFlags::NONE,
+            optional: false,
+            token: None,
+        }
+    }
+}
+
+/// ArgType is the type of an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgType {
+    String,
+    Integer,
+    Double,
+    Key,
+    Pattern,
+    UnixTime,
+    /// The argument is its token alone, like `NX`.
+    PureToken,
+    /// Exactly one of the nested arguments.
+    OneOf(&'static [ArgSpec]),
+    /// All the nested arguments, in order.
+    Block(&'static [ArgSpec]),
+}
+
+impl ArgType {
+    /// name returns the type as written by COMMAND DOCS.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArgType::String => "string",
+            ArgType::Integer => "integer",
+            ArgType::Double => "double",
+            ArgType::Key => "key",
+            ArgType::Pattern => "pattern",
+            ArgType::UnixTime => "unix-time",
+            ArgType::PureToken => "pure-token",
+            ArgType::OneOf(_) => "oneof",
+            ArgType::Block(_) => "block",
+        }
+    }
+
+    /// nested returns the arguments of a oneof or a block.
+    pub fn nested(&self) -> &'static [ArgSpec] {
+        match self {
+            ArgType::OneOf(args) | ArgType::Block(args) => args,
+            _ => &[],
+        }
+    }
+}
+
+/// ArgFlags tells whether an argument can be repeated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArgFlags {
+    /// The argument can be given several times, like the keys of DEL.
+    pub multiple: bool,
+    /// The token must be repeated with every occurrence of the argument.
+    pub multiple_token: bool,
+}
+
+impl ArgFlags {
+    pub const NONE: ArgFlags = ArgFlags {
+        multiple: false,
+        multiple_token: false,
+    };
+    pub const MULTIPLE: ArgFlags = ArgFlags {
+        multiple: true,
+        multiple_token: false,
+    };
+}
+
+pub const COMMAND_DOCS: &[CommandDoc] = &[
+    CommandDoc {
+        name: "acl",
+        arity: -2,
+        flags: &[],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "append",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("value", ArgType::String),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "auth",
+        arity: -2,
+        flags: &[
+            "noscript",
+            "loading",
+            "stale",
+            "fast",
+            "no_auth",
+            "allow_busy",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["fast", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "cluster",
+        arity: -2,
+        flags: &[],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "command",
+        arity: -1,
+        flags: &["loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "config",
+        arity: -2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["admin", "slow", "dangerous"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "copy",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "write", "slow"],
+        args: &[
+            ArgSpec::required("source", ArgType::Key),
+            ArgSpec::required("destination", ArgType::Key),
+            ArgSpec {
+                optional: true,
+                token: Some("DB"),
+                ..ArgSpec::required("destination-db", ArgType::Integer)
+            },
+            ArgSpec {
+                optional: true,
+                token: Some("REPLACE"),
+                ..ArgSpec::required("replace", ArgType::PureToken)
+            },
+            ArgSpec {
+                optional: true,
+                token: Some("PERSIST"),
+                ..ArgSpec::required("persist", ArgType::PureToken)
+            },
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "debug",
+        arity: -2,
+        flags: &["admin", "noscript", "loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["admin", "slow", "dangerous"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "decr",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[ArgSpec::required("key", ArgType::Key)],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "decrby",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("decrement", ArgType::Integer),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "del",
+        arity: -2,
+        flags: &["write"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "slow"],
+        args: &[ArgSpec {
+            flags: ArgFlags::MULTIPLE,
+            ..ArgSpec::required("key", ArgType::Key)
+        }],
+        deprecated_since_version: None,
+    },
+    #[cfg(feature = "lua")]
+    CommandDoc {
+        name: "eval",
+        arity: -3,
+        flags: &[
+            "noscript",
+            "stale",
+            "skip_monitor",
+            "no_mandatory_keys",
+            "movablekeys",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "scripting"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    #[cfg(feature = "lua")]
+    CommandDoc {
+        name: "evalsha",
+        arity: -3,
+        flags: &[
+            "noscript",
+            "stale",
+            "skip_monitor",
+            "no_mandatory_keys",
+            "movablekeys",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "scripting"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "expire",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("seconds", ArgType::Integer),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "expireat",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("unix-time-seconds", ArgType::UnixTime),
+        ],
+        deprecated_since_version: None,
+    },
+    #[cfg(feature = "commands-scripting")]
+    CommandDoc {
+        name: "fcall",
+        arity: -3,
+        flags: &[
+            "noscript",
+            "stale",
+            "skip_monitor",
+            "no_mandatory_keys",
+            "movablekeys",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "scripting"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    #[cfg(feature = "commands-scripting")]
+    CommandDoc {
+        name: "function",
+        arity: -2,
+        flags: &[],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "get",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "string", "fast"],
+        args: &[ArgSpec::required("key", ArgType::Key)],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "getrange",
+        arity: 4,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "string", "slow"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("start", ArgType::Integer),
+            ArgSpec::required("end", ArgType::Integer),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "getset",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("value", ArgType::String),
+        ],
+        deprecated_since_version: Some("6.2.0"),
+    },
+    CommandDoc {
+        name: "hotkeys",
+        arity: -1,
+        flags: &["readonly"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["keyspace", "read", "slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "incr",
+        arity: 2,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[ArgSpec::required("key", ArgType::Key)],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "incrby",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("increment", ArgType::Integer),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "info",
+        arity: -1,
+        flags: &["loading", "stale"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "dangerous"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "lolwut",
+        arity: -1,
+        flags: &["readonly", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["read", "fast"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "mget",
+        arity: -2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: -1,
+        step: 1,
+        acl_categories: &["read", "string", "fast"],
+        args: &[ArgSpec {
+            flags: ArgFlags::MULTIPLE,
+            ..ArgSpec::required("key", ArgType::Key)
+        }],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "move",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "fast"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "mset",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: -1,
+        step: 2,
+        acl_categories: &["write", "string", "slow"],
+        args: &[ArgSpec {
+            flags: ArgFlags::MULTIPLE,
+            ..ArgSpec::required(
+                "data",
+                ArgType::Block(&[
+                    ArgSpec::required("key", ArgType::Key),
+                    ArgSpec::required("value", ArgType::String),
+                ]),
+            )
+        }],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "object",
+        arity: -1,
+        flags: &["readonly"],
+        first_key: 2,
+        last_key: 2,
+        step: 1,
+        acl_categories: &["keyspace", "read", "slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "pexpire",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("milliseconds", ArgType::Integer),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "pexpireat",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "write", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("unix-time-milliseconds", ArgType::UnixTime),
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "ping",
+        arity: -1,
+        flags: &["fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["fast", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "pttl",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "read", "fast"],
+        args: &[ArgSpec::required("key", ArgType::Key)],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "reset",
+        arity: 1,
+        flags: &[
+            "noscript",
+            "loading",
+            "stale",
+            "fast",
+            "no_auth",
+            "allow_busy",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["fast", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    #[cfg(feature = "lua")]
+    CommandDoc {
+        name: "script",
+        arity: -2,
+        flags: &[],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "select",
+        arity: 2,
+        flags: &["loading", "stale", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["fast", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "set",
+        arity: -3,
+        flags: &["write", "denyoom"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "slow"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("value", ArgType::String),
+            ArgSpec {
+                optional: true,
+                ..ArgSpec::required(
+                    "condition",
+                    ArgType::OneOf(&[
+                        ArgSpec {
+                            token: Some("NX"),
+                            ..ArgSpec::required("nx", ArgType::PureToken)
+                        },
+                        ArgSpec {
+                            token: Some("XX"),
+                            ..ArgSpec::required("xx", ArgType::PureToken)
+                        },
+                    ]),
+                )
+            },
+            ArgSpec {
+                optional: true,
+                ..ArgSpec::required(
+                    "expiration",
+                    ArgType::OneOf(&[
+                        ArgSpec {
+                            token: Some("EX"),
+                            ..ArgSpec::required("seconds", ArgType::Integer)
+                        },
+                        ArgSpec {
+                            token: Some("PX"),
+                            ..ArgSpec::required("milliseconds", ArgType::Integer)
+                        },
+                        ArgSpec {
+                            token: Some("EXAT"),
+                            ..ArgSpec::required("unix-time-seconds", ArgType::UnixTime)
+                        },
+                        ArgSpec {
+                            token: Some("PXAT"),
+                            ..ArgSpec::required("unix-time-milliseconds", ArgType::UnixTime)
+                        },
+                    ]),
+                )
+            },
+        ],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "setnx",
+        arity: 3,
+        flags: &["write", "denyoom", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["write", "string", "fast"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("value", ArgType::String),
+        ],
+        deprecated_since_version: Some("2.6.12"),
+    },
+    CommandDoc {
+        name: "shutdown",
+        arity: -1,
+        flags: &[
+            "admin",
+            "noscript",
+            "loading",
+            "stale",
+            "no_multi",
+            "allow_busy",
+        ],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["admin", "slow", "dangerous"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "substr",
+        arity: 4,
+        flags: &["readonly"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["read", "string", "slow"],
+        args: &[
+            ArgSpec::required("key", ArgType::Key),
+            ArgSpec::required("start", ArgType::Integer),
+            ArgSpec::required("end", ArgType::Integer),
+        ],
+        deprecated_since_version: Some("2.0.0"),
+    },
+    CommandDoc {
+        name: "swapdb",
+        arity: 3,
+        flags: &["write", "fast"],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["keyspace", "write", "fast", "dangerous"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "ttl",
+        arity: 2,
+        flags: &["readonly", "fast"],
+        first_key: 1,
+        last_key: 1,
+        step: 1,
+        acl_categories: &["keyspace", "read", "fast"],
+        args: &[ArgSpec::required("key", ArgType::Key)],
+        deprecated_since_version: None,
+    },
+    CommandDoc {
+        name: "wait",
+        arity: 3,
+        flags: &[],
+        first_key: 0,
+        last_key: 0,
+        step: 0,
+        acl_categories: &["slow", "connection"],
+        args: &[],
+        deprecated_since_version: None,
+    },
+];
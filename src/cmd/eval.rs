@@ -0,0 +1,208 @@
+use crate::cmd::{parse_call, Command};
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::scripting;
+use crate::session::Session;
+
+enum ScriptSource {
+    Code(String),
+    Sha(String),
+}
+
+/// Eval implements `EVAL script numkeys [key ...] [arg ...]` and `EVALSHA sha1 numkeys ...`,
+/// which run a Lua script. EVAL also caches the script, so it can be run again with EVALSHA.
+pub struct Eval {
+    source: ScriptSource,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+impl Command for Eval {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let scripts = session.scripts().clone();
+        let script = match &self.source {
+            ScriptSource::Code(script) => {
+                scripts.insert(script);
+                Some(script.clone())
+            }
+            ScriptSource::Sha(sha) => scripts.get(sha),
+        };
+        match script {
+            Some(script) => scripting::eval(&script, &self.keys, &self.args, session),
+            None => Frame::Error("NOSCRIPT No matching script. Please use EVAL.".to_string()),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let by_sha = matches!(frames.first(), Some(Frame::Bulk(name)) if name.eq_ignore_ascii_case("EVALSHA"));
+        let (script, keys, args) = parse_call(frames)?;
+        let source = if by_sha {
+            ScriptSource::Sha(script)
+        } else {
+            ScriptSource::Code(script)
+        };
+        Ok(Eval { source, keys, args })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_eval() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &[
+                    "EVAL",
+                    "return redis.call('SET', KEYS[1], ARGV[1])",
+                    "1",
+                    "k",
+                    "v"
+                ]
+            ),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return redis.call('GET', KEYS[1])", "1", "k"]
+            ),
+            Frame::Bulk("v".to_string())
+        );
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return {1, 'two', false, 3, nil, 5}", "0"]
+            ),
+            Frame::Array(vec![
+                Frame::Integer(1),
+                Frame::Bulk("two".to_string()),
+                Frame::Null,
+                Frame::Integer(3)
+            ])
+        );
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return redis.call('GET', 'missing')", "0"]
+            ),
+            Frame::Null
+        );
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return redis.status_reply('FINE')", "0"]
+            ),
+            Frame::Simple("FINE".to_string())
+        );
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return redis.error_reply('ERR bad')", "0"]
+            ),
+            Frame::Error("ERR bad".to_string())
+        );
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        let mut session = test_session();
+        // redis.call stops the script on error, redis.pcall returns the error to the script
+        let reply =
+            run_command::<Eval>(&mut session, &["EVAL", "redis.call('NOPE'); return 1", "0"]);
+        assert!(matches!(reply, Frame::Error(_)), "{:?}", reply);
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["EVAL", "return redis.pcall('NOPE').err ~= nil", "0"]
+            ),
+            Frame::Integer(1)
+        );
+        let reply = run_command::<Eval>(
+            &mut session,
+            &["EVAL", "return redis.call('EVAL', 'return 1', '0')", "0"],
+        );
+        assert_eq!(
+            reply,
+            Frame::Error("ERR This Redis command is not allowed from script".to_string())
+        );
+        let reply = run_command::<Eval>(&mut session, &["EVAL", "return +", "0"]);
+        assert!(
+            matches!(&reply, Frame::Error(e) if e.starts_with("ERR Error running script")),
+            "{:?}",
+            reply
+        );
+        let reply =
+            run_command::<Eval>(&mut session, &["EVAL", "return io.open('/etc/hosts')", "0"]);
+        assert!(matches!(reply, Frame::Error(_)), "{:?}", reply);
+    }
+
+    #[test]
+    fn test_eval_limits() {
+        let mut session = test_session();
+        session
+            .db()
+            .config()
+            .set(&[("lua-time-limit", "50"), ("lua-memory-limit", "1mb")])
+            .unwrap();
+        let reply = run_command::<Eval>(&mut session, &["EVAL", "while true do end", "0"]);
+        assert_eq!(
+            reply,
+            Frame::Error(
+                "ERR Error running script: runtime error: script killed after running longer than lua-time-limit"
+                    .to_string()
+            )
+        );
+        let reply = run_command::<Eval>(
+            &mut session,
+            &[
+                "EVAL",
+                "local t = {} while true do t[#t + 1] = 'x' end",
+                "0",
+            ],
+        );
+        assert!(
+            matches!(&reply, Frame::Error(e) if e.starts_with("ERR Error running script")),
+            "{:?}",
+            reply
+        );
+        let reply = run_command::<Eval>(&mut session, &["EVAL", "return load('return 1')()", "0"]);
+        assert!(matches!(reply, Frame::Error(_)), "{:?}", reply);
+    }
+
+    #[test]
+    fn test_evalsha() {
+        let mut session = test_session();
+        let script = "return ARGV[1]";
+        let sha = scripting::sha1_hex(script);
+        assert_eq!(
+            run_command::<Eval>(&mut session, &["EVALSHA", &sha, "0", "hello"]),
+            Frame::Error("NOSCRIPT No matching script. Please use EVAL.".to_string())
+        );
+        run_command::<Eval>(&mut session, &["EVAL", script, "0", "first"]);
+        assert_eq!(
+            run_command::<Eval>(
+                &mut session,
+                &["evalsha", &sha.to_uppercase(), "0", "hello"]
+            ),
+            Frame::Bulk("hello".to_string())
+        );
+
+        let eval = <Eval as Command>::from(
+            &["EVALSHA", &sha, "2", "a", "b", "c"]
+                .iter()
+                .map(|v| Frame::Bulk(v.to_string()))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(eval.keys(), ["a", "b"]);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Expire implements `EXPIRE key seconds`, which sets the time left to live of a key. It replies 1
+/// when the key exists and 0 otherwise. A time which is not positive expires the key right away.
+pub struct Expire {
+    key: String,
+    seconds: i64,
+}
+
+impl Command for Expire {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let ttl = Duration::from_secs(self.seconds.max(0) as u64);
+        expire(session, &self.key, ttl)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let (key, seconds) = parse("EXPIRE", frames)?;
+        Ok(Expire { key, seconds })
+    }
+}
+
+/// PExpire implements `PEXPIRE key milliseconds`, which works like EXPIRE with milliseconds.
+pub struct PExpire {
+    key: String,
+    millis: i64,
+}
+
+impl Command for PExpire {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let ttl = Duration::from_millis(self.millis.max(0) as u64);
+        expire(session, &self.key, ttl)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let (key, millis) = parse("PEXPIRE", frames)?;
+        Ok(PExpire { key, millis })
+    }
+}
+
+/// ExpireAt implements `EXPIREAT key unix-time-seconds`, which sets the time at which a key
+/// expires. It replies 1 when the key exists and 0 otherwise. A time already passed expires the
+/// key right away.
+pub struct ExpireAt {
+    key: String,
+    seconds: i64,
+}
+
+impl Command for ExpireAt {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let at = Duration::from_secs(self.seconds.max(0) as u64);
+        expire(session, &self.key, until(at))
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let (key, seconds) = parse("EXPIREAT", frames)?;
+        Ok(ExpireAt { key, seconds })
+    }
+}
+
+/// PExpireAt implements `PEXPIREAT key unix-time-milliseconds`, which works like EXPIREAT with
+/// milliseconds.
+pub struct PExpireAt {
+    key: String,
+    millis: i64,
+}
+
+impl Command for PExpireAt {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let at = Duration::from_millis(self.millis.max(0) as u64);
+        expire(session, &self.key, until(at))
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let (key, millis) = parse("PEXPIREAT", frames)?;
+        Ok(PExpireAt { key, millis })
+    }
+}
+
+/// parse reads the key and the time of an expire command.
+fn parse(cmd_name: &str, frames: &[Frame]) -> Result<(String, i64), CommandError> {
+    match &frames[1..] {
+        [Frame::Bulk(key), Frame::Bulk(time)] => {
+            let time = time.parse().map_err(|_| {
+                CommandError::Malformed(format!("{} time must be an integer", cmd_name))
+            })?;
+            Ok((key.clone(), time))
+        }
+        [_, _] => Err(CommandError::InvalidCmdFrame),
+        _ => Err(CommandError::Malformed(format!(
+            "{} command requires 2 arguments",
+            cmd_name
+        ))),
+    }
+}
+
+/// until returns the time left before a Unix time, zero when it has passed.
+fn until(at: Duration) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    at.saturating_sub(now)
+}
+
+/// expire sets a key of the current database to expire after `ttl` and builds the reply.
+fn expire(session: &Session, key: &str, ttl: Duration) -> Frame {
+    match Instant::now().checked_add(ttl) {
+        Some(expiry) => Frame::Integer(session.db().set_expiry(key, expiry) as i64),
+        None => Frame::Error("ERR invalid expire time".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_expire() {
+        let mut session = test_session();
+        session.db().set_kv("key", "value", None);
+        assert_eq!(
+            run_command::<Expire>(&mut session, &["EXPIRE", "missing", "10"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run_command::<Expire>(&mut session, &["EXPIRE", "key", "100"]),
+            Frame::Integer(1)
+        );
+        let remaining = |session: &Session| {
+            session
+                .db()
+                .key_expiration("key")
+                .unwrap()
+                .duration_since(Instant::now())
+        };
+        assert!(remaining(&session) > Duration::from_secs(99));
+        assert_eq!(
+            run_command::<PExpire>(&mut session, &["PEXPIRE", "key", "50000"]),
+            Frame::Integer(1)
+        );
+        assert!(remaining(&session) <= Duration::from_secs(50));
+
+        let in_a_minute = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        assert_eq!(
+            run_command::<ExpireAt>(&mut session, &["EXPIREAT", "key", &in_a_minute.to_string()]),
+            Frame::Integer(1)
+        );
+        assert!(remaining(&session) > Duration::from_secs(50));
+        assert_eq!(
+            run_command::<Expire>(&mut session, &["EXPIRE", "key", &i64::MAX.to_string()]),
+            Frame::Error("ERR invalid expire time".to_string())
+        );
+
+        // a time already passed expires the key right away
+        assert_eq!(
+            run_command::<PExpireAt>(&mut session, &["PEXPIREAT", "key", "1000"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(session.db().get_value_by_key("key"), None);
+        assert!(<Expire as Command>::from(&[
+            Frame::Bulk("EXPIRE".to_string()),
+            Frame::Bulk("key".to_string()),
+            Frame::Bulk("soon".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_expire_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<Expire>(&mut session, &["EXPIRE", "key", "100"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(session.db().peek_value("key"), None);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::cmd::{parse_call, Command};
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// FCall implements `FCALL function numkeys [key ...] [arg ...]`, which calls a function loaded
+/// with FUNCTION LOAD. The function receives the keys followed by the arguments.
+pub struct FCall {
+    function: String,
+    keys: Vec<String>,
+    args: Vec<String>,
+}
+
+impl Command for FCall {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let keys_and_args = self
+            .keys
+            .iter()
+            .chain(&self.args)
+            .map(|value| Frame::Bulk(value.clone()))
+            .collect();
+        match session.functions().call(&self.function, keys_and_args) {
+            Ok(frame) => frame,
+            Err(err) => Frame::Error(format!("ERR {}", err)),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let (function, keys, args) = parse_call(frames)?;
+        Ok(FCall {
+            function,
+            keys,
+            args,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_fcall() {
+        let mut session = test_session();
+        let code = r#"{"name": "mylib", "functions": {"shout": "upper", "count": "len"}}"#;
+        session.functions().load(code, false).unwrap();
+
+        let fcall = <FCall as Command>::from(
+            &["FCALL", "shout", "1", "key", "hello"]
+                .iter()
+                .map(|v| Frame::Bulk(v.to_string()))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(fcall.keys(), ["key"]);
+        assert_eq!(
+            run_command::<FCall>(&mut session, &["FCALL", "shout", "1", "key", "hello"]),
+            Frame::Array(vec![
+                Frame::Bulk("KEY".to_string()),
+                Frame::Bulk("HELLO".to_string())
+            ])
+        );
+        assert_eq!(
+            run_command::<FCall>(&mut session, &["FCALL", "count", "0", "a", "b"]),
+            Frame::Integer(2)
+        );
+        assert_eq!(
+            run_command::<FCall>(&mut session, &["FCALL", "missing", "0"]),
+            Frame::Error("ERR Function not found".to_string())
+        );
+        assert!(<FCall as Command>::from(
+            &["FCALL", "count", "3", "a"]
+                .iter()
+                .map(|v| Frame::Bulk(v.to_string()))
+                .collect::<Vec<_>>()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+enum FunctionSubCmd {
+    Load { code: String, replace: bool },
+    List,
+    Delete(String),
+}
+
+/// Function implements FUNCTION LOAD, LIST and DELETE, which manage the libraries called by FCALL.
+pub struct Function {
+    sub_cmd: FunctionSubCmd,
+}
+
+impl Command for Function {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let functions = session.functions();
+        match &self.sub_cmd {
+            FunctionSubCmd::Load { code, replace } => match functions.load(code, *replace) {
+                Ok(name) => Frame::Bulk(name),
+                Err(err) => Frame::Error(format!("ERR {}", err)),
+            },
+            FunctionSubCmd::List => {
+                Frame::Array(functions.list().into_iter().map(Frame::Bulk).collect())
+            }
+            FunctionSubCmd::Delete(name) => match functions.delete(name) {
+                Ok(()) => Frame::Simple("OK".to_string()),
+                Err(err) => Frame::Error(format!("ERR {}", err)),
+            },
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(frames.len());
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.as_str()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        let Some(sub_cmd_name) = args.first().map(|name| name.to_uppercase()) else {
+            return Err(CommandError::Malformed(
+                "FUNCTION command requires a sub-command".to_string(),
+            ));
+        };
+        let sub_cmd = match (sub_cmd_name.as_str(), &args[1..]) {
+            ("LOAD", [code]) => FunctionSubCmd::Load {
+                code: code.to_string(),
+                replace: false,
+            },
+            ("LOAD", [replace, code]) if replace.eq_ignore_ascii_case("REPLACE") => {
+                FunctionSubCmd::Load {
+                    code: code.to_string(),
+                    replace: true,
+                }
+            }
+            ("LIST", []) => FunctionSubCmd::List,
+            ("DELETE", [name]) => FunctionSubCmd::Delete(name.to_string()),
+            ("LOAD" | "LIST" | "DELETE", _) => {
+                return Err(CommandError::Malformed(format!(
+                    "wrong number of arguments for FUNCTION {}",
+                    sub_cmd_name
+                )))
+            }
+            _ => return Err(CommandError::Unknown(format!("FUNCTION {}", sub_cmd_name))),
+        };
+        Ok(Function { sub_cmd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_function() {
+        let mut session = test_session();
+        let code = r#"{"name": "mylib", "functions": {"myecho": "echo"}}"#;
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "LOAD", code]),
+            Frame::Bulk("mylib".to_string())
+        );
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "LOAD", code]),
+            Frame::Error("ERR Library 'mylib' already exists".to_string())
+        );
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "LOAD", "replace", code]),
+            Frame::Bulk("mylib".to_string())
+        );
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "LIST"]),
+            Frame::Array(vec![Frame::Bulk("mylib".to_string())])
+        );
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "DELETE", "mylib"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Function>(&mut session, &["FUNCTION", "DELETE", "mylib"]),
+            Frame::Error("ERR Library not found".to_string())
+        );
+    }
+}
@@ -1,24 +1,26 @@
 use crate::cmd::Command;
-use crate::db::State;
 use crate::error;
 use crate::frame::Frame;
-use std::io::{BufWriter, Write};
-use std::sync::Arc;
+use crate::session::Session;
 
 pub struct Get {
     key: String,
 }
 
 impl Command for Get {
-    fn apply<T: Write>(&self, dest: &mut BufWriter<T>, cache: &Arc<State>) -> std::io::Result<()> {
-        let response_frame = match cache.get_value_by_key(&self.key) {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        match cache.get_value_by_key(&self.key) {
             Some(value) => Frame::Bulk(value.to_string()),
             None => Frame::Null,
-        };
-        response_frame.write_to(dest)
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
     }
 
-    fn from(frames: Vec<Frame>) -> Result<Self, error::CommandError> {
+    fn from(frames: &[Frame]) -> Result<Self, error::CommandError> {
         // cmd name is included
         if frames.len() != 2 {
             return Err(error::CommandError::Malformed(
@@ -0,0 +1,104 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// GetRange implements `GETRANGE key start end`, which returns the bytes of a value between two
+/// offsets, both included. Negative offsets count from the end of the value, -1 being the last
+/// byte. A missing key reads as an empty value.
+/// It also implements SUBSTR, the name of the command before Redis 2.0.
+pub struct GetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+impl Command for GetRange {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let value = session.db().get_value_by_key(&self.key).unwrap_or_default();
+        Frame::Bulk(range(&value, self.start, self.end))
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(start), Frame::Bulk(end)] => {
+                let offset = |offset: &str| {
+                    offset.parse().map_err(|_| {
+                        CommandError::Malformed(
+                            "GETRANGE start and end must be integers".to_string(),
+                        )
+                    })
+                };
+                Ok(GetRange {
+                    key: key.clone(),
+                    start: offset(start)?,
+                    end: offset(end)?,
+                })
+            }
+            [_, _, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "GETRANGE command requires 3 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+/// range returns the bytes of `value` from `start` to `end` included, the way Redis does: the
+/// offsets are clamped to the value, and an empty string is returned when start is after end.
+fn range(value: &str, start: i64, end: i64) -> String {
+    let len = value.len() as i64;
+    let resolve = |offset: i64| if offset < 0 { len + offset } else { offset };
+    let start = resolve(start).max(0);
+    let end = resolve(end).min(len - 1);
+    if start > end {
+        return String::new();
+    }
+    // offsets are bytes, so a range may cut a multi-byte character
+    String::from_utf8_lossy(&value.as_bytes()[start as usize..=end as usize]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{execute, run_command, test_session};
+
+    #[test]
+    fn test_range() {
+        assert_eq!(range("This is a string", 0, 3), "This");
+        assert_eq!(range("This is a string", -3, -1), "ing");
+        assert_eq!(range("This is a string", 0, -1), "This is a string");
+        assert_eq!(range("This is a string", 10, 100), "string");
+        assert_eq!(range("This is a string", -100, 1), "Th");
+        assert_eq!(range("This is a string", 5, 2), "");
+        assert_eq!(range("", 0, -1), "");
+    }
+
+    #[test]
+    fn test_substr_is_getrange() {
+        let mut session = test_session();
+        session.db().set_kv("key", "This is a string", None);
+        assert_eq!(
+            run_command::<GetRange>(&mut session, &["GETRANGE", "key", "-3", "-1"]),
+            Frame::Bulk("ing".to_string())
+        );
+        for args in [
+            ["key", "0", "3"],
+            ["key", "-3", "-1"],
+            ["missing", "0", "-1"],
+        ] {
+            let reply = |cmd_name: &str, session: &mut Session| {
+                let mut frames = vec![Frame::Bulk(cmd_name.to_string())];
+                frames.extend(args.iter().map(|arg| Frame::Bulk(arg.to_string())));
+                execute(cmd_name, &frames, session)
+            };
+            assert_eq!(
+                reply("GETRANGE", &mut session),
+                reply("SUBSTR", &mut session)
+            );
+        }
+    }
+}
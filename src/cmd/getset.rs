@@ -0,0 +1,81 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Getset implements `GETSET key value`, which sets a key and returns its previous value, or nil
+/// when the key did not exist.
+pub struct Getset {
+    key: String,
+    value: String,
+}
+
+impl Command for Getset {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let db = session.db();
+        if self.key.len() > db.config().max_key_bytes() {
+            Frame::Error("ERR key too long".to_string())
+        } else if self.value.len() > db.config().max_value_bytes() {
+            Frame::Error("ERR value too large".to_string())
+        } else {
+            match db.get_and_set(&self.key, &self.value) {
+                Some(previous) => Frame::Bulk(previous),
+                None => Frame::Null,
+            }
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(value)] => Ok(Getset {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "GETSET command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_getset() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Getset>(&mut session, &["GETSET", "token", "first"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run_command::<Getset>(&mut session, &["GETSET", "token", "second"]),
+            Frame::Bulk("first".to_string())
+        );
+        assert_eq!(session.db().peek_value("token"), Some("second".to_string()));
+
+        // like SET, GETSET drops the expiration time of the key
+        session
+            .db()
+            .set_kv("token", "third", Some(std::time::Duration::from_secs(60)));
+        run_command::<Getset>(&mut session, &["GETSET", "token", "fourth"]);
+        assert_eq!(session.db().key_expiration("token"), None);
+    }
+
+    #[test]
+    fn test_getset_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "token", "first");
+        assert_eq!(
+            run_command::<Getset>(&mut session, &["GETSET", "token", "second"]),
+            Frame::Null
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+const DEFAULT_COUNT: usize = 10;
+
+/// HotKeys implements `HOTKEYS [count]`, which lists the keys of the current database read the most
+/// recently, with their number of reads. It helps finding the keys causing lock contention.
+pub struct HotKeys {
+    count: usize,
+}
+
+impl Command for HotKeys {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let hot_keys = session
+            .db()
+            .hot_keys(self.count)
+            .into_iter()
+            .map(|(key, reads)| Frame::Array(vec![Frame::Bulk(key), Frame::Integer(reads as i64)]))
+            .collect();
+        Frame::Array(hot_keys)
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [] => Ok(HotKeys {
+                count: DEFAULT_COUNT,
+            }),
+            [Frame::Bulk(count)] => {
+                let count = count.parse().map_err(|_| {
+                    CommandError::Malformed("HOTKEYS count must be a positive integer".to_string())
+                })?;
+                Ok(HotKeys { count })
+            }
+            [_] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "HOTKEYS command accepts an optional count".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_hotkeys() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<HotKeys>(&mut session, &["HOTKEYS"]),
+            Frame::Array(vec![])
+        );
+        session.db().set_kv("key", "value", None);
+        for _ in 0..10_000 {
+            session.db().get_value_by_key("key");
+        }
+        assert_eq!(
+            run_command::<HotKeys>(&mut session, &["HOTKEYS", "5"]),
+            Frame::Array(vec![Frame::Array(vec![
+                Frame::Bulk("key".to_string()),
+                Frame::Integer(10_000)
+            ])])
+        );
+        assert!(<HotKeys as Command>::from(&[
+            Frame::Bulk("HOTKEYS".to_string()),
+            Frame::Bulk("many".to_string()),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Incr implements `INCR key`, which adds 1 to the integer stored at a key and returns the new
+/// value. A missing key counts as 0.
+pub struct Incr {
+    key: String,
+}
+
+impl Command for Incr {
+    fn apply(&self, session: &mut Session) -> Frame {
+        incr_by(session, &self.key, 1)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key)] => Ok(Incr { key: key.clone() }),
+            [_] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "INCR command requires 1 argument".to_string(),
+            )),
+        }
+    }
+}
+
+/// IncrBy implements `INCRBY key increment`, which adds `increment` to the integer stored at a
+/// key and returns the new value. A missing key counts as 0.
+pub struct IncrBy {
+    key: String,
+    increment: i64,
+}
+
+impl Command for IncrBy {
+    fn apply(&self, session: &mut Session) -> Frame {
+        incr_by(session, &self.key, self.increment)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(increment)] => Ok(IncrBy {
+                key: key.clone(),
+                increment: increment.parse().map_err(|_| {
+                    CommandError::Malformed("INCRBY increment must be an integer".to_string())
+                })?,
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "INCRBY command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+/// incr_by increments a key of the current database and builds the reply: the new value, or an
+/// error when the value is not an integer or the result would overflow.
+pub(super) fn incr_by(session: &Session, key: &str, delta: i64) -> Frame {
+    match session.db().incr_by(key, delta) {
+        Ok(value) => Frame::Integer(value),
+        Err(CommandError::Malformed(message)) => Frame::Error(format!("ERR {}", message)),
+        Err(err) => Frame::Error(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_incr() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Incr>(&mut session, &["INCR", "counter"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run_command::<IncrBy>(&mut session, &["INCRBY", "counter", "-11"]),
+            Frame::Integer(-10)
+        );
+        assert_eq!(session.db().peek_value("counter"), Some("-10".to_string()));
+        session.db().set_kv("text", "abc", None);
+        assert_eq!(
+            run_command::<Incr>(&mut session, &["INCR", "text"]),
+            Frame::Error("ERR value is not an integer or out of range".to_string())
+        );
+        assert!(<IncrBy as Command>::from(&[
+            Frame::Bulk("INCRBY".to_string()),
+            Frame::Bulk("counter".to_string()),
+            Frame::Bulk("one".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_incr_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "counter", "41");
+        assert_eq!(
+            run_command::<Incr>(&mut session, &["INCR", "counter"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(session.db().key_expiration("counter"), None);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::info::{format_section, InfoCollector};
+use crate::session::Session;
+
+/// Info implements INFO. Without argument, or with `default`, `all` or `everything`, every
+/// section is returned. Unknown sections are ignored, as Redis does.
+pub struct Info {
+    sections: Vec<String>,
+}
+
+impl Command for Info {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let collectors: [&dyn InfoCollector; 3] = [
+            session.thread_pool(),
+            session.clients().as_ref(),
+            session.command_stats().as_ref(),
+        ];
+        let all = self.sections.is_empty()
+            || self
+                .sections
+                .iter()
+                .any(|section| matches!(section.as_str(), "default" | "all" | "everything"));
+
+        let sections: Vec<String> = collectors
+            .iter()
+            .filter(|collector| all || self.sections.iter().any(|s| s == collector.section()))
+            .map(|collector| format_section(*collector))
+            .collect();
+        Frame::Bulk(sections.join("\r\n"))
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut sections = Vec::with_capacity(frames.len().saturating_sub(1));
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(section) => sections.push(section.to_lowercase()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        Ok(Info { sections })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+
+    // The INFO payload spans several lines, so the response is checked in its encoded form.
+    fn info(session: &mut Session, args: &[&str]) -> String {
+        let frames: Vec<Frame> = args
+            .iter()
+            .map(|arg| Frame::Bulk(arg.to_string()))
+            .collect();
+        let cmd = <Info as Command>::from(&frames).unwrap();
+        String::from_utf8(cmd.apply(session).encode()).unwrap()
+    }
+
+    #[test]
+    fn test_info_clients() {
+        let mut session = test_session();
+        for args in [&["INFO"][..], &["INFO", "CLIENTS"], &["INFO", "all"]] {
+            let response = info(&mut session, args);
+            assert!(response.contains("\r\n# Clients\r\n"), "{}", response);
+            assert!(
+                response.contains("\r\nconnected_clients:1\r\n"),
+                "{}",
+                response
+            );
+            assert!(
+                response.contains("\r\nmaxclients:10000\r\n"),
+                "{}",
+                response
+            );
+        }
+        assert_eq!(info(&mut session, &["INFO", "keyspace"]), "$0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_info_server() {
+        let mut session = test_session();
+        let response = info(&mut session, &["INFO", "server"]);
+        assert!(
+            response.contains("# Server\r\nthread_pool_workers:0\r\nthread_pool_jobs_processed:0\r\nthread_pool_panics:0\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn test_info_commandstats() {
+        let mut session = test_session();
+        for _ in 0..2 {
+            let frames = vec![Frame::Bulk("PING".to_string())];
+            crate::cmd::execute("PING", &frames, &mut session);
+        }
+        let response = info(&mut session, &["INFO", "commandstats"]);
+        assert!(
+            response.contains("# Commandstats\r\ncmdstat_ping:calls=2,usec="),
+            "{}",
+            response
+        );
+        assert!(!response.contains("cmdstat_get"), "{}", response);
+    }
+}
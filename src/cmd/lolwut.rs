@@ -0,0 +1,120 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_VERSION: u64 = 5;
+const MAZE_COLUMNS: usize = 66;
+const MAZE_ROWS: usize = 16;
+
+/// Lolwut implements `LOLWUT [VERSION n]`, which draws some art followed by the server version.
+/// Only version 5 has art: a random maze. Other versions only print the server version.
+pub struct Lolwut {
+    version: u64,
+}
+
+impl Command for Lolwut {
+    fn apply(&self, _: &mut Session) -> Frame {
+        let art = if self.version == 5 {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            draw_maze(&mut Xorshift::new(seed), MAZE_COLUMNS, MAZE_ROWS)
+        } else {
+            String::new()
+        };
+        Frame::Bulk(format!("{}{}", art, version_line()))
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let malformed =
+            || CommandError::Malformed("LOLWUT accepts an optional VERSION <number>".to_string());
+        match &frames[1..] {
+            [] => Ok(Lolwut {
+                version: DEFAULT_VERSION,
+            }),
+            [Frame::Bulk(option), Frame::Bulk(version)]
+                if option.eq_ignore_ascii_case("VERSION") =>
+            {
+                let version = version.parse().map_err(|_| malformed())?;
+                Ok(Lolwut { version })
+            }
+            _ => Err(malformed()),
+        }
+    }
+}
+
+fn version_line() -> String {
+    format!("\nhtcache ver {}\n", env!("CARGO_PKG_VERSION"))
+}
+
+/// Xorshift is a small pseudo random generator, good enough to draw art.
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn new(seed: u64) -> Self {
+        // the generator never leaves 0, so it needs another seed
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
+
+/// draw_maze draws a maze with diagonal box-drawing characters, picked at random.
+fn draw_maze(rng: &mut Xorshift, columns: usize, rows: usize) -> String {
+    let mut maze = String::with_capacity((columns + 1) * rows * 3);
+    for _ in 0..rows {
+        for _ in 0..columns {
+            maze.push(if rng.next() & 1 == 0 { '╱' } else { '╲' });
+        }
+        maze.push('\n');
+    }
+    maze
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maze_is_seeded() {
+        let maze = draw_maze(&mut Xorshift::new(42), 8, 3);
+        assert_eq!(maze, draw_maze(&mut Xorshift::new(42), 8, 3));
+        assert_ne!(maze, draw_maze(&mut Xorshift::new(43), 8, 3));
+        let lines: Vec<&str> = maze.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.chars().count() == 8));
+        assert!(maze.chars().all(|c| matches!(c, '╱' | '╲' | '\n')));
+    }
+
+    #[test]
+    fn test_lolwut_versions() {
+        let frames = |args: &[&str]| -> Vec<Frame> {
+            args.iter()
+                .map(|arg| Frame::Bulk(arg.to_string()))
+                .collect()
+        };
+        let lolwut = <Lolwut as Command>::from(&frames(&["LOLWUT"])).unwrap();
+        assert_eq!(lolwut.version, 5);
+        let lolwut = <Lolwut as Command>::from(&frames(&["LOLWUT", "version", "6"])).unwrap();
+        assert_eq!(lolwut.version, 6);
+        assert!(<Lolwut as Command>::from(&frames(&["LOLWUT", "VERSION", "x"])).is_err());
+
+        let mut session = crate::cmd::test_session();
+        let response = String::from_utf8(lolwut.apply(&mut session).encode()).unwrap();
+        assert!(response.ends_with(&format!("{}\r\n", version_line())));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Mget implements `MGET key [key ...]`, which returns the values of several keys in one call.
+/// The reply holds a value per key, in the order of the keys, and nil for the missing ones.
+pub struct Mget {
+    keys: Vec<String>,
+}
+
+impl Command for Mget {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        let values = self
+            .keys
+            .iter()
+            .map(|key| match cache.get_value_by_key(key) {
+                Some(value) => Frame::Bulk(value),
+                None => Frame::Null,
+            })
+            .collect();
+        Frame::Array(values)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.keys.iter().map(String::as_str).collect()
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() < 2 {
+            return Err(CommandError::Malformed(
+                "MGET command requires at least one key".to_string(),
+            ));
+        }
+        let mut keys = Vec::with_capacity(frames.len() - 1);
+        for frame in &frames[1..] {
+            match frame {
+                Frame::Bulk(key) => keys.push(key.clone()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        Ok(Mget { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_mget() {
+        let mut session = test_session();
+        session.db().set_kv("a", "1", None);
+        session.db().set_kv("c", "3", None);
+        assert_eq!(
+            run_command::<Mget>(&mut session, &["MGET", "a", "b", "c", "a"]),
+            Frame::Array(vec![
+                Frame::Bulk("1".to_string()),
+                Frame::Null,
+                Frame::Bulk("3".to_string()),
+                Frame::Bulk("1".to_string()),
+            ])
+        );
+        assert!(<Mget as Command>::from(&[Frame::Bulk("MGET".to_string())]).is_err());
+    }
+}
@@ -8,23 +8,106 @@ pub use del::Del;
 mod set;
 
 pub use set::Set;
+mod object;
+pub use object::Object;
+mod debug;
+pub use debug::Debug;
+mod config;
+pub use config::Config;
+mod info;
+pub use info::Info;
+mod acl;
+pub use acl::Acl;
+mod auth;
+pub use auth::Auth;
+mod command;
+pub use command::CommandCmd;
+pub mod docs;
+mod lolwut;
+pub use lolwut::Lolwut;
+mod swapdb;
+pub use swapdb::SwapDb;
+mod select;
+pub use select::Select;
+mod reset;
+pub use reset::Reset;
+mod shutdown;
+pub use shutdown::Shutdown;
+mod move_cmd;
+pub use move_cmd::Move;
+mod copy;
+pub use copy::CopyCmd;
+mod getrange;
+pub use getrange::GetRange;
+mod mget;
+pub use mget::Mget;
+mod mset;
+pub use mset::Mset;
+mod incr;
+pub use incr::{Incr, IncrBy};
+mod decr;
+pub use decr::{Decr, DecrBy};
+mod append;
+pub use append::Append;
+mod setnx;
+pub use setnx::Setnx;
+mod getset;
+pub use getset::Getset;
+mod ttl;
+pub use ttl::{Pttl, Ttl};
+mod expire;
+pub use expire::{Expire, ExpireAt, PExpire, PExpireAt};
+mod wait;
+pub use wait::Wait;
+mod cluster;
+pub use cluster::Cluster;
+#[cfg(feature = "commands-scripting")]
+mod function;
+#[cfg(feature = "commands-scripting")]
+pub use function::Function;
+#[cfg(feature = "commands-scripting")]
+mod fcall;
+#[cfg(feature = "commands-scripting")]
+pub use fcall::FCall;
+mod hotkeys;
+pub use hotkeys::HotKeys;
+#[cfg(feature = "lua")]
+mod eval;
+#[cfg(feature = "lua")]
+pub use eval::Eval;
+#[cfg(feature = "lua")]
+mod script;
+#[cfg(feature = "lua")]
+pub use script::Script;
 
+use crate::error;
 use crate::frame::Frame;
-use crate::{db, error};
-use std::io;
-use std::io::{BufWriter, Write};
-use std::sync::Arc;
+use crate::session::Session;
+use std::time::Instant;
+use tracing::debug;
+#[cfg(debug_assertions)]
+use tracing::warn;
 use Frame::Bulk;
 
 /// Command represents a htcache command
 pub(crate) trait Command {
-    // apply applies the command
-    // @TODO: This method should take DB and Writer as args.
-    // Will do after I define them.
-    fn apply<T: Write>(&self, dest: &mut BufWriter<T>, cache: &Arc<db::State>) -> io::Result<()>;
+    /// apply applies the command within the session of the client and returns its reply.
+    fn apply(&self, session: &mut Session) -> Frame;
+
+    /// replies returns false for the commands whose reply is not sent, like SHUTDOWN which closes
+    /// the connection instead.
+    fn replies(&self) -> bool {
+        true
+    }
+
+    /// keys returns the keys the command accesses. They are checked against the ACL of the user
+    /// and returned by COMMAND GETKEYS.
+    fn keys(&self) -> Vec<&str> {
+        Vec::new()
+    }
 
     /// from read forms the command from a frame
-    fn from(frames: Vec<Frame>) -> Result<Self, error::CommandError>
+    fn from(frames: &[Frame]) -> Result<Self, error::CommandError>
     where
         Self: Sized;
 }
@@ -45,3 +128,181 @@ pub fn parse_frame(frame: Frame) -> Result<(String, Vec<Frame>), error::CommandE
         _ => Err(error::CommandError::NotCmdFrame),
     }
 }
+
+/// parse_call reads the arguments shared by FCALL, EVAL and EVALSHA: what to call, followed by
+/// `numkeys [key ...] [arg ...]`. It returns what to call, the keys and the arguments.
+#[cfg(feature = "commands-scripting")]
+pub(crate) fn parse_call(
+    frames: &[Frame],
+) -> Result<(String, Vec<String>, Vec<String>), error::CommandError> {
+    let cmd_name = match frames.first() {
+        Some(Bulk(name)) => name.to_uppercase(),
+        _ => return Err(error::CommandError::InvalidCmdFrame),
+    };
+    let mut args = Vec::with_capacity(frames.len());
+    for frame in frames.iter().skip(1) {
+        match frame {
+            Bulk(value) => args.push(value.clone()),
+            _ => return Err(error::CommandError::InvalidCmdFrame),
+        }
+    }
+    if args.len() < 2 {
+        return Err(error::CommandError::Malformed(format!(
+            "{} command requires at least 2 arguments",
+            cmd_name
+        )));
+    }
+    let numkeys: usize = args[1].parse().map_err(|_| {
+        error::CommandError::Malformed(format!("{} numkeys must be a positive integer", cmd_name))
+    })?;
+    if numkeys > args.len() - 2 {
+        return Err(error::CommandError::Malformed(
+            "Number of keys can't be greater than number of args".to_string(),
+        ));
+    }
+    let mut args = args.into_iter();
+    let target = args.next().unwrap_or_default();
+    let mut args = args.skip(1);
+    let keys = args.by_ref().take(numkeys).collect();
+    Ok((target, keys, args.collect()))
+}
+
+/// parse builds the command named `cmd_name` from its frames.
+pub(crate) fn parse(
+    cmd_name: &str,
+    frames: &[Frame],
+) -> Result<Box<dyn Command>, error::CommandError> {
+    fn build<Cmd: Command + 'static>(
+        frames: &[Frame],
+    ) -> Result<Box<dyn Command>, error::CommandError> {
+        Ok(Box::new(Cmd::from(frames)?))
+    }
+
+    match cmd_name {
+        "PING" => build::<Ping>(frames),
+        "SET" => build::<Set>(frames),
+        "GET" => build::<Get>(frames),
+        "DEL" => build::<Del>(frames),
+        "GETRANGE" => build::<GetRange>(frames),
+        "MGET" => build::<Mget>(frames),
+        "MSET" => build::<Mset>(frames),
+        "INCR" => build::<Incr>(frames),
+        "INCRBY" => build::<IncrBy>(frames),
+        "DECR" => build::<Decr>(frames),
+        "DECRBY" => build::<DecrBy>(frames),
+        "APPEND" => build::<Append>(frames),
+        "SETNX" => build::<Setnx>(frames),
+        "GETSET" => build::<Getset>(frames),
+        "TTL" => build::<Ttl>(frames),
+        "PTTL" => build::<Pttl>(frames),
+        "EXPIRE" => build::<Expire>(frames),
+        "PEXPIRE" => build::<PExpire>(frames),
+        "EXPIREAT" => build::<ExpireAt>(frames),
+        "PEXPIREAT" => build::<PExpireAt>(frames),
+        "SUBSTR" => {
+            // only in debug builds, production logs are not filled with it
+            #[cfg(debug_assertions)]
+            warn!("SUBSTR is deprecated, use GETRANGE");
+            build::<GetRange>(frames)
+        }
+        "OBJECT" => build::<Object>(frames),
+        "DEBUG" => build::<Debug>(frames),
+        "CONFIG" => build::<Config>(frames),
+        "INFO" => build::<Info>(frames),
+        "ACL" => build::<Acl>(frames),
+        "AUTH" => build::<Auth>(frames),
+        "COMMAND" => build::<CommandCmd>(frames),
+        "LOLWUT" => build::<Lolwut>(frames),
+        "SWAPDB" => build::<SwapDb>(frames),
+        "SELECT" => build::<Select>(frames),
+        "RESET" => build::<Reset>(frames),
+        "MOVE" => build::<Move>(frames),
+        "COPY" => build::<CopyCmd>(frames),
+        "WAIT" => build::<Wait>(frames),
+        "CLUSTER" => build::<Cluster>(frames),
+        #[cfg(feature = "commands-scripting")]
+        "FUNCTION" => build::<Function>(frames),
+        #[cfg(feature = "commands-scripting")]
+        "FCALL" => build::<FCall>(frames),
+        "SHUTDOWN" => build::<Shutdown>(frames),
+        "HOTKEYS" => build::<HotKeys>(frames),
+        #[cfg(feature = "lua")]
+        "EVAL" | "EVALSHA" => build::<Eval>(frames),
+        #[cfg(feature = "lua")]
+        "SCRIPT" => build::<Script>(frames),
+        _ => Err(error::CommandError::Unknown(cmd_name.to_string())),
+    }
+}
+
+/// apply applies a parsed command and returns its reply, None when the command replies nothing.
+/// The calls of the commands applied, and the time they took, are counted in the command stats.
+/// The commands accessing keys go through the circuit breaker of the database: a panic is replied
+/// as an error, and they are rejected while the circuit is open.
+pub(crate) fn apply(cmd_name: &str, command: &dyn Command, session: &mut Session) -> Option<Frame> {
+    let started_at = Instant::now();
+    let response = if command.keys().is_empty() {
+        command.apply(session)
+    } else {
+        let db = session.db().clone();
+        db.circuit_breaker()
+            .call(|| command.apply(session))
+            .unwrap_or_else(|e| Frame::Error(format!("ERR {}", e)))
+    };
+    session
+        .command_stats()
+        .record(cmd_name, started_at.elapsed());
+    command.replies().then_some(response)
+}
+
+/// execute builds the command named `cmd_name` from its frames and applies it. Invalid commands get
+/// an error frame as reply.
+/// Permissions are not checked here but by the `AclEnforcer` middleware.
+pub(crate) fn execute(cmd_name: &str, frames: &[Frame], session: &mut Session) -> Option<Frame> {
+    match parse(cmd_name, frames) {
+        Ok(command) => apply(cmd_name, command.as_ref(), session),
+        Err(err) => {
+            debug!("command processing failed: {}", err);
+            Some(Frame::Error(err.to_string()))
+        }
+    }
+}
+
+/// test_session creates a session on a small State for command tests.
+#[cfg(test)]
+pub(crate) fn test_session() -> Session {
+    use std::sync::Arc;
+
+    let cleanup_needed = Arc::new((std::sync::Mutex::new(false), std::sync::Condvar::new()));
+    let config = Arc::new(crate::config::ServerConfig::new(99).unwrap());
+    let clients = Arc::new(crate::clients::ClientRegistry::new(config.clone()));
+    let databases = (0..config.databases())
+        .map(|db_id| {
+            let storage = Box::new(crate::db::cmap::CMap::new(4, 256).unwrap());
+            let state =
+                crate::db::State::new(db_id, storage, 1024, cleanup_needed.clone(), config.clone());
+            Arc::new(state)
+        })
+        .collect();
+    let node = crate::cluster::Node::new("127.0.0.1:6379".parse().unwrap());
+    Session::new(
+        Arc::new(databases),
+        clients.register().unwrap(),
+        crate::session::ServerState::new(node),
+    )
+}
+
+/// set_expired sets a key which expires right away, for tests of commands on expired keys.
+#[cfg(test)]
+pub(crate) fn set_expired(session: &Session, key: &str, value: &str) {
+    let ttl = std::time::Duration::from_millis(1);
+    session.db().set_kv(key, value, Some(ttl));
+    std::thread::sleep(ttl * 5);
+}
+
+/// run_command builds a command from its arguments, applies it and returns its reply.
+#[cfg(test)]
+pub(crate) fn run_command<Cmd: Command>(session: &mut Session, args: &[&str]) -> Frame {
+    let frames: Vec<Frame> = args.iter().map(|arg| Bulk(arg.to_string())).collect();
+    let cmd = Cmd::from(&frames).unwrap_or_else(|e| panic!("invalid command {:?}: {}", args, e));
+    cmd.apply(session)
+}
@@ -0,0 +1,96 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Move implements `MOVE key db`, which moves a key from the current database to another one.
+/// It replies 1 when the key was moved, and 0 when it does not exist or already exists in `db`.
+pub struct Move {
+    key: String,
+    db: usize,
+}
+
+impl Command for Move {
+    fn apply(&self, session: &mut Session) -> Frame {
+        match session.databases().get(self.db) {
+            None => Frame::Error("ERR DB index is out of range".to_string()),
+            Some(db) if db.db_id() == session.db().db_id() => {
+                Frame::Error("ERR source and destination objects are the same".to_string())
+            }
+            Some(db) => Frame::Integer(session.db().move_key(&self.key, db) as i64),
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(db)] => Ok(Move {
+                key: key.clone(),
+                db: db.parse().map_err(|_| {
+                    CommandError::Malformed("MOVE db must be a positive integer".to_string())
+                })?,
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "MOVE command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_move() {
+        let mut session = test_session();
+        session.db().set_kv("key", "value", None);
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "2"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            session.databases()[2].peek_value("key"),
+            Some("value".to_string())
+        );
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "2"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "0"]),
+            Frame::Error("ERR source and destination objects are the same".to_string())
+        );
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "16"]),
+            Frame::Error("ERR DB index is out of range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_move_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "2"]),
+            Frame::Integer(0)
+        );
+        // an expired key of the destination does not block the move
+        session.db().set_kv("key", "value", None);
+        session.databases()[2].set_kv("key", "old", Some(std::time::Duration::from_millis(1)));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(
+            run_command::<Move>(&mut session, &["MOVE", "key", "2"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            session.databases()[2].peek_value("key"),
+            Some("value".to_string())
+        );
+    }
+}
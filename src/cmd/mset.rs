@@ -0,0 +1,97 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Mset implements `MSET key value [key value ...]`, which sets several keys at once. The keys
+/// are set together: other clients see either none or all of them.
+pub struct Mset {
+    pairs: Vec<(String, String)>,
+}
+
+impl Command for Mset {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        let (max_key_bytes, max_value_bytes) = (
+            cache.config().max_key_bytes(),
+            cache.config().max_value_bytes(),
+        );
+        // the limits are checked first, so that no key is set when one of them is exceeded
+        if self.pairs.iter().any(|(key, _)| key.len() > max_key_bytes) {
+            Frame::Error("ERR key too long".to_string())
+        } else if self
+            .pairs
+            .iter()
+            .any(|(_, value)| value.len() > max_value_bytes)
+        {
+            Frame::Error("ERR value too large".to_string())
+        } else {
+            cache.set_kvs(&self.pairs);
+            Frame::Simple("OK".to_string())
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.pairs.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() < 3 || frames.len().is_multiple_of(2) {
+            return Err(CommandError::Malformed(
+                "MSET command requires pairs of keys and values".to_string(),
+            ));
+        }
+        let mut pairs = Vec::with_capacity(frames.len() / 2);
+        for pair in frames[1..].chunks(2) {
+            match pair {
+                [Frame::Bulk(key), Frame::Bulk(value)] => pairs.push((key.clone(), value.clone())),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        Ok(Mset { pairs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_mset() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Mset>(&mut session, &["MSET", "a", "1", "b", "2", "a", "3"]),
+            Frame::Simple("OK".to_string())
+        );
+        // pairs are set in order, so the last value of a key wins
+        assert_eq!(session.db().peek_value("a"), Some("3".to_string()));
+        assert_eq!(session.db().peek_value("b"), Some("2".to_string()));
+
+        // like SET, MSET drops the expiration time of the keys it overwrites
+        session
+            .db()
+            .set_kv("a", "1", Some(std::time::Duration::from_secs(60)));
+        run_command::<Mset>(&mut session, &["MSET", "a", "4"]);
+        assert_eq!(session.db().key_expiration("a"), None);
+
+        session
+            .db()
+            .config()
+            .set(&[("max-value-bytes", "4")])
+            .unwrap();
+        assert_eq!(
+            run_command::<Mset>(&mut session, &["MSET", "c", "1", "d", "too long"]),
+            Frame::Error("ERR value too large".to_string())
+        );
+        assert_eq!(session.db().peek_value("c"), None);
+
+        let frames = |args: &[&str]| -> Vec<Frame> {
+            args.iter()
+                .map(|arg| Frame::Bulk(arg.to_string()))
+                .collect()
+        };
+        assert!(<Mset as Command>::from(&frames(&["MSET", "a"])).is_err());
+        assert!(<Mset as Command>::from(&frames(&["MSET", "a", "1", "b"])).is_err());
+    }
+}
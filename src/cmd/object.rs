@@ -0,0 +1,236 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// ObjectHelpEntry describes a sub-command in the reply to OBJECT HELP.
+struct ObjectHelpEntry {
+    name: &'static str,
+    args: &'static str,
+    description: &'static str,
+}
+
+/// OBJECT_HELP lists the supported sub-commands, in the order of the reply to OBJECT HELP.
+const OBJECT_HELP: &[ObjectHelpEntry] = &[
+    ObjectHelpEntry {
+        name: "ENCODING",
+        args: "<key>",
+        description: "Return the kind of internal representation used to store the value of a key.",
+    },
+    ObjectHelpEntry {
+        name: "FREQ",
+        args: "<key>",
+        description: "Return the number of times a key was read or overwritten since its creation.",
+    },
+    ObjectHelpEntry {
+        name: "HELP",
+        args: "",
+        description: "Return this help.",
+    },
+    ObjectHelpEntry {
+        name: "IDLETIME",
+        args: "<key>",
+        description: "Return the number of seconds elapsed since the last access to a key.",
+    },
+    ObjectHelpEntry {
+        name: "REFCOUNT",
+        args: "<key>",
+        description: "Return the number of references to the value of a key.",
+    },
+];
+
+enum ObjectSubCmd {
+    Encoding(String),
+    Freq(String),
+    Help,
+    IdleTime(String),
+    RefCount(String),
+}
+
+pub struct Object {
+    sub_cmd: ObjectSubCmd,
+}
+
+impl Command for Object {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        // Introspection must not count as an access, so keys are read with peek_value and get_meta.
+        match &self.sub_cmd {
+            ObjectSubCmd::Help => help_frame(),
+            ObjectSubCmd::Encoding(key) => match cache.peek_value(key) {
+                Some(value) => Frame::Bulk(string_encoding(&value).to_string()),
+                None => Frame::Null,
+            },
+            ObjectSubCmd::Freq(key) => match cache.get_meta(key) {
+                Some(meta) => Frame::Integer(meta.access_count as i64),
+                None => Frame::Null,
+            },
+            ObjectSubCmd::IdleTime(key) => match cache.get_meta(key) {
+                Some(meta) => Frame::Integer(meta.last_access.elapsed().as_secs() as i64),
+                None => Frame::Null,
+            },
+            // Values are never shared between keys.
+            ObjectSubCmd::RefCount(key) => match cache.get_meta(key) {
+                Some(_) => Frame::Integer(1),
+                None => Frame::Null,
+            },
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match &self.sub_cmd {
+            ObjectSubCmd::Help => vec![],
+            ObjectSubCmd::Encoding(key)
+            | ObjectSubCmd::Freq(key)
+            | ObjectSubCmd::IdleTime(key)
+            | ObjectSubCmd::RefCount(key) => vec![key],
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        // As in Redis, a missing or unknown sub-command is answered with the help text.
+        let help = Object {
+            sub_cmd: ObjectSubCmd::Help,
+        };
+        let sub_cmd_name = match frames.get(1) {
+            Some(Frame::Bulk(name)) => name.to_uppercase(),
+            Some(_) => return Err(CommandError::InvalidCmdFrame),
+            None => return Ok(help),
+        };
+        if !matches!(
+            sub_cmd_name.as_str(),
+            "ENCODING" | "FREQ" | "IDLETIME" | "REFCOUNT"
+        ) {
+            return Ok(help);
+        }
+
+        if frames.len() != 3 {
+            return Err(CommandError::Malformed(format!(
+                "OBJECT {} requires 1 argument",
+                sub_cmd_name
+            )));
+        }
+        let key = match &frames[2] {
+            Frame::Bulk(key) => key.clone(),
+            _ => return Err(CommandError::InvalidCmdFrame),
+        };
+        let sub_cmd = match sub_cmd_name.as_str() {
+            "ENCODING" => ObjectSubCmd::Encoding(key),
+            "FREQ" => ObjectSubCmd::Freq(key),
+            "IDLETIME" => ObjectSubCmd::IdleTime(key),
+            _ => ObjectSubCmd::RefCount(key),
+        };
+        Ok(Object { sub_cmd })
+    }
+}
+
+/// help_frame answers OBJECT HELP with a line per sub-command, like
+/// `OBJECT FREQ <key> -- Return the number of times...`.
+fn help_frame() -> Frame {
+    Frame::Array(
+        OBJECT_HELP
+            .iter()
+            .map(|entry| {
+                let usage = if entry.args.is_empty() {
+                    format!("OBJECT {}", entry.name)
+                } else {
+                    format!("OBJECT {} {}", entry.name, entry.args)
+                };
+                Frame::Bulk(format!("{} -- {}", usage, entry.description))
+            })
+            .collect(),
+    )
+}
+
+/// string_encoding returns the encoding Redis would report for a string value:
+/// `int` for canonical 64 bits integers, `embstr` for short strings and `raw` for the others.
+pub(crate) fn string_encoding(value: &str) -> &'static str {
+    const EMBSTR_SIZE_LIMIT: usize = 44;
+    match value.parse::<i64>() {
+        Ok(number) if number.to_string() == value => "int",
+        _ if value.len() <= EMBSTR_SIZE_LIMIT => "embstr",
+        _ => "raw",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_object_help() {
+        let mut session = test_session();
+        let lines = match run_command::<Object>(&mut session, &["OBJECT", "help"]) {
+            Frame::Array(lines) => lines,
+            other => panic!("expected an array, got {:?}", other),
+        };
+        let names: Vec<&str> = lines
+            .iter()
+            .map(|line| match line {
+                Frame::Bulk(line) => line.split(' ').nth(1).unwrap(),
+                other => panic!("expected a bulk string, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(names, ["ENCODING", "FREQ", "HELP", "IDLETIME", "REFCOUNT"]);
+        assert_eq!(
+            lines[1],
+            Frame::Bulk(
+                "OBJECT FREQ <key> -- Return the number of times a key was read or overwritten \
+                 since its creation."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            lines[2],
+            Frame::Bulk("OBJECT HELP -- Return this help.".to_string())
+        );
+        let help = run_command::<Object>(&mut session, &["OBJECT", "help"]);
+        assert_eq!(run_command::<Object>(&mut session, &["OBJECT"]), help);
+        assert_eq!(
+            run_command::<Object>(&mut session, &["OBJECT", "nope", "key"]),
+            help
+        );
+        // known sub-commands still check their arguments
+        assert!(<Object as Command>::from(&[
+            Frame::Bulk("OBJECT".to_string()),
+            Frame::Bulk("FREQ".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_object_encoding() {
+        let mut session = test_session();
+        session.db().set_kv("int", "-1234", None);
+        session.db().set_kv("padded", "01234", None);
+        session.db().set_kv("short", "hello", None);
+        session.db().set_kv("long", &"a".repeat(45), None);
+
+        let mut encoding = |key| run_command::<Object>(&mut session, &["OBJECT", "ENCODING", key]);
+        assert_eq!(encoding("int"), Frame::Bulk("int".to_string()));
+        assert_eq!(encoding("padded"), Frame::Bulk("embstr".to_string()));
+        assert_eq!(encoding("short"), Frame::Bulk("embstr".to_string()));
+        assert_eq!(encoding("long"), Frame::Bulk("raw".to_string()));
+        assert_eq!(encoding("missing"), Frame::Null);
+        // introspection is not an access
+        assert_eq!(
+            run_command::<Object>(&mut session, &["OBJECT", "FREQ", "int"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_object_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "key", "value");
+        assert_eq!(
+            run_command::<Object>(&mut session, &["OBJECT", "FREQ", "key"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run_command::<Object>(&mut session, &["OBJECT", "ENCODING", "key"]),
+            Frame::Null
+        );
+    }
+}
@@ -1,25 +1,22 @@
 use crate::cmd::Command;
-use crate::db::State;
 use crate::error;
 use crate::frame::Frame;
-use std::io::{BufWriter, Write};
-use std::sync::Arc;
+use crate::session::Session;
 
 pub struct Ping {
     message: Option<String>,
 }
 
 impl Command for Ping {
-    fn apply<T: Write>(&self, dest: &mut BufWriter<T>, _: &Arc<State>) -> std::io::Result<()> {
-        let response = if self.message.is_none() {
+    fn apply(&self, _: &mut Session) -> Frame {
+        if self.message.is_none() {
             Frame::Simple("PONG".into())
         } else {
             Frame::Bulk(self.message.clone().unwrap())
-        };
-        response.write_to(dest)
+        }
     }
 
-    fn from(frames: Vec<Frame>) -> Result<Self, error::CommandError> {
+    fn from(frames: &[Frame]) -> Result<Self, error::CommandError> {
         let len = frames.len();
         if len > 2 {
             return Err(error::CommandError::Malformed(
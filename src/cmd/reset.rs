@@ -0,0 +1,54 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Reset implements `RESET`, which puts the connection back in the state of a new one, without
+/// closing it: the session goes back to the first database and to the default user.
+/// It replies with the simple string RESET.
+pub struct Reset;
+
+impl Command for Reset {
+    fn apply(&self, session: &mut Session) -> Frame {
+        session.reset();
+        Frame::Simple("RESET".to_string())
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() != 1 {
+            return Err(CommandError::Malformed(
+                "RESET command takes no argument".to_string(),
+            ));
+        }
+        Ok(Reset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session, Acl, Auth, Select};
+
+    #[test]
+    fn test_reset() {
+        let mut session = test_session();
+        run_command::<Acl>(
+            &mut session,
+            &["ACL", "SETUSER", "alice", "on", ">secret", "+@all"],
+        );
+        run_command::<Auth>(&mut session, &["AUTH", "alice", "secret"]);
+        run_command::<Select>(&mut session, &["SELECT", "3"]);
+        assert_eq!((session.user(), session.db().db_id()), ("alice", 3));
+
+        assert_eq!(
+            run_command::<Reset>(&mut session, &["RESET"]),
+            Frame::Simple("RESET".to_string())
+        );
+        assert_eq!((session.user(), session.db().db_id()), ("default", 0));
+        // the connection keeps working after a reset
+        assert_eq!(
+            run_command::<Select>(&mut session, &["SELECT", "1"]),
+            Frame::Simple("OK".to_string())
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::scripting;
+use crate::session::Session;
+
+enum ScriptSubCmd {
+    Load(String),
+    Exists(Vec<String>),
+    Flush,
+}
+
+/// Script implements SCRIPT LOAD, EXISTS and FLUSH, which manage the scripts run by EVALSHA.
+pub struct Script {
+    sub_cmd: ScriptSubCmd,
+}
+
+impl Command for Script {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let scripts = session.scripts();
+        match &self.sub_cmd {
+            ScriptSubCmd::Load(script) => match scripting::compile(script) {
+                Ok(()) => Frame::Bulk(scripts.insert(script)),
+                Err(err) => Frame::Error(err),
+            },
+            ScriptSubCmd::Exists(shas) => Frame::Array(
+                shas.iter()
+                    .map(|sha| Frame::Integer(scripts.contains(sha) as i64))
+                    .collect(),
+            ),
+            ScriptSubCmd::Flush => {
+                scripts.flush();
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        let mut args = Vec::with_capacity(frames.len());
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(value) => args.push(value.clone()),
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        if args.is_empty() {
+            return Err(CommandError::Malformed(
+                "SCRIPT command requires a sub-command".to_string(),
+            ));
+        }
+        let sub_cmd_name = args.remove(0).to_uppercase();
+        let sub_cmd = match (sub_cmd_name.as_str(), args.len()) {
+            ("LOAD", 1) => ScriptSubCmd::Load(args.remove(0)),
+            ("EXISTS", 1..) => ScriptSubCmd::Exists(args),
+            // scripts are flushed right away, ASYNC and SYNC are accepted for compatibility
+            ("FLUSH", 0) => ScriptSubCmd::Flush,
+            ("FLUSH", 1)
+                if args[0].eq_ignore_ascii_case("ASYNC")
+                    || args[0].eq_ignore_ascii_case("SYNC") =>
+            {
+                ScriptSubCmd::Flush
+            }
+            ("LOAD" | "EXISTS" | "FLUSH", _) => {
+                return Err(CommandError::Malformed(format!(
+                    "wrong number of arguments for SCRIPT {}",
+                    sub_cmd_name
+                )))
+            }
+            _ => return Err(CommandError::Unknown(format!("SCRIPT {}", sub_cmd_name))),
+        };
+        Ok(Script { sub_cmd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session, Eval};
+
+    #[test]
+    fn test_script() {
+        let mut session = test_session();
+        let script = "return 42";
+        let sha = scripting::sha1_hex(script);
+        assert_eq!(
+            run_command::<Script>(&mut session, &["SCRIPT", "LOAD", script]),
+            Frame::Bulk(sha.clone())
+        );
+        assert_eq!(
+            run_command::<Script>(&mut session, &["SCRIPT", "EXISTS", &sha, "nope"]),
+            Frame::Array(vec![Frame::Integer(1), Frame::Integer(0)])
+        );
+        assert_eq!(
+            run_command::<Eval>(&mut session, &["EVALSHA", &sha, "0"]),
+            Frame::Integer(42)
+        );
+        assert_eq!(
+            run_command::<Script>(&mut session, &["SCRIPT", "FLUSH", "async"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            run_command::<Script>(&mut session, &["SCRIPT", "EXISTS", &sha]),
+            Frame::Array(vec![Frame::Integer(0)])
+        );
+
+        let reply = run_command::<Script>(&mut session, &["SCRIPT", "LOAD", "return +"]);
+        assert!(
+            matches!(&reply, Frame::Error(e) if e.starts_with("ERR Error compiling script")),
+            "{:?}",
+            reply
+        );
+        assert!(<Script as Command>::from(&[
+            Frame::Bulk("SCRIPT".to_string()),
+            Frame::Bulk("KILL".to_string()),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Select implements `SELECT index`, which switches the database used by the connection.
+pub struct Select {
+    index: usize,
+}
+
+impl Command for Select {
+    fn apply(&self, session: &mut Session) -> Frame {
+        if session.select(self.index) {
+            Frame::Simple("OK".to_string())
+        } else {
+            Frame::Error("ERR DB index is out of range".to_string())
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() != 2 {
+            return Err(CommandError::Malformed(
+                "SELECT command requires 1 argument".to_string(),
+            ));
+        }
+        match &frames[1] {
+            Frame::Bulk(index) => Ok(Select {
+                index: index.parse().map_err(|_| {
+                    CommandError::Malformed("SELECT index must be a positive integer".to_string())
+                })?,
+            }),
+            _ => Err(CommandError::InvalidCmdFrame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session, Get, Set};
+
+    #[test]
+    fn test_select() {
+        let mut session = test_session();
+        run_command::<Set>(&mut session, &["SET", "key", "zero"]);
+        assert_eq!(
+            run_command::<Select>(&mut session, &["SELECT", "3"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(session.db().db_id(), 3);
+        assert_eq!(
+            run_command::<Get>(&mut session, &["GET", "key"]),
+            Frame::Null
+        );
+        run_command::<Set>(&mut session, &["SET", "key", "three"]);
+
+        assert_eq!(
+            run_command::<Select>(&mut session, &["SELECT", "16"]),
+            Frame::Error("ERR DB index is out of range".to_string())
+        );
+        assert_eq!(session.db().db_id(), 3);
+
+        run_command::<Select>(&mut session, &["SELECT", "0"]);
+        assert_eq!(
+            run_command::<Get>(&mut session, &["GET", "key"]),
+            Frame::Bulk("zero".to_string())
+        );
+        assert!(<Select as Command>::from(&[
+            Frame::Bulk("SELECT".to_string()),
+            Frame::Bulk("-1".to_string()),
+        ])
+        .is_err());
+    }
+}
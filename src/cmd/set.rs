@@ -1,43 +1,199 @@
 use crate::cmd::Command;
-use crate::db::State;
 use crate::error;
 use crate::frame::Frame;
-use std::io::{BufWriter, Write};
-use std::sync::Arc;
+use crate::session::Session;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+/// Set implements `SET key value [NX | XX] [EX seconds | PX milliseconds | EXAT unix-time-seconds
+/// | PXAT unix-time-milliseconds]`. NX only sets a key which does not exist, XX only a key which
+/// exists; the reply is nil when the key was not set.
 pub struct Set {
     key: String,
     value: String,
-    // ttl: Option<Duration>,
+    ttl: Option<Duration>,
+    nx: bool,
+    xx: bool,
 }
 
 impl Command for Set {
-    fn apply<T: Write>(&self, dest: &mut BufWriter<T>, cache: &Arc<State>) -> std::io::Result<()> {
-        cache.set_kv(&self.key, &self.value, None);
-        let response = Frame::Simple("OK".into());
-        response.write_to(dest)
+    fn apply(&self, session: &mut Session) -> Frame {
+        let cache = session.db();
+        if self.key.len() > cache.config().max_key_bytes() {
+            Frame::Error("ERR key too long".to_string())
+        } else if self.value.len() > cache.config().max_value_bytes() {
+            Frame::Error("ERR value too large".to_string())
+        } else if cache.set_kv_with_options(&self.key, &self.value, self.ttl, self.nx, self.xx) {
+            Frame::Simple("OK".into())
+        } else {
+            Frame::Null
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
     }
 
-    fn from(frames: Vec<Frame>) -> Result<Self, error::CommandError> {
-        if frames.len() != 3 {
+    fn from(frames: &[Frame]) -> Result<Self, error::CommandError> {
+        let mut args = Vec::with_capacity(frames.len() - 1);
+        for frame in &frames[1..] {
+            match frame {
+                Frame::Bulk(arg) => args.push(arg.as_str()),
+                _ => return Err(error::CommandError::InvalidCmdFrame),
+            }
+        }
+        let [key, value, options @ ..] = &args[..] else {
             return Err(error::CommandError::Malformed(
-                "SET command requires 2 arguments".to_string(),
+                "SET command requires at least 2 arguments".to_string(),
             ));
-        }
+        };
         let mut cmd = new();
-        if let Frame::Bulk(value) = &frames[1] {
-            cmd.key = value.to_string();
-        }
-        if let Frame::Bulk(value) = &frames[2] {
-            cmd.value = value.to_string();
+        cmd.key = key.to_string();
+        cmd.value = value.to_string();
+        let mut options = options.iter();
+        while let Some(option) = options.next() {
+            let option = option.to_uppercase();
+            match option.as_str() {
+                "NX" if !cmd.xx => cmd.nx = true,
+                "XX" if !cmd.nx => cmd.xx = true,
+                "EX" | "PX" | "EXAT" | "PXAT" if cmd.ttl.is_none() => {
+                    // the expiration time must also fit in an Instant
+                    let ttl = options
+                        .next()
+                        .and_then(|time| time.parse::<u64>().ok())
+                        .filter(|time| *time > 0)
+                        .map(|time| ttl(&option, time))
+                        .filter(|ttl| Instant::now().checked_add(*ttl).is_some())
+                        .ok_or(error::CommandError::Malformed(format!(
+                            "SET {} is not a valid expire time",
+                            option
+                        )))?;
+                    cmd.ttl = Some(ttl);
+                }
+                _ => {
+                    return Err(error::CommandError::Malformed(format!(
+                        "SET invalid option '{}'",
+                        option
+                    )))
+                }
+            }
         }
         Ok(cmd)
     }
 }
 
+/// ttl turns the time given with an expiration option into the time left to live. A Unix time
+/// already passed gives a zero TTL: the key expires right away.
+fn ttl(option: &str, time: u64) -> Duration {
+    let since_epoch = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    };
+    match option {
+        "EX" => Duration::from_secs(time),
+        "PX" => Duration::from_millis(time),
+        "EXAT" => Duration::from_secs(time).saturating_sub(since_epoch()),
+        _ => Duration::from_millis(time).saturating_sub(since_epoch()),
+    }
+}
+
 pub fn new() -> Set {
     Set {
         key: "".to_string(),
         value: "".to_string(),
+        ttl: None,
+        nx: false,
+        xx: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_set_size_limits() {
+        let mut session = test_session();
+        session
+            .db()
+            .config()
+            .set(&[("max-key-bytes", "4"), ("max-value-bytes", "8")])
+            .unwrap();
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "long key", "value"]),
+            Frame::Error("ERR key too long".to_string())
+        );
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "long value"]),
+            Frame::Error("ERR value too large".to_string())
+        );
+        assert_eq!(session.db().peek_value("key"), None);
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "value"]),
+            Frame::Simple("OK".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_options() {
+        let mut session = test_session();
+        let ok = Frame::Simple("OK".to_string());
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "1", "XX"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "1", "nx", "EX", "60"]),
+            ok
+        );
+        assert!(session.db().key_expiration("key").is_some());
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "2", "NX"]),
+            Frame::Null
+        );
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "key", "2", "XX"]),
+            ok
+        );
+        assert_eq!(session.db().peek_value("key"), Some("2".to_string()));
+        // a plain SET drops the expiration time
+        assert_eq!(session.db().key_expiration("key"), None);
+
+        // a Unix time already passed expires the key right away
+        assert_eq!(
+            run_command::<Set>(&mut session, &["SET", "old", "1", "PXAT", "1000"]),
+            ok
+        );
+        assert_eq!(session.db().get_value_by_key("old"), None);
+
+        for args in [
+            &["SET", "key", "1", "NX", "XX"][..],
+            &["SET", "key", "1", "EX", "10", "PX", "10"],
+            &["SET", "key", "1", "EX", "0"],
+            &["SET", "key", "1", "EX", "18446744073709551615"],
+            &["SET", "key", "1", "EX"],
+            &["SET", "key", "1", "KEEP"],
+            &["SET", "key"],
+        ] {
+            let frames: Vec<Frame> = args
+                .iter()
+                .map(|arg| Frame::Bulk(arg.to_string()))
+                .collect();
+            assert!(<Set as Command>::from(&frames).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_ttl() {
+        assert_eq!(ttl("EX", 10), Duration::from_secs(10));
+        assert_eq!(ttl("PX", 10), Duration::from_millis(10));
+        let in_a_minute = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        assert!(ttl("EXAT", in_a_minute) > Duration::from_secs(50));
+        assert_eq!(ttl("EXAT", 1), Duration::ZERO);
     }
 }
@@ -0,0 +1,77 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Setnx implements `SETNX key value`, which sets a key only when it does not exist yet. It
+/// replies 1 when the key was set and 0 when it already existed.
+pub struct Setnx {
+    key: String,
+    value: String,
+}
+
+impl Command for Setnx {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let db = session.db();
+        if self.key.len() > db.config().max_key_bytes() {
+            Frame::Error("ERR key too long".to_string())
+        } else if self.value.len() > db.config().max_value_bytes() {
+            Frame::Error("ERR value too large".to_string())
+        } else {
+            Frame::Integer(db.set_if_absent(&self.key, &self.value) as i64)
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key), Frame::Bulk(value)] => Ok(Setnx {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "SETNX command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, set_expired, test_session};
+
+    #[test]
+    fn test_setnx() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Setnx>(&mut session, &["SETNX", "lock", "owner1"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(
+            run_command::<Setnx>(&mut session, &["SETNX", "lock", "owner2"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(session.db().peek_value("lock"), Some("owner1".to_string()));
+        assert!(<Setnx as Command>::from(&[
+            Frame::Bulk("SETNX".to_string()),
+            Frame::Bulk("lock".to_string()),
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_setnx_expired_key() {
+        let mut session = test_session();
+        set_expired(&session, "lock", "owner1");
+        assert_eq!(
+            run_command::<Setnx>(&mut session, &["SETNX", "lock", "owner2"]),
+            Frame::Integer(1)
+        );
+        assert_eq!(session.db().key_expiration("lock"), None);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// Shutdown implements `SHUTDOWN [NOSAVE | SAVE] [NOW] [FORCE]`, which stops the server. The server
+/// stops accepting connections, and the connections close once their current command is done.
+/// Nothing is saved, so the options are accepted and ignored. As with Redis, nothing is replied:
+/// the connection closes.
+pub struct Shutdown;
+
+impl Command for Shutdown {
+    fn apply(&self, session: &mut Session) -> Frame {
+        session.shutdown().request();
+        Frame::Simple("OK".to_string())
+    }
+
+    fn replies(&self) -> bool {
+        false
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        for frame in frames.iter().skip(1) {
+            match frame {
+                Frame::Bulk(option)
+                    if ["NOSAVE", "SAVE", "NOW", "FORCE"]
+                        .iter()
+                        .any(|known| option.eq_ignore_ascii_case(known)) => {}
+                Frame::Bulk(option) => {
+                    return Err(CommandError::Malformed(format!(
+                        "SHUTDOWN unknown option {}",
+                        option
+                    )))
+                }
+                _ => return Err(CommandError::InvalidCmdFrame),
+            }
+        }
+        Ok(Shutdown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+
+    #[test]
+    fn test_shutdown() {
+        let mut session = test_session();
+        let frames: Vec<Frame> = ["SHUTDOWN", "nosave", "NOW"]
+            .iter()
+            .map(|v| Frame::Bulk(v.to_string()))
+            .collect();
+        let shutdown = <Shutdown as Command>::from(&frames).unwrap();
+        shutdown.apply(&mut session);
+        assert!(!shutdown.replies());
+        assert!(session.shutdown().is_requested());
+
+        assert!(<Shutdown as Command>::from(&[
+            Frame::Bulk("SHUTDOWN".to_string()),
+            Frame::Bulk("ABORT".to_string()),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+
+/// SwapDb implements `SWAPDB index1 index2`, which exchanges the keys of two databases.
+/// Clients using one of the databases immediately see the keys of the other one.
+pub struct SwapDb {
+    first: usize,
+    second: usize,
+}
+
+impl Command for SwapDb {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let databases = session.databases();
+        match (databases.get(self.first), databases.get(self.second)) {
+            (Some(first), Some(second)) => {
+                first.swap(second);
+                Frame::Simple("OK".to_string())
+            }
+            _ => Frame::Error("ERR DB index is out of range".to_string()),
+        }
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        if frames.len() != 3 {
+            return Err(CommandError::Malformed(
+                "SWAPDB command requires 2 arguments".to_string(),
+            ));
+        }
+        let index = |frame: &Frame, position: &str| match frame {
+            Frame::Bulk(value) => value.parse().map_err(|_| {
+                CommandError::Malformed(format!("SWAPDB invalid {} DB index", position))
+            }),
+            _ => Err(CommandError::InvalidCmdFrame),
+        };
+        Ok(SwapDb {
+            first: index(&frames[1], "first")?,
+            second: index(&frames[2], "second")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+
+    #[test]
+    fn test_swapdb() {
+        let mut session = test_session();
+        session.databases()[0].set_kv("key", "zero", None);
+        session.databases()[1].set_kv("key", "one", None);
+        assert_eq!(
+            run_command::<SwapDb>(&mut session, &["SWAPDB", "0", "1"]),
+            Frame::Simple("OK".to_string())
+        );
+        assert_eq!(
+            session.db().get_value_by_key("key"),
+            Some("one".to_string())
+        );
+        assert_eq!(
+            session.databases()[1].get_value_by_key("key"),
+            Some("zero".to_string())
+        );
+        assert_eq!(
+            run_command::<SwapDb>(&mut session, &["SWAPDB", "0", "16"]),
+            Frame::Error("ERR DB index is out of range".to_string())
+        );
+        assert!(<SwapDb as Command>::from(&[
+            Frame::Bulk("SWAPDB".to_string()),
+            Frame::Bulk("0".to_string()),
+            Frame::Bulk("x".to_string()),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::time::Instant;
+
+/// Ttl implements `TTL key`, which returns the time left to live of a key in seconds, -1 when the
+/// key does not expire and -2 when it does not exist.
+pub struct Ttl {
+    key: String,
+}
+
+impl Command for Ttl {
+    fn apply(&self, session: &mut Session) -> Frame {
+        // rounded to the nearest second, like Redis does
+        let reply = match remaining_millis(session, &self.key) {
+            millis if millis < 0 => millis,
+            millis => (millis + 500) / 1000,
+        };
+        Frame::Integer(reply)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key)] => Ok(Ttl { key: key.clone() }),
+            [_] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "TTL command requires 1 argument".to_string(),
+            )),
+        }
+    }
+}
+
+/// Pttl implements `PTTL key`, which works like TTL with milliseconds.
+pub struct Pttl {
+    key: String,
+}
+
+impl Command for Pttl {
+    fn apply(&self, session: &mut Session) -> Frame {
+        Frame::Integer(remaining_millis(session, &self.key))
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        vec![&self.key]
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(key)] => Ok(Pttl { key: key.clone() }),
+            [_] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "PTTL command requires 1 argument".to_string(),
+            )),
+        }
+    }
+}
+
+/// remaining_millis returns the time left to live of a key in milliseconds, -1 when the key does
+/// not expire and -2 when it does not exist. A key expired but not removed yet does not exist.
+fn remaining_millis(session: &Session, key: &str) -> i64 {
+    let db = session.db();
+    match db.key_expiration(key) {
+        Some(expiry) => match expiry.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => {
+                remaining.as_millis().try_into().unwrap_or(i64::MAX)
+            }
+            _ => -2,
+        },
+        None if db.peek_value(key).is_some() => -1,
+        None => -2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+    use std::time::Duration;
+
+    #[test]
+    fn test_ttl() {
+        let mut session = test_session();
+        session.db().set_kv("persistent", "value", None);
+        session
+            .db()
+            .set_kv("expiring", "value", Some(Duration::from_secs(100)));
+        assert_eq!(
+            run_command::<Ttl>(&mut session, &["TTL", "missing"]),
+            Frame::Integer(-2)
+        );
+        assert_eq!(
+            run_command::<Pttl>(&mut session, &["PTTL", "persistent"]),
+            Frame::Integer(-1)
+        );
+        assert_eq!(
+            run_command::<Ttl>(&mut session, &["TTL", "expiring"]),
+            Frame::Integer(100)
+        );
+        match run_command::<Pttl>(&mut session, &["PTTL", "expiring"]) {
+            Frame::Integer(millis) => assert!(millis > 99_000 && millis <= 100_000, "{}", millis),
+            frame => panic!("unexpected PTTL reply {:?}", frame),
+        }
+
+        session
+            .db()
+            .set_kv("expired", "value", Some(Duration::from_millis(1)));
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(
+            run_command::<Pttl>(&mut session, &["PTTL", "expired"]),
+            Frame::Integer(-2)
+        );
+    }
+}
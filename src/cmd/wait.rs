@@ -0,0 +1,84 @@
+use crate::cmd::Command;
+use crate::error::CommandError;
+use crate::frame::Frame;
+use crate::session::Session;
+use std::time::Duration;
+
+/// Wait implements `WAIT numreplicas timeout`, which blocks until `numreplicas` replicas are
+/// connected, or until `timeout` milliseconds passed. A timeout of 0 blocks forever.
+/// It replies with the number of connected replicas.
+pub struct Wait {
+    numreplicas: usize,
+    timeout: Option<Duration>,
+}
+
+impl Command for Wait {
+    fn apply(&self, session: &mut Session) -> Frame {
+        let count = session.replicas().wait(self.numreplicas, self.timeout);
+        Frame::Integer(count as i64)
+    }
+
+    fn from(frames: &[Frame]) -> Result<Self, CommandError> {
+        match &frames[1..] {
+            [Frame::Bulk(numreplicas), Frame::Bulk(timeout)] => {
+                let numreplicas = numreplicas.parse().map_err(|_| {
+                    CommandError::Malformed(
+                        "WAIT numreplicas must be a positive integer".to_string(),
+                    )
+                })?;
+                let timeout: u64 = timeout.parse().map_err(|_| {
+                    CommandError::Malformed("WAIT timeout must be a positive integer".to_string())
+                })?;
+                Ok(Wait {
+                    numreplicas,
+                    timeout: (timeout > 0).then(|| Duration::from_millis(timeout)),
+                })
+            }
+            [_, _] => Err(CommandError::InvalidCmdFrame),
+            _ => Err(CommandError::Malformed(
+                "WAIT command requires 2 arguments".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session};
+    use std::time::Instant;
+
+    #[test]
+    fn test_wait() {
+        let mut session = test_session();
+        assert_eq!(
+            run_command::<Wait>(&mut session, &["WAIT", "0", "0"]),
+            Frame::Integer(0)
+        );
+        assert_eq!(
+            run_command::<Wait>(&mut session, &["WAIT", "1", "10"]),
+            Frame::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let mut session = test_session();
+        // no replica is required, so the timeout does not even start
+        let start = Instant::now();
+        assert_eq!(
+            run_command::<Wait>(&mut session, &["WAIT", "0", "100"]),
+            Frame::Integer(0)
+        );
+        assert!(start.elapsed() < Duration::from_millis(110));
+
+        let start = Instant::now();
+        assert_eq!(
+            run_command::<Wait>(&mut session, &["WAIT", "1", "100"]),
+            Frame::Integer(0)
+        );
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,131 @@
+//! Execution statistics of the commands, reported by the commandstats section of INFO.
+
+use crate::cmd::docs::COMMAND_DOCS;
+use crate::info::InfoCollector;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// CommandMetrics counts the calls of a command and the time they took.
+#[derive(Debug, Default)]
+pub struct CommandMetrics {
+    calls: AtomicU64,
+    usec: AtomicU64,
+}
+
+impl CommandMetrics {
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// usec returns the total time spent running the command, in microseconds.
+    pub fn usec(&self) -> u64 {
+        self.usec.load(Ordering::Relaxed)
+    }
+}
+
+/// CommandStats holds the metrics of every command, indexed by the upper case name the commands
+/// are dispatched with. The commands are known up front, so the map never changes and counting a
+/// call only takes atomic operations.
+#[derive(Debug)]
+pub struct CommandStats {
+    commands: HashMap<String, CommandMetrics>,
+}
+
+impl CommandStats {
+    pub fn new() -> Self {
+        Self {
+            commands: COMMAND_DOCS
+                .iter()
+                .map(|doc| (doc.name.to_uppercase(), CommandMetrics::default()))
+                .collect(),
+        }
+    }
+
+    /// record counts a call of a command which ran for `elapsed`. Unknown commands are ignored.
+    pub fn record(&self, cmd_name: &str, elapsed: Duration) {
+        if let Some(metrics) = self.commands.get(cmd_name) {
+            metrics.calls.fetch_add(1, Ordering::Relaxed);
+            metrics
+                .usec
+                .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// reset sets the metrics of every command back to zero.
+    pub fn reset(&self) {
+        for metrics in self.commands.values() {
+            metrics.calls.store(0, Ordering::Relaxed);
+            metrics.usec.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// get returns the metrics of a command, by its upper case name.
+    pub fn get(&self, cmd_name: &str) -> Option<&CommandMetrics> {
+        self.commands.get(cmd_name)
+    }
+}
+
+impl Default for CommandStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InfoCollector for CommandStats {
+    fn section(&self) -> &'static str {
+        "commandstats"
+    }
+
+    /// collect returns a `cmdstat_<command>` field for every command called at least once.
+    fn collect(&self) -> Vec<(Cow<'static, str>, String)> {
+        COMMAND_DOCS
+            .iter()
+            .filter_map(|doc| {
+                let metrics = self.commands.get(&doc.name.to_uppercase())?;
+                let calls = metrics.calls();
+                if calls == 0 {
+                    return None;
+                }
+                let usec = metrics.usec();
+                let value = format!(
+                    "calls={},usec={},usec_per_call={:.2}",
+                    calls,
+                    usec,
+                    usec as f64 / calls as f64
+                );
+                Some((format!("cmdstat_{}", doc.name).into(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_stats() {
+        let stats = CommandStats::new();
+        assert!(stats.collect().is_empty());
+        stats.record("GET", Duration::from_micros(10));
+        stats.record("GET", Duration::from_micros(5));
+        stats.record("SET", Duration::from_micros(3));
+        stats.record("NOPE", Duration::from_micros(3));
+        assert_eq!(stats.get("GET").unwrap().calls(), 2);
+        assert_eq!(
+            stats.collect(),
+            vec![
+                (
+                    "cmdstat_get".into(),
+                    "calls=2,usec=15,usec_per_call=7.50".to_string()
+                ),
+                (
+                    "cmdstat_set".into(),
+                    "calls=1,usec=3,usec_per_call=3.00".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,571 @@
+//! Runtime configuration of the server, read and updated with CONFIG GET and CONFIG SET.
+//! Most parameters are only stored so that Redis tools relying on them keep working.
+//! The ones htcache acts on are read live from `ServerConfig` by the structures they control.
+
+use crate::error::ConfigError;
+use crate::glob;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Name of the parameter holding the percentage of the capacity which triggers the background eviction.
+pub const EVICTION_THRESHOLD: &str = "eviction-threshold";
+
+/// Name of the parameter holding the number of commands remembered by each connection, for
+/// DEBUG COMMAND-HISTORY.
+pub const COMMAND_HISTORY_LEN: &str = "command-history-len";
+
+/// Name of the parameter holding the number of databases. It cannot be changed at runtime.
+pub const DATABASES: &str = "databases";
+
+/// Name of the parameter allowing the DEBUG command, off unless the server enables it.
+pub const ENABLE_DEBUG_COMMAND: &str = "enable-debug-command";
+
+/// Name of the parameter holding the directory DEBUG SNIFFER writes to. Empty when the sniffer
+/// is not allowed.
+pub const SNIFFER_DIR: &str = "sniffer-dir";
+
+/// Name of the parameter holding the size of the queue of connections waiting to be accepted.
+/// It is set when the server starts.
+pub const TCP_BACKLOG: &str = "tcp-backlog";
+
+/// Parameters which can only be set when the server starts.
+const IMMUTABLE_PARAMETERS: &[&str] = &[DATABASES, ENABLE_DEBUG_COMMAND, SNIFFER_DIR, TCP_BACKLOG];
+
+/// Name of the parameter holding the time a Lua script can run, in milliseconds. 0 is no limit.
+pub const LUA_TIME_LIMIT: &str = "lua-time-limit";
+
+/// Name of the parameter holding the memory a Lua script can use, in bytes. 0 is no limit.
+pub const LUA_MEMORY_LIMIT: &str = "lua-memory-limit";
+
+/// Name of the parameter holding the maximum size of a key, in bytes.
+pub const MAX_KEY_BYTES: &str = "max-key-bytes";
+
+/// Name of the parameter holding the maximum size of a value, in bytes.
+pub const MAX_VALUE_BYTES: &str = "max-value-bytes";
+
+/// Name of the parameter holding the maximum number of clients connected at the same time.
+pub const MAX_CLIENTS: &str = "maxclients";
+
+/// Name of the parameter holding the policy choosing the keys evicted when expiring keys is not
+/// enough to bring a database under the eviction threshold.
+pub const MAXMEMORY_POLICY: &str = "maxmemory-policy";
+
+const MAXMEMORY_POLICIES: &[&str] = &[
+    "volatile-lru",
+    "volatile-lfu",
+    "volatile-random",
+    "volatile-ttl",
+    "allkeys-lru",
+    "allkeys-lfu",
+    "allkeys-random",
+    "noeviction",
+];
+
+enum ParamKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Memory,
+    Enum(&'static [&'static str]),
+    String,
+}
+
+struct Parameter {
+    name: &'static str,
+    default: &'static str,
+    kind: ParamKind,
+}
+
+const PARAMETERS: &[Parameter] = &[
+    Parameter {
+        name: "activedefrag",
+        default: "no",
+        kind: ParamKind::Bool,
+    },
+    Parameter {
+        name: "activerehashing",
+        default: "yes",
+        kind: ParamKind::Bool,
+    },
+    Parameter {
+        name: COMMAND_HISTORY_LEN,
+        default: "10",
+        kind: ParamKind::Integer { min: 0, max: 1024 },
+    },
+    Parameter {
+        name: DATABASES,
+        default: "16",
+        kind: ParamKind::Integer {
+            min: 1,
+            max: i32::MAX as i64,
+        },
+    },
+    Parameter {
+        name: ENABLE_DEBUG_COMMAND,
+        default: "no",
+        kind: ParamKind::Bool,
+    },
+    Parameter {
+        name: EVICTION_THRESHOLD,
+        default: "80",
+        kind: ParamKind::Integer { min: 0, max: 99 },
+    },
+    Parameter {
+        name: "hash-max-listpack-entries",
+        default: "128",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: "hash-max-listpack-value",
+        default: "64",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: "hz",
+        default: "10",
+        kind: ParamKind::Integer { min: 1, max: 500 },
+    },
+    Parameter {
+        name: "list-max-listpack-size",
+        default: "-2",
+        kind: ParamKind::Integer {
+            min: -5,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: LUA_MEMORY_LIMIT,
+        default: "67108864",
+        kind: ParamKind::Memory,
+    },
+    Parameter {
+        name: LUA_TIME_LIMIT,
+        default: "5000",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: MAX_KEY_BYTES,
+        default: "536870912",
+        kind: ParamKind::Memory,
+    },
+    Parameter {
+        name: MAX_VALUE_BYTES,
+        default: "536870912",
+        kind: ParamKind::Memory,
+    },
+    Parameter {
+        name: MAX_CLIENTS,
+        default: "10000",
+        kind: ParamKind::Integer {
+            min: 1,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: "maxmemory",
+        default: "0",
+        kind: ParamKind::Memory,
+    },
+    Parameter {
+        name: MAXMEMORY_POLICY,
+        default: "noeviction",
+        kind: ParamKind::Enum(MAXMEMORY_POLICIES),
+    },
+    Parameter {
+        name: "set-max-intset-entries",
+        default: "512",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: SNIFFER_DIR,
+        default: "",
+        kind: ParamKind::String,
+    },
+    Parameter {
+        name: TCP_BACKLOG,
+        default: "511",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i32::MAX as i64,
+        },
+    },
+    Parameter {
+        name: "zset-max-listpack-entries",
+        default: "128",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+    Parameter {
+        name: "zset-max-listpack-value",
+        default: "64",
+        kind: ParamKind::Integer {
+            min: 0,
+            max: i64::MAX,
+        },
+    },
+];
+
+/// ServerConfig holds the current value of every configuration parameter.
+/// Values are kept in their normalized string form, as returned by CONFIG GET.
+pub struct ServerConfig {
+    values: RwLock<BTreeMap<&'static str, String>>,
+    // Read on every write to the cache, so it is also kept as an atomic to avoid taking the lock.
+    eviction_threshold: AtomicU8,
+}
+
+impl ServerConfig {
+    /// new creates a configuration with default values, except for the eviction threshold.
+    pub fn new(eviction_threshold: u8) -> Result<Self, ConfigError> {
+        let config = Self {
+            values: RwLock::new(
+                PARAMETERS
+                    .iter()
+                    .map(|param| (param.name, param.default.to_string()))
+                    .collect(),
+            ),
+            eviction_threshold: AtomicU8::new(0),
+        };
+        config.set(&[(EVICTION_THRESHOLD, &eviction_threshold.to_string())])?;
+        Ok(config)
+    }
+
+    /// get returns the parameters whose name matches the glob-style pattern, sorted by name.
+    pub fn get(&self, pattern: &str) -> Vec<(&'static str, String)> {
+        let pattern = pattern.to_lowercase();
+        self.values
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| glob::matches(&pattern, name))
+            .map(|(name, value)| (*name, value.clone()))
+            .collect()
+    }
+
+    /// set updates parameters. Either all the values are valid and applied, or none is.
+    pub fn set(&self, params: &[(&str, &str)]) -> Result<(), ConfigError> {
+        self.apply(params, false)
+    }
+
+    /// set_at_startup updates parameters like `set`, but also accepts the immutable ones. It is
+    /// meant for the values given when the server is created.
+    pub fn set_at_startup(&self, params: &[(&str, &str)]) -> Result<(), ConfigError> {
+        self.apply(params, true)
+    }
+
+    fn apply(&self, params: &[(&str, &str)], startup: bool) -> Result<(), ConfigError> {
+        let mut normalized = Vec::with_capacity(params.len());
+        for (name, value) in params {
+            let name = name.to_lowercase();
+            let param = PARAMETERS
+                .iter()
+                .find(|param| param.name == name)
+                .ok_or(ConfigError::UnknownParameter(name.clone()))?;
+            if !startup && IMMUTABLE_PARAMETERS.contains(&param.name) {
+                return Err(ConfigError::InvalidValue {
+                    name: param.name.to_string(),
+                    reason: "can't set immutable config".to_string(),
+                });
+            }
+            let value = normalize(param, value).map_err(|reason| ConfigError::InvalidValue {
+                name: param.name.to_string(),
+                reason,
+            })?;
+            normalized.push((param.name, value));
+        }
+
+        let mut values = self.values.write().unwrap();
+        for (name, value) in normalized {
+            if name == EVICTION_THRESHOLD {
+                // validated as an integer between 0 and 99
+                let threshold = value.parse().unwrap_or_default();
+                self.eviction_threshold.store(threshold, Ordering::SeqCst);
+            }
+            values.insert(name, value);
+        }
+        Ok(())
+    }
+
+    /// eviction_threshold returns the percentage of the capacity which triggers the background eviction.
+    pub fn eviction_threshold(&self) -> u8 {
+        self.eviction_threshold.load(Ordering::SeqCst)
+    }
+
+    /// databases returns the number of databases of the server.
+    pub fn databases(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a positive integer
+        values[DATABASES].parse().unwrap_or(1)
+    }
+
+    /// max_clients returns the maximum number of clients connected at the same time.
+    pub fn max_clients(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a positive integer
+        values[MAX_CLIENTS].parse().unwrap_or(usize::MAX)
+    }
+
+    /// max_key_bytes returns the maximum size of a key, in bytes.
+    pub fn max_key_bytes(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a memory value
+        values[MAX_KEY_BYTES].parse().unwrap_or(usize::MAX)
+    }
+
+    /// max_value_bytes returns the maximum size of a value, in bytes.
+    pub fn max_value_bytes(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a memory value
+        values[MAX_VALUE_BYTES].parse().unwrap_or(usize::MAX)
+    }
+
+    /// tcp_backlog returns the size of the queue of connections waiting to be accepted.
+    pub fn tcp_backlog(&self) -> i32 {
+        let values = self.values.read().unwrap();
+        // validated as a non negative i32
+        values[TCP_BACKLOG].parse().unwrap_or(511)
+    }
+
+    /// maxmemory_policy returns the name of the eviction policy.
+    pub fn maxmemory_policy(&self) -> String {
+        self.values.read().unwrap()[MAXMEMORY_POLICY].clone()
+    }
+
+    /// enable_debug_command returns true when the DEBUG command is allowed.
+    pub fn enable_debug_command(&self) -> bool {
+        self.values.read().unwrap()[ENABLE_DEBUG_COMMAND] == "yes"
+    }
+
+    /// sniffer_dir returns the directory DEBUG SNIFFER writes to, None when the sniffer is not
+    /// allowed.
+    pub fn sniffer_dir(&self) -> Option<PathBuf> {
+        let values = self.values.read().unwrap();
+        Some(&values[SNIFFER_DIR])
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// lua_time_limit returns the time a Lua script can run, None when there is no limit.
+    pub fn lua_time_limit(&self) -> Option<Duration> {
+        let values = self.values.read().unwrap();
+        // validated as a non negative integer
+        match values[LUA_TIME_LIMIT].parse().unwrap_or(0) {
+            0 => None,
+            millis => Some(Duration::from_millis(millis)),
+        }
+    }
+
+    /// lua_memory_limit returns the memory a Lua script can use, in bytes, 0 when there is no limit.
+    pub fn lua_memory_limit(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a memory value
+        values[LUA_MEMORY_LIMIT].parse().unwrap_or(0)
+    }
+
+    /// command_history_len returns the number of commands remembered by each connection.
+    pub fn command_history_len(&self) -> usize {
+        let values = self.values.read().unwrap();
+        // validated as a non negative integer
+        values[COMMAND_HISTORY_LEN].parse().unwrap_or(0)
+    }
+}
+
+impl Debug for ServerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ServerConfig{{{:?}}}", self.values.read().unwrap())
+    }
+}
+
+/// normalize validates a value for a parameter and returns the form stored in the configuration.
+fn normalize(param: &Parameter, value: &str) -> Result<String, String> {
+    match &param.kind {
+        ParamKind::Bool => match value.to_lowercase().as_str() {
+            "yes" => Ok("yes".to_string()),
+            "no" => Ok("no".to_string()),
+            _ => Err("argument must be 'yes' or 'no'".to_string()),
+        },
+        ParamKind::Integer { min, max } => {
+            let number: i64 = value
+                .parse()
+                .map_err(|_| "argument couldn't be parsed into an integer".to_string())?;
+            if number < *min || number > *max {
+                return Err(format!("argument must be between {} and {}", min, max));
+            }
+            Ok(number.to_string())
+        }
+        ParamKind::Memory => parse_memory(value)
+            .map(|bytes| bytes.to_string())
+            .ok_or("argument must be a memory value".to_string()),
+        ParamKind::Enum(choices) => {
+            let value = value.to_lowercase();
+            if choices.contains(&value.as_str()) {
+                Ok(value)
+            } else {
+                Err(format!("argument must be one of: {}", choices.join(", ")))
+            }
+        }
+        ParamKind::String => Ok(value.to_string()),
+    }
+}
+
+/// parse_memory reads a memory amount like `100`, `1kb` or `5gb` and returns it in bytes.
+/// As in Redis, `k`, `m` and `g` are powers of 1000 while `kb`, `mb` and `gb` are powers of 1024.
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let digits_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits_end);
+    let number: u64 = number.parse().ok()?;
+    let multiplier = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_with_pattern() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(
+            config.get("maxmemory*"),
+            vec![
+                ("maxmemory", "0".to_string()),
+                ("maxmemory-policy", "noeviction".to_string())
+            ]
+        );
+        assert_eq!(config.get("HZ"), vec![("hz", "10".to_string())]);
+        assert!(config.get("unknown").is_empty());
+        assert_eq!(config.get("*").len(), PARAMETERS.len());
+    }
+
+    #[test]
+    fn test_set_normalizes_values() {
+        let config = ServerConfig::new(80).unwrap();
+        config
+            .set(&[
+                ("maxmemory", "2mb"),
+                ("ActiveDefrag", "YES"),
+                ("maxmemory-policy", "allkeys-LRU"),
+                (EVICTION_THRESHOLD, "50"),
+            ])
+            .unwrap();
+        assert_eq!(config.get("maxmemory")[0].1, "2097152");
+        assert_eq!(config.get("activedefrag")[0].1, "yes");
+        assert_eq!(config.get("maxmemory-policy")[0].1, "allkeys-lru");
+        assert_eq!(config.eviction_threshold(), 50);
+    }
+
+    #[test]
+    fn test_databases_is_immutable() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(config.databases(), 16);
+        assert!(matches!(
+            config.set(&[(DATABASES, "4")]),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert_eq!(config.databases(), 16);
+    }
+
+    #[test]
+    fn test_tcp_backlog_is_set_at_startup() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(config.tcp_backlog(), 511);
+        assert!(config.set(&[(TCP_BACKLOG, "1024")]).is_err());
+        config.set_at_startup(&[(TCP_BACKLOG, "1024")]).unwrap();
+        assert_eq!(config.tcp_backlog(), 1024);
+        assert!(config.set_at_startup(&[(TCP_BACKLOG, "-1")]).is_err());
+    }
+
+    #[test]
+    fn test_debug_command_is_set_at_startup() {
+        let config = ServerConfig::new(80).unwrap();
+        assert!(!config.enable_debug_command());
+        assert_eq!(config.sniffer_dir(), None);
+        assert!(config.set(&[(ENABLE_DEBUG_COMMAND, "yes")]).is_err());
+        assert!(config.set(&[(SNIFFER_DIR, "/tmp")]).is_err());
+        config
+            .set_at_startup(&[(ENABLE_DEBUG_COMMAND, "yes"), (SNIFFER_DIR, "/tmp")])
+            .unwrap();
+        assert!(config.enable_debug_command());
+        assert_eq!(config.sniffer_dir(), Some(PathBuf::from("/tmp")));
+    }
+
+    #[test]
+    fn test_max_key_and_value_bytes() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(config.max_key_bytes(), 512 * 1024 * 1024);
+        assert_eq!(config.max_value_bytes(), 512 * 1024 * 1024);
+        config
+            .set(&[(MAX_KEY_BYTES, "1kb"), (MAX_VALUE_BYTES, "1mb")])
+            .unwrap();
+        assert_eq!(config.max_key_bytes(), 1024);
+        assert_eq!(config.max_value_bytes(), 1024 * 1024);
+        assert_eq!(config.get("max-*-bytes").len(), 2);
+    }
+
+    #[test]
+    fn test_lua_limits() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(config.lua_time_limit(), Some(Duration::from_secs(5)));
+        assert_eq!(config.lua_memory_limit(), 64 * 1024 * 1024);
+        config
+            .set(&[(LUA_TIME_LIMIT, "0"), (LUA_MEMORY_LIMIT, "1mb")])
+            .unwrap();
+        assert_eq!(config.lua_time_limit(), None);
+        assert_eq!(config.lua_memory_limit(), 1024 * 1024);
+        assert!(config.set(&[(LUA_TIME_LIMIT, "-1")]).is_err());
+    }
+
+    #[test]
+    fn test_max_clients() {
+        let config = ServerConfig::new(80).unwrap();
+        assert_eq!(config.max_clients(), 10000);
+        config.set(&[(MAX_CLIENTS, "2")]).unwrap();
+        assert_eq!(config.max_clients(), 2);
+        assert!(config.set(&[(MAX_CLIENTS, "0")]).is_err());
+    }
+
+    #[test]
+    fn test_set_is_all_or_nothing() {
+        let config = ServerConfig::new(80).unwrap();
+        let result = config.set(&[("hz", "20"), ("hz", "1000")]);
+        assert!(matches!(result, Err(ConfigError::InvalidValue { .. })));
+        assert_eq!(config.get("hz")[0].1, "10");
+
+        let result = config.set(&[("hz", "20"), ("save", "")]);
+        assert!(matches!(result, Err(ConfigError::UnknownParameter(_))));
+        assert_eq!(config.get("hz")[0].1, "10");
+    }
+
+    #[test]
+    fn test_invalid_eviction_threshold() {
+        assert!(ServerConfig::new(100).is_err());
+    }
+}
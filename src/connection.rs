@@ -1,7 +1,11 @@
-use crate::cmd::{self, parse_frame, Command};
-use crate::error::{CommandError, HandleCommandError};
+use crate::cmd::docs::COMMAND_DOCS;
+use crate::cmd::{self, parse_frame};
+use crate::error::HandleCommandError;
+use crate::frame;
 use crate::frame::Frame;
-use crate::{db, frame};
+use crate::middleware::Middleware;
+use crate::session::Session;
+use crate::sniffer::{TeeReader, TeeWriter};
 use std::io;
 use std::io::{BufReader, BufWriter, Write};
 use std::net::TcpStream;
@@ -9,32 +13,43 @@ use std::sync::Arc;
 use tracing::{debug, error};
 
 /// Connection struct contains the TCP Stream derived from an established connection. Both reader
-/// and writer share the same underline stream. State is a shared reference of the Cache database
+/// and writer share the same underline stream. Session holds the client context the commands run in.
+/// Middlewares intercept every command, in order.
 pub struct Connection {
-    reader: BufReader<TcpStream>,
-    writer: BufWriter<TcpStream>,
-    state: Arc<db::State>,
+    reader: BufReader<TeeReader<TcpStream>>,
+    writer: BufWriter<TeeWriter<TcpStream>>,
+    session: Session,
+    middlewares: Arc<Vec<Box<dyn Middleware>>>,
 }
 
 impl Connection {
     // pub fn close(&self) -> io::Result<()> {
     //     self.conn.shutdown(Shutdown::Both)
     // }
-    pub fn new(stream: TcpStream, state: Arc<db::State>) -> io::Result<Self> {
+    pub fn new(
+        stream: TcpStream,
+        session: Session,
+        middlewares: Arc<Vec<Box<dyn Middleware>>>,
+    ) -> io::Result<Self> {
         let stream_clone = stream.try_clone()?;
         // let mut reader = BufReader::new(read_half);
-        let writer = BufWriter::new(stream_clone);
-        let reader = BufReader::new(stream);
+        // the bytes go through the sniffer of the session, which only copies them once enabled
+        let writer = BufWriter::new(TeeWriter::new(stream_clone, session.sniffer().clone()));
+        let reader = BufReader::new(TeeReader::new(stream, session.sniffer().clone()));
         Ok(Self {
             reader,
             writer,
-            state,
+            session,
+            middlewares,
         })
     }
 
     pub fn close(&self) -> io::Result<()> {
         // Both reader and writer are linked to the same tcp stream so closing on only one is ok.
-        self.reader.get_ref().shutdown(std::net::Shutdown::Both)?;
+        self.reader
+            .get_ref()
+            .get_ref()
+            .shutdown(std::net::Shutdown::Both)?;
         Ok(())
     }
 
@@ -62,43 +77,91 @@ impl Connection {
     pub fn handle_command(&mut self) -> Result<(), HandleCommandError> {
         // get frame fist
         let frame = frame::decode(&mut self.reader)?;
+        // what is left in the buffer is the input already received for the next commands
+        self.session
+            .clients()
+            .record_input_buffer(self.reader.buffer().len());
         debug!("received command frame: {:?}", frame);
         // parse frame
         let (cmd_name, frames) = parse_frame(frame)?;
+        self.session
+            .record_command(history_entry(&cmd_name, &frames));
         self.apply_command(&cmd_name, frames);
         Ok(())
     }
 
-    fn execute_command<Cmd>(&mut self, frames: Vec<Frame>)
-    where
-        Cmd: Command,
-    {
-        match Cmd::from(frames) {
-            Ok(command) => {
-                command
-                    .apply(&mut self.writer, &self.state)
-                    .unwrap_or_else(|err| {
-                        // This error happens when the data cannot be written to the connection,
-                        // So it is not useful to try to send it to the client over the connection.
-                        error!(
-                            error_message = err.to_string(),
-                            "error writing response to client"
-                        );
-                    });
+    /// apply_command parses a command, then runs it unless a middleware rejects it. Its reply
+    /// is handed to the middlewares before being sent.
+    fn apply_command(&mut self, cmd_name: &str, frames: Vec<Frame>) {
+        let command = cmd::parse(cmd_name, &frames);
+        let keys = command.as_ref().ok().map(|command| command.keys());
+        let rejected = self.middlewares.iter().try_for_each(|middleware| {
+            middleware.before(&self.session, cmd_name, &frames, keys.as_deref())
+        });
+        let response = match (rejected, command) {
+            (Err(frame), _) => Some(frame),
+            (Ok(()), command) => {
+                let response = match command {
+                    Ok(command) => cmd::apply(cmd_name, command.as_ref(), &mut self.session),
+                    Err(err) => {
+                        debug!("command processing failed: {}", err);
+                        Some(Frame::Error(err.to_string()))
+                    }
+                };
+                if let Some(response) = &response {
+                    for middleware in self.middlewares.iter() {
+                        middleware.after(&self.session, cmd_name, &frames, response);
+                    }
+                }
+                response
             }
-            Err(err) => self.send_error(&HandleCommandError::Command(err)),
-        }
+        };
+        // SHUTDOWN replies nothing, the connection closes
+        let Some(response) = response else {
+            return;
+        };
+        self.write_frame(&response).unwrap_or_else(|err| {
+            // This error happens when the data cannot be written to the connection,
+            // So it is not useful to try to send it to the client over the connection.
+            error!(
+                error_message = err.to_string(),
+                "error writing response to client"
+            );
+        });
     }
+}
 
-    fn apply_command(&mut self, cmd_name: &str, frames: Vec<Frame>) {
-        match cmd_name {
-            "PING" => self.execute_command::<cmd::Ping>(frames),
-            "SET" => self.execute_command::<cmd::Set>(frames),
-            "GET" => self.execute_command::<cmd::Get>(frames),
-            "DEL" => self.execute_command::<cmd::Del>(frames),
-            _ => self.send_error(&HandleCommandError::Command(CommandError::Unknown(
-                cmd_name.to_string(),
-            ))),
-        }
+/// history_entry describes a command for DEBUG COMMAND-HISTORY: its name, followed by its first
+/// key for the commands taking keys. The values are left out, they can be large.
+fn history_entry(cmd_name: &str, frames: &[Frame]) -> String {
+    let first_key = COMMAND_DOCS
+        .iter()
+        .find(|doc| doc.name.eq_ignore_ascii_case(cmd_name))
+        .and_then(|doc| usize::try_from(doc.first_key).ok())
+        .filter(|position| *position > 0)
+        .and_then(|position| frames.get(position));
+    match first_key {
+        Some(Frame::Bulk(key)) => format!("{} {}", cmd_name, key),
+        _ => cmd_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_entry() {
+        let frames = |args: &[&str]| -> Vec<Frame> {
+            args.iter()
+                .map(|arg| Frame::Bulk(arg.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            history_entry("SET", &frames(&["SET", "key", "value"])),
+            "SET key"
+        );
+        assert_eq!(history_entry("PING", &frames(&["PING", "hello"])), "PING");
+        assert_eq!(history_entry("GET", &frames(&["GET"])), "GET");
     }
 }
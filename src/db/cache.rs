@@ -4,44 +4,55 @@
 //If you try to set an element and there is no space, random eviction will happen.
 
 extern crate rand;
-use crate::db::cmap::CMap;
+use crate::circuit_breaker::{CircuitBreaker, BREAKER_THRESHOLD, BREAKER_TIMEOUT};
+use crate::config::ServerConfig;
+use crate::db::cmap::{CMap, KeyMeta};
+use crate::db::eviction::{self, Eviction, NoEviction};
+use crate::db::StorageBackend;
+use crate::error::CommandError;
 use metrics::{counter, describe_counter};
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::{Debug, Formatter};
-use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, RwLock, RwLockReadGuard};
 use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use std::{io, thread};
-use tracing::debug;
+use tracing::{debug, error};
 
 const METRIC_EVICTED_KEY: &str = "evicted_keys";
 const METRIC_EVICTED_KEY_DESC: &str = "number of evicted keys";
 const LABEL_EVICTED_KEY_SHARD: &str = "shard";
 
+/// EvictionCallback is called with the key and the value of every entry removed by the eviction job.
+pub type EvictionCallback = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// LoadDone is set once the load of a key by get_or_load is over, and wakes up its waiters.
+type LoadDone = Arc<(Mutex<bool>, Condvar)>;
+
 pub struct Cache {
     // We could have put everything in the same struct,
     // but this means
     // this struc could need to be shared to all threads while only State is required.
     // Cache will behave like a higher level struct orchestrating state sharing among threads.
-    storage: Arc<State>,
-    // Cleanup_needed will signal the background thread to start cleaning up.
-    // So it needs to be created here and shared to State.
-    cleanup_needed: Arc<(Mutex<bool>, Condvar)>,
-    // The cleanup background job will run in a thread.
-    // We don't have mechanism to shut down this job.
-    // So the handler is owned by the Cache structure.
-    // This way, the job will be dropped when the cache goes out of scope.
-    cleanup_job: JoinHandle<()>,
+    // There is one State per database, indexed by database id.
+    storage: Arc<Vec<Arc<State>>>,
 }
 
 impl Cache {
-    pub fn db(&self) -> Arc<State> {
+    /// db returns the database with the given index, if it exists.
+    pub fn db(&self, index: usize) -> Option<Arc<State>> {
+        self.storage.get(index).cloned()
+    }
+
+    /// databases returns all the databases, indexed by database id.
+    pub fn databases(&self) -> Arc<Vec<Arc<State>>> {
         self.storage.clone()
     }
 
     pub fn create_cleanup_job(
         cleanup_needed: Arc<(Mutex<bool>, Condvar)>,
-        state: Arc<State>,
+        databases: Arc<Vec<Arc<State>>>,
     ) -> io::Result<JoinHandle<()>> {
         thread::Builder::new()
             .name("htcache-eviction-job".to_string())
@@ -55,7 +66,11 @@ impl Cache {
 
                     // We need to perform cleanup here
                     debug!("start performing background automatic eviction");
-                    state.evict_expired_keys();
+                    // The databases share the cleanup flag, so they are all cleaned up together.
+                    for state in databases.iter() {
+                        state.evict_expired_keys();
+                        state.evict_with_policy();
+                    }
                     debug!("finish performing background automatic eviction");
                     *cleanup_threshold_reached = false;
                 }
@@ -65,7 +80,12 @@ impl Cache {
 
 impl Debug for Cache {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Cache: {{state: {:?}}}", self.storage)
+        write!(
+            f,
+            "Cache: {{databases: {}, state: {:?}}}",
+            self.storage.len(),
+            self.storage[0]
+        )
     }
 }
 
@@ -81,31 +101,48 @@ pub fn create_cache(
         ));
     }
 
+    let config = Arc::new(
+        ServerConfig::new(auto_eviction_threshold)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?,
+    );
     let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
-    let cleanup_needed_clone = cleanup_needed.clone();
-    let state = Arc::new(State::new(
-        capacity,
-        shard_count,
-        cleanup_needed_clone,
-        auto_eviction_threshold,
-    )?);
-
-    let job = Cache::create_cleanup_job(cleanup_needed.clone(), state.clone())
+    let mut databases = Vec::with_capacity(config.databases());
+    for db_id in 0..config.databases() {
+        // Only the default database is allocated upfront, the others grow on demand.
+        let bucket_size = if db_id == 0 {
+            capacity / shard_count
+        } else {
+            0
+        };
+        databases.push(Arc::new(State::new(
+            db_id,
+            Box::new(CMap::new(shard_count, bucket_size)?),
+            capacity,
+            cleanup_needed.clone(),
+            config.clone(),
+        )));
+    }
+    let databases = Arc::new(databases);
+
+    // The cleanup background job runs in a thread of its own, signaled by the states through
+    // cleanup_needed. We don't have mechanism to shut down this job, so its handle is dropped.
+    Cache::create_cleanup_job(cleanup_needed, databases.clone())
         .expect("failed to create cleanup background job");
 
-    Ok(Cache {
-        storage: state,
-        cleanup_needed: cleanup_needed.clone(),
-        cleanup_job: job,
-    })
+    Ok(Cache { storage: databases })
 }
 
 /// State is the underlined data structure of the Cache.
 pub struct State {
-    // Data is the core storage. It is based on a concurrent, bucket-based hashmap data structure.
-    // Cmap uses read-write lock at bucket level.
+    // Index of the database in the cache.
+    db_id: usize,
+    // Data is the core storage. By default, it is a CMap: a concurrent, bucket-based hashmap
+    // data structure which locks at bucket level.
     // This allows parallel access to read and write the state.
-    data: CMap,
+    // The lock is only taken for writing by SWAPDB, which exchanges the data of two databases,
+    // by MOVE and COPY, which move or copy a key between two databases, and to check a key and
+    // write it atomically, as SET NX or XX and the removal of an expired key do.
+    data: RwLock<Box<dyn StorageBackend>>,
     // The total number of elements in the cache. We do not want it to grow indefinitely.
     // Capacity should be a power of two.
     capacity: usize,
@@ -127,79 +164,521 @@ pub struct State {
     // that the capacity is not guaranteed to be respected.
     // If the keys do not expire often and new ones keep being added,
     // capacity would outgrow the set value.
-    auto_eviction_threshold: u8,
+    // The threshold is a runtime parameter, so it is read from the shared server configuration.
+    config: Arc<ServerConfig>,
     // shared cleanup flag with the parent struct Cache.
     cleanup_needed: Arc<(Mutex<bool>, Condvar)>,
-    shard_count: usize,
+    // Optional hook notified of every key removed by the background eviction.
+    // It lets embedding applications react to evictions without polling the cache.
+    eviction_callback: RwLock<Option<EvictionCallback>>,
+    // Policy choosing the keys to evict when removing the expired ones is not enough. It follows
+    // the maxmemory-policy parameter, which the eviction job reads again on every run.
+    eviction: RwLock<Box<dyn Eviction>>,
+    // Keys being loaded by get_or_load, so that a burst of misses on a key only loads it once.
+    // The flag is set and the waiters are woken up once the load is over, even when it failed.
+    loading: Mutex<HashMap<String, LoadDone>>,
+    // Rejects the commands accessing keys for a while when the storage keeps failing.
+    circuit_breaker: CircuitBreaker,
 }
 
 impl State {
+    /// new creates a database storing its keys in `storage`.
     pub fn new(
+        db_id: usize,
+        storage: Box<dyn StorageBackend>,
         capacity: usize,
-        shard_count: usize,
         cleanup_needed: Arc<(Mutex<bool>, Condvar)>,
-        auto_eviction_threshold: u8,
-    ) -> io::Result<Self> {
-        let data = CMap::new(shard_count, capacity / shard_count)?;
-        let tracking = Mutex::new(BTreeSet::new());
-        Ok(Self {
-            data,
+        config: Arc<ServerConfig>,
+    ) -> Self {
+        let eviction = eviction::from_policy(&config.maxmemory_policy())
+            .unwrap_or_else(|| Box::new(NoEviction));
+        Self {
+            db_id,
+            data: RwLock::new(storage),
             capacity,
-            tracking,
-            auto_eviction_threshold,
+            tracking: Mutex::new(BTreeSet::new()),
+            config,
             cleanup_needed,
-            shard_count,
-        })
+            eviction_callback: RwLock::new(None),
+            eviction: RwLock::new(eviction),
+            loading: Mutex::new(HashMap::new()),
+            circuit_breaker: CircuitBreaker::new(BREAKER_THRESHOLD, BREAKER_TIMEOUT),
+        }
+    }
+
+    /// is_available returns false once a panic happened while the storage was being written to:
+    /// the storage may be inconsistent, and every access to it fails.
+    pub fn is_available(&self) -> bool {
+        !self.data.is_poisoned()
+    }
+
+    /// data gives access to the storage of the database.
+    fn data(&self) -> RwLockReadGuard<'_, Box<dyn StorageBackend>> {
+        self.data.read().unwrap()
+    }
+
+    /// circuit_breaker returns the circuit breaker the commands accessing keys go through.
+    pub fn circuit_breaker(&self) -> &CircuitBreaker {
+        &self.circuit_breaker
+    }
+
+    /// db_id returns the index of the database in the cache.
+    pub fn db_id(&self) -> usize {
+        self.db_id
+    }
+
+    /// swap exchanges the keys of two databases, with their expiration tracking. Other clients
+    /// see either the databases before the swap or after it, never a mix of both.
+    pub fn swap(&self, other: &State) {
+        if self.db_id == other.db_id {
+            return;
+        }
+        // Locks are always taken in database order so that two concurrent swaps cannot deadlock.
+        let (first, second) = if self.db_id < other.db_id {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut first_data = first.data.write().unwrap();
+        let mut second_data = second.data.write().unwrap();
+        let mut first_tracking = first.tracking.lock().unwrap();
+        let mut second_tracking = second.tracking.lock().unwrap();
+        std::mem::swap(&mut *first_data, &mut *second_data);
+        std::mem::swap(&mut *first_tracking, &mut *second_tracking);
+    }
+
+    /// move_key moves a key, with its metadata and expiration, to another database.
+    /// Nothing is moved if the key does not exist or if it already exists in the other database.
+    pub fn move_key(&self, key: &str, dest: &State) -> bool {
+        if self.db_id == dest.db_id {
+            return false;
+        }
+        // expired keys are removed first, they must neither be moved nor block the move
+        self.expire_if_needed(key);
+        dest.expire_if_needed(key);
+        // Both databases are locked for writing so that the move is atomic whatever the storage.
+        // Same order as swap: the database with the lowest id is always locked first.
+        let (source_data, dest_data) = if self.db_id < dest.db_id {
+            let source_data = self.data.write().unwrap();
+            (source_data, dest.data.write().unwrap())
+        } else {
+            let dest_data = dest.data.write().unwrap();
+            (self.data.write().unwrap(), dest_data)
+        };
+        if dest_data.contains(key) {
+            return false;
+        }
+        let Some((value, meta)) = source_data.take(key) else {
+            return false;
+        };
+        dest_data.put(key, &value, meta);
+        if let Some(expiry) = meta.and_then(|meta| meta.expiry) {
+            let tracked = (expiry, key.to_string());
+            self.tracking.lock().unwrap().remove(&tracked);
+            dest.tracking.lock().unwrap().insert(tracked);
+        }
+        true
+    }
+
+    /// copy_with_options copies the value of `src` to the key `dst` of `dst_db`, which may be this
+    /// database. The copy keeps the expiration time of `src`, unless `persist` is set. An existing
+    /// `dst` is only overwritten when `replace` is set. It returns true when the key was copied.
+    pub fn copy_with_options(
+        &self,
+        src: &str,
+        dst: &str,
+        dst_db: &Arc<State>,
+        replace: bool,
+        persist: bool,
+    ) -> bool {
+        // expired keys are removed first, they must neither be copied nor block the copy
+        self.expire_if_needed(src);
+        dst_db.expire_if_needed(dst);
+        let copy = |source: &dyn StorageBackend, dest: &dyn StorageBackend| {
+            let Some(value) = source.peek(src) else {
+                return false;
+            };
+            let replaced = dest.meta(dst);
+            if !replace && dest.contains(dst) {
+                return false;
+            }
+            // the copy is a new key, only the expiration time comes from the source
+            let now = Instant::now();
+            let expiry = source.meta(src).and_then(|meta| meta.expiry);
+            let expiry = if persist { None } else { expiry };
+            let meta = KeyMeta {
+                last_access: now,
+                created_at: now,
+                access_count: 0,
+                expiry,
+            };
+            dest.put(dst, &value, Some(meta));
+            let mut tracking = dst_db.tracking.lock().unwrap();
+            if let Some(expiry) = replaced.and_then(|meta| meta.expiry) {
+                tracking.remove(&(expiry, dst.to_string()));
+            }
+            if let Some(expiry) = expiry {
+                tracking.insert((expiry, dst.to_string()));
+            }
+            true
+        };
+
+        if self.db_id == dst_db.db_id {
+            if src == dst {
+                return false;
+            }
+            // Locked for writing so that no other write goes between the check and the copy.
+            #[allow(clippy::readonly_write_lock)]
+            let data = self.data.write().unwrap();
+            return copy(data.as_ref(), data.as_ref());
+        }
+        // Same order as swap: the database with the lowest id is always locked first.
+        let (source_data, dest_data) = if self.db_id < dst_db.db_id {
+            let source_data = self.data.write().unwrap();
+            (source_data, dst_db.data.write().unwrap())
+        } else {
+            let dest_data = dst_db.data.write().unwrap();
+            (self.data.write().unwrap(), dest_data)
+        };
+        copy(source_data.as_ref(), dest_data.as_ref())
+    }
+
+    /// set_eviction_callback registers a function to call with the key and the value of each entry
+    /// removed by the background eviction. It replaces any previously registered callback.
+    pub fn set_eviction_callback(&self, callback: EvictionCallback) {
+        *self.eviction_callback.write().unwrap() = Some(callback);
     }
 
     fn evict_expired_keys(&self) {
-        // get keys that need to be deleted and remove them from tracking
-        let mut guard = self.tracking.lock().unwrap();
-        let cut_off_item = Self::get_last_item_before_cutoff(&guard);
-        let expired_items = guard.split_off(&cut_off_item);
-        // now remove them from the primary storage
-        let mut keys = Vec::new();
-        for item in expired_items {
-            keys.push(item.1);
-        }
-        let evicted = self.data.del_entries(&keys);
+        // get keys that need to be deleted and remove them from tracking.
+        // Everything ranked before (now, "") has expired, the rest stays tracked.
+        let expired_items = {
+            let mut guard = self.tracking.lock().unwrap();
+            let alive_items = guard.split_off(&(Instant::now(), String::new()));
+            std::mem::replace(&mut *guard, alive_items)
+        };
+        // now remove them from the primary storage. A key set again concurrently may have left a
+        // stale entry in the tracking, so keys with a later expiration time are kept.
+        let now = Instant::now();
+        let keys: Vec<String> = {
+            let data = self.data();
+            expired_items
+                .into_iter()
+                .map(|(_, key)| key)
+                .filter(|key| {
+                    data.meta(key)
+                        .is_none_or(|meta| meta.expiry.is_some_and(|expiry| expiry <= now))
+                })
+                .collect()
+        };
+        self.evict(&keys);
+    }
+
+    /// eviction_policy returns the name of the eviction policy in use.
+    pub fn eviction_policy(&self) -> &'static str {
+        self.eviction.read().unwrap().name()
+    }
+
+    /// evict_with_policy brings the database back under its eviction threshold, removing the keys
+    /// chosen by the eviction policy. The policy is first updated from the configuration, so a
+    /// CONFIG SET maxmemory-policy applies from the next run of the eviction job.
+    fn evict_with_policy(&self) {
+        let policy = self.config.maxmemory_policy();
+        if self.eviction_policy() != policy {
+            // the configuration only accepts known policies
+            if let Some(eviction) = eviction::from_policy(&policy) {
+                *self.eviction.write().unwrap() = eviction;
+            }
+        }
+
+        let target = self.capacity * self.config.eviction_threshold() as usize / 100;
+        let size = self.data().size();
+        // noeviction selects nothing, there is no need to list the keys
+        if size <= target || policy == "noeviction" {
+            return;
+        }
+        let candidates = self.data().keys_with_meta();
+        let keys = self
+            .eviction
+            .read()
+            .unwrap()
+            .select(candidates, size - target);
+        self.evict(&keys);
+    }
+
+    /// evict removes keys from the storage, counting them in the metrics and notifying the
+    /// eviction callback.
+    fn evict(&self, keys: &[String]) {
+        if keys.is_empty() {
+            return;
+        }
+        let evicted = self.data().remove_entries(keys);
+        let callback = self.eviction_callback.read().unwrap().clone();
         // emit metrics
         describe_counter!(METRIC_EVICTED_KEY, METRIC_EVICTED_KEY_DESC);
-        for (shard_id, count) in evicted {
+        for (shard_id, entries) in evicted {
             counter!(METRIC_EVICTED_KEY, LABEL_EVICTED_KEY_SHARD => shard_id.to_string())
-                .increment(count as u64);
+                .increment(entries.len() as u64);
+            if let Some(callback) = &callback {
+                Self::notify_evictions(callback, &entries);
+            }
         }
         // todo!("can we set metric description only once in main?");
     }
 
-    fn get_last_item_before_cutoff(
-        instants: &MutexGuard<BTreeSet<(Instant, String)>>,
-    ) -> (Instant, String) {
-        let cut_off_instant = Instant::now();
-        let mut prev_item = (Instant::now(), String::new());
-        for item in instants.iter() {
-            if item.0 >= cut_off_instant {
-                break;
+    /// notify_evictions calls the eviction callback for each entry. A panicking callback must not
+    /// take the eviction job down with it, so panics are caught and logged.
+    fn notify_evictions(callback: &EvictionCallback, entries: &[(String, String)]) {
+        for (key, value) in entries {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| callback(key, value)));
+            if result.is_err() {
+                error!(key = key, "eviction callback panicked");
             }
-            prev_item = item.clone();
         }
-        prev_item
     }
 
-    pub fn set_kv(&self, key: &str, value: &str, _ttl: Option<Duration>) {
-        // Insert
-        // let expiration_time = if let Some(ttl) = ttl {
-        //     Instant::now() + ttl
-        // } else {
-        //     Instant::now()
-        // };
-        // let entry = CacheEntry::new(key, value, expiration_time);
-        self.data.set_kv(key, value);
+    /// set_kvs sets several keys at once, dropping their expiration times like set_kv does. Whether
+    /// other commands may see some of the keys set and not the others depends on the backend, see
+    /// StorageBackend::set_many.
+    pub fn set_kvs(&self, pairs: &[(String, String)]) {
+        let previous = self.data().set_many(pairs);
+        for ((key, _), previous) in pairs.iter().zip(previous) {
+            self.track_expiry(key, previous, None);
+        }
+        self.notify_cleanup_if_needed();
+    }
 
-        let current_size = self.data.size();
+    /// incr_by adds `delta` to the integer stored at a key and returns the new value. A missing key
+    /// counts as 0. The value is read and written with one storage update, so concurrent
+    /// increments of a key are not lost on backends with an atomic update.
+    pub fn incr_by(&self, key: &str, delta: i64) -> Result<i64, CommandError> {
+        self.expire_if_needed(key);
+        let mut result = Ok(0);
+        let updated = self.data().update(
+            key,
+            Box::new(|current| {
+                result = match current.map(str::parse::<i64>) {
+                    Some(Err(_)) => Err(CommandError::Malformed(
+                        "value is not an integer or out of range".to_string(),
+                    )),
+                    current => current
+                        .unwrap_or(Ok(0))
+                        .ok()
+                        .and_then(|current| current.checked_add(delta))
+                        .ok_or_else(|| {
+                            CommandError::Malformed(
+                                "increment or decrement would overflow".to_string(),
+                            )
+                        }),
+                };
+                result.as_ref().ok().map(i64::to_string)
+            }),
+        );
+        if updated {
+            self.notify_cleanup_if_needed();
+        }
+        result
+    }
+
+    /// get_and_set stores the value of a key and returns its previous value. Like set_kv, it drops
+    /// the expiration time of the key: the value is read and replaced, and the expiration time
+    /// dropped, under the same shard lock.
+    pub fn get_and_set(&self, key: &str, new_value: &str) -> Option<String> {
+        self.expire_if_needed(key);
+        let (previous, previous_expiry) = self.data().replace_persist(key, new_value);
+        self.track_expiry(key, previous_expiry, None);
+        if previous.is_none() {
+            self.notify_cleanup_if_needed();
+        }
+        previous
+    }
+
+    /// append_value adds `suffix` at the end of the value of a key and returns the length of the
+    /// new value. A missing key is created with the suffix as value. The key is left unchanged
+    /// when the new value would be larger than max-value-bytes.
+    pub fn append_value(&self, key: &str, suffix: &str) -> Result<usize, CommandError> {
+        self.expire_if_needed(key);
+        let len = self
+            .data()
+            .append(key, suffix, self.config.max_value_bytes())
+            .ok_or_else(|| CommandError::Malformed("value too large".to_string()))?;
+        self.notify_cleanup_if_needed();
+        Ok(len)
+    }
+
+    /// set_if_absent sets a key only when it does not exist, and returns true when it was set.
+    /// The check and the insertion are done under the same shard lock.
+    pub fn set_if_absent(&self, key: &str, value: &str) -> bool {
+        self.expire_if_needed(key);
+        let (_, inserted) = self
+            .data()
+            .get_or_insert_with(key, Box::new(|| value.to_string()));
+        if inserted {
+            self.notify_cleanup_if_needed();
+        }
+        inserted
+    }
+
+    /// set_kv sets the value of a key. The key expires after `ttl`, or never when it is None: like
+    /// a Redis SET, the expiration time of an existing key is not kept.
+    pub fn set_kv(&self, key: &str, value: &str, ttl: Option<Duration>) {
+        self.set_kv_with_options(key, value, ttl, false, false);
+    }
+
+    /// set_kv_with_options works like set_kv, but only sets a key which does not exist when `nx`
+    /// is set, and only a key which exists when `xx` is set. It returns true when the key was set.
+    pub fn set_kv_with_options(
+        &self,
+        key: &str,
+        value: &str,
+        ttl: Option<Duration>,
+        nx: bool,
+        xx: bool,
+    ) -> bool {
+        let expiry = ttl.map(|ttl| Instant::now() + ttl);
+        let set = |data: &dyn StorageBackend| {
+            if (nx || xx) && data.contains(key) != xx {
+                return None;
+            }
+            data.set(key, value);
+            Some(data.set_expiry(key, expiry))
+        };
+        let previous = if nx || xx {
+            // an expired key which was not removed yet must not count as existing
+            self.expire_if_needed(key);
+            // Locked for writing so that no other write goes between the check and the write.
+            #[allow(clippy::readonly_write_lock)]
+            let data = self.data.write().unwrap();
+            set(data.as_ref())
+        } else {
+            // the read lock is held until the expiry is set, so the key cannot be expired in
+            // between based on its previous expiration time
+            set(self.data().as_ref())
+        };
+        let Some(previous) = previous else {
+            return false;
+        };
+        self.track_expiry(key, previous, expiry);
+        self.notify_cleanup_if_needed();
+        true
+    }
+
+    /// set_expiry sets the expiration time of an existing key, replacing its previous one in the
+    /// tracking of the eviction job. It returns false when the key does not exist.
+    pub fn set_expiry(&self, key: &str, expiry: Instant) -> bool {
+        self.expire_if_needed(key);
+        let previous = {
+            let data = self.data();
+            if !data.contains(key) {
+                return false;
+            }
+            data.set_expiry(key, Some(expiry))
+        };
+        self.track_expiry(key, previous, Some(expiry));
+        true
+    }
+
+    /// track_expiry replaces the expiration time of a key in the tracking of the eviction job.
+    fn track_expiry(&self, key: &str, previous: Option<Instant>, expiry: Option<Instant>) {
+        if previous.is_none() && expiry.is_none() {
+            return;
+        }
+        let mut tracking = self.tracking.lock().unwrap();
+        if let Some(previous) = previous {
+            tracking.remove(&(previous, key.to_string()));
+        }
+        if let Some(expiry) = expiry {
+            tracking.insert((expiry, key.to_string()));
+        }
+    }
+
+    /// key_expiration returns the time at which a key expires, None when the key does not exist,
+    /// does not expire, or is stored by a backend which does not keep metadata.
+    pub fn key_expiration(&self, key: &str) -> Option<Instant> {
+        self.data().meta(key).and_then(|meta| meta.expiry)
+    }
+
+    /// expire_if_needed removes a key whose expiration time has passed, without waiting for the
+    /// eviction job, and returns true when it did.
+    fn expire_if_needed(&self, key: &str) -> bool {
+        if self
+            .key_expiration(key)
+            .is_none_or(|expiry| expiry > Instant::now())
+        {
+            return false;
+        }
+        // Checked again under the write lock: the key may have been set again meanwhile.
+        #[allow(clippy::readonly_write_lock)]
+        let data = self.data.write().unwrap();
+        let Some(expiry) = data.meta(key).and_then(|meta| meta.expiry) else {
+            return false;
+        };
+        if expiry > Instant::now() {
+            return false;
+        }
+        data.del(&[key]);
+        self.tracking
+            .lock()
+            .unwrap()
+            .remove(&(expiry, key.to_string()));
+        true
+    }
+
+    /// get_or_load is a read-through accessor: it returns the value of a key, calling `loader` to
+    /// fetch and store it when the key is absent. The loader runs without holding any lock of the
+    /// storage. A burst of misses on the same key only loads it once: the other callers wait for
+    /// the load, and one of them loads the key again if the loader panicked.
+    pub fn get_or_load<F>(&self, key: &str, loader: F) -> String
+    where
+        F: FnOnce(&str) -> (String, Option<Duration>),
+    {
+        let _loading = loop {
+            if let Some(value) = self.get_value_by_key(key) {
+                return value;
+            }
+            let mut loading = self.loading.lock().unwrap();
+            let Some(load) = loading.get(key).cloned() else {
+                let load = Arc::new((Mutex::new(false), Condvar::new()));
+                loading.insert(key.to_string(), load.clone());
+                break Loading {
+                    state: self,
+                    key,
+                    load,
+                };
+            };
+            drop(loading);
+            let (done, loaded) = &*load;
+            let _done = loaded
+                .wait_while(done.lock().unwrap(), |done| !*done)
+                .unwrap();
+        };
+        // The key may have been loaded between the first read and the registration of the load.
+        if let Some(value) = self.get_value_by_key(key) {
+            return value;
+        }
+
+        let (value, ttl) = loader(key);
+        // A value set while the loader ran is kept.
+        let (value, loaded) = self.data().get_or_insert_with(key, Box::new(move || value));
+        if loaded {
+            if let Some(ttl) = ttl {
+                let expiry = Instant::now() + ttl;
+                let previous = self.data().set_expiry(key, Some(expiry));
+                self.track_expiry(key, previous, Some(expiry));
+            }
+            self.notify_cleanup_if_needed();
+        }
+        value
+    }
+
+    /// notify_cleanup_if_needed wakes up the background eviction job when the storage reaches
+    /// the eviction threshold.
+    fn notify_cleanup_if_needed(&self) {
+        let current_size = self.data().size();
 
         // check if global eviction is needed
-        if current_size >= (self.capacity * self.auto_eviction_threshold as usize / 100) {
+        let auto_eviction_threshold = self.config.eviction_threshold() as usize;
+        if current_size >= (self.capacity * auto_eviction_threshold / 100) {
             let (lock, cvar) = &*self.cleanup_needed;
             let mut cleanup_threshold_reached = lock.lock().unwrap();
             *cleanup_threshold_reached = true;
@@ -210,25 +689,63 @@ impl State {
                 current_size
             );
         }
-
-        // // Track key
-        // self.tracking
-        //     .lock()
-        //     .unwrap()
-        //     .insert((expiration_time, key.to_string()));
     }
 
+    /// get_value_by_key returns the value of a key. An expired key is removed on the way and
+    /// reads as missing.
     pub fn get_value_by_key(&self, key: &str) -> Option<String> {
-        self.data.get_value(key)
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        self.data().get(key)
+    }
+
+    /// hot_keys returns up to `top_k` keys among the most read recently, with their number of reads.
+    pub fn hot_keys(&self, top_k: usize) -> Vec<(String, u64)> {
+        self.data().hot_keys(top_k)
     }
 
-    pub fn delete_entries(&self, keys: &Vec<String>) -> usize {
-        let deleted_items = self.data.del_entries(keys);
-        let mut ans = 0;
-        for (_, v) in deleted_items {
-            ans += v;
+    /// config returns the server configuration shared by the cache.
+    pub fn config(&self) -> &Arc<ServerConfig> {
+        &self.config
+    }
+
+    /// peek_value returns the value of a key, like get_value_by_key, without updating its metadata.
+    /// It is meant for introspection commands which should not count as key accesses.
+    /// An expired key is removed on the way and reads as missing.
+    pub fn peek_value(&self, key: &str) -> Option<String> {
+        if self.expire_if_needed(key) {
+            return None;
         }
-        ans
+        self.data().peek(key)
+    }
+
+    /// get_meta returns the bookkeeping information of a key, if it exists. An expired key is
+    /// removed on the way and reads as missing.
+    pub fn get_meta(&self, key: &str) -> Option<KeyMeta> {
+        if self.expire_if_needed(key) {
+            return None;
+        }
+        self.data().meta(key)
+    }
+
+    /// delete_entries removes keys and returns the number of keys removed. Expired keys are not
+    /// counted, and the removed keys leave the tracking of the eviction job.
+    pub fn delete_entries(&self, keys: &[String]) -> usize {
+        let mut deleted = 0;
+        for key in keys {
+            if self.expire_if_needed(key) {
+                continue;
+            }
+            let Some((_, meta)) = self.data().take(key) else {
+                continue;
+            };
+            deleted += 1;
+            if let Some(expiry) = meta.and_then(|meta| meta.expiry) {
+                self.tracking.lock().unwrap().remove(&(expiry, key.clone()));
+            }
+        }
+        deleted
     }
 }
 
@@ -236,8 +753,497 @@ impl Debug for State {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "State{{capacity: {}, shards: {}}}",
-            self.capacity, self.shard_count
+            "State{{db: {}, capacity: {}, storage: {:?}}}",
+            self.db_id,
+            self.capacity,
+            self.data()
         )
     }
 }
+
+/// Loading marks a key being loaded by get_or_load. Dropping it ends the load, also when the
+/// loader panicked, and wakes up the callers waiting for it.
+struct Loading<'a> {
+    state: &'a State,
+    key: &'a str,
+    load: LoadDone,
+}
+
+impl Drop for Loading<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut loading) = self.state.loading.lock() {
+            loading.remove(self.key);
+        }
+        let (done, loaded) = &*self.load;
+        if let Ok(mut done) = done.lock() {
+            *done = true;
+        }
+        loaded.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn new_db(
+        db_id: usize,
+        cleanup_needed: Arc<(Mutex<bool>, Condvar)>,
+        config: Arc<ServerConfig>,
+    ) -> State {
+        let storage = Box::new(CMap::new(4, 16).unwrap());
+        State::new(db_id, storage, 64, cleanup_needed, config)
+    }
+
+    fn new_state() -> State {
+        let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
+        let config = Arc::new(ServerConfig::new(99).unwrap());
+        new_db(0, cleanup_needed, config)
+    }
+
+    fn track(state: &State, key: &str, expiration_time: Instant) {
+        state
+            .tracking
+            .lock()
+            .unwrap()
+            .insert((expiration_time, key.to_string()));
+    }
+
+    #[test]
+    fn test_eviction_callback_called_for_each_evicted_key() {
+        let state = new_state();
+        for i in 0..10 {
+            let key = format!("key{}", i);
+            state.set_kv(
+                &key,
+                &format!("value{}", i),
+                Some(Duration::from_millis(10)),
+            );
+        }
+        state.set_kv("alive", "value", Some(Duration::from_secs(3600)));
+        std::thread::sleep(Duration::from_millis(50));
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let evicted_clone = evicted.clone();
+        state.set_eviction_callback(Arc::new(move |key, value| {
+            evicted_clone
+                .lock()
+                .unwrap()
+                .push((key.to_string(), value.to_string()));
+        }));
+        state.evict_expired_keys();
+
+        let mut evicted = evicted.lock().unwrap().clone();
+        evicted.sort();
+        let mut want: Vec<(String, String)> = (0..10)
+            .map(|i| (format!("key{}", i), format!("value{}", i)))
+            .collect();
+        want.sort();
+        assert_eq!(evicted, want);
+        assert_eq!(state.get_value_by_key("alive"), Some("value".to_string()));
+        assert_eq!(state.data().size(), 1);
+    }
+
+    #[test]
+    fn test_set_kv_with_ttl() {
+        let state = new_state();
+        state.set_kv("short", "value", Some(Duration::from_millis(10)));
+        state.set_kv("long", "value", Some(Duration::from_secs(3600)));
+        assert!(state.key_expiration("long").unwrap() > Instant::now());
+        assert_eq!(state.tracking.lock().unwrap().len(), 2);
+
+        // setting a key again without a ttl makes it persistent
+        state.set_kv("long", "other", None);
+        assert_eq!(state.key_expiration("long"), None);
+        assert_eq!(state.tracking.lock().unwrap().len(), 1);
+
+        // expired keys read as missing and are removed without waiting for the eviction job
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(state.get_value_by_key("short"), None);
+        assert_eq!(state.peek_value("short"), None);
+        assert!(state.tracking.lock().unwrap().is_empty());
+        assert_eq!(state.get_value_by_key("long"), Some("other".to_string()));
+
+        // a stale tracking entry does not evict a key set again meanwhile
+        track(&state, "long", Instant::now());
+        state.evict_expired_keys();
+        assert_eq!(state.get_value_by_key("long"), Some("other".to_string()));
+    }
+
+    #[test]
+    fn test_delete_entries() {
+        let state = new_state();
+        state.set_kv("live", "value", Some(Duration::from_secs(60)));
+        state.set_kv("expired", "value", Some(Duration::from_millis(1)));
+        thread::sleep(Duration::from_millis(5));
+        let keys = ["live".to_string(), "expired".to_string()];
+        assert_eq!(state.delete_entries(&keys), 1);
+        assert_eq!(state.data().size(), 0);
+        assert!(state.tracking.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_expiry() {
+        let state = new_state();
+        let expiry = Instant::now() + Duration::from_secs(60);
+        assert!(!state.set_expiry("missing", expiry));
+        state.set_kv("key", "value", Some(Duration::from_secs(10)));
+        assert!(state.set_expiry("key", expiry));
+        assert_eq!(state.key_expiration("key"), Some(expiry));
+        // the previous expiration time is not tracked anymore
+        assert_eq!(
+            *state.tracking.lock().unwrap(),
+            BTreeSet::from([(expiry, "key".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_evict_expired_keys() {
+        let state = new_state();
+        for i in 0..100 {
+            let key = format!("key{}", i);
+            state.set_kv(&key, "value", Some(Duration::from_millis(10)));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        state.evict_expired_keys();
+        assert_eq!(state.data().size(), 0);
+        for i in 0..100 {
+            assert_eq!(state.get_value_by_key(&format!("key{}", i)), None);
+        }
+
+        state.set_kv("alive", "value", Some(Duration::from_secs(3600)));
+        state.evict_expired_keys();
+        assert_eq!(state.get_value_by_key("alive"), Some("value".to_string()));
+        assert_eq!(state.tracking.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_evict_expired_keys_cut_off() {
+        // the expired keys are evicted and the live ones kept, whatever their order in the tracking
+        let state = new_state();
+        let now = Instant::now();
+        for i in 0..10 {
+            let key = format!("key{}", i);
+            state.set_kv(&key, "value", None);
+            let expiry = if i % 2 == 0 {
+                now - Duration::from_secs(10 - i)
+            } else {
+                now + Duration::from_secs(3600 + i)
+            };
+            assert!(state.set_expiry(&key, expiry));
+        }
+        state.evict_expired_keys();
+
+        for i in 0..10 {
+            let value = state.peek_value(&format!("key{}", i));
+            assert_eq!(value.is_some(), i % 2 == 1, "key{}", i);
+        }
+        let tracked: Vec<_> = state
+            .tracking
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(expiry, key)| {
+                assert!(*expiry > now);
+                key.clone()
+            })
+            .collect();
+        assert_eq!(tracked, ["key1", "key3", "key5", "key7", "key9"]);
+    }
+
+    #[test]
+    fn test_change_eviction_policy_under_load() {
+        let cache = create_cache(256, 4, 50).unwrap();
+        let db = cache.db(0).unwrap();
+        let target = 128;
+        // wait_for_eviction waits for the eviction job to bring the database back to its target
+        let wait_for_eviction = |db: &State| {
+            let start = Instant::now();
+            while db.data().size() > target {
+                assert!(
+                    start.elapsed() < Duration::from_secs(5),
+                    "eviction did not run, size is {}",
+                    db.data().size()
+                );
+                thread::sleep(Duration::from_millis(5));
+            }
+        };
+        let load = |db: &Arc<State>, prefix: &'static str| {
+            let writers: Vec<_> = (0..4)
+                .map(|writer| {
+                    let db = db.clone();
+                    thread::spawn(move || {
+                        for i in 0..500 {
+                            db.set_kv(&format!("{}:{}:{}", prefix, writer, i), "value", None);
+                        }
+                    })
+                })
+                .collect();
+            for writer in writers {
+                writer.join().unwrap();
+            }
+        };
+
+        db.config()
+            .set(&[("maxmemory-policy", "allkeys-lfu")])
+            .unwrap();
+        db.set_kv("frequent", "value", None);
+        for _ in 0..10 {
+            db.get_value_by_key("frequent");
+        }
+        load(&db, "lfu");
+        wait_for_eviction(&db);
+        assert_eq!(db.eviction_policy(), "allkeys-lfu");
+        assert!(db.peek_value("frequent").is_some());
+
+        db.config()
+            .set(&[("maxmemory-policy", "allkeys-lru")])
+            .unwrap();
+        load(&db, "lru");
+        wait_for_eviction(&db);
+        assert_eq!(db.eviction_policy(), "allkeys-lru");
+        // the keys read often but not recently are evicted first
+        assert!(db.peek_value("frequent").is_none());
+    }
+
+    #[test]
+    fn test_copy_with_options() {
+        let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
+        let config = Arc::new(ServerConfig::new(99).unwrap());
+        let first = Arc::new(new_db(0, cleanup_needed.clone(), config.clone()));
+        let second = Arc::new(new_db(1, cleanup_needed, config));
+        first.set_kv("a", "1", Some(Duration::from_secs(3600)));
+        let expiry = first.key_expiration("a").unwrap();
+
+        assert!(first.copy_with_options("a", "b", &first, false, false));
+        assert_eq!(first.peek_value("b"), Some("1".to_string()));
+        assert_eq!(first.get_meta("b").unwrap().expiry, Some(expiry));
+        assert!(first
+            .tracking
+            .lock()
+            .unwrap()
+            .contains(&(expiry, "b".to_string())));
+
+        assert!(first.copy_with_options("a", "a", &second, false, true));
+        assert_eq!(second.peek_value("a"), Some("1".to_string()));
+        assert_eq!(second.get_meta("a").unwrap().expiry, None);
+        assert!(second.tracking.lock().unwrap().is_empty());
+
+        // existing keys are only overwritten with replace
+        first.set_kv("c", "3", None);
+        assert!(!first.copy_with_options("c", "b", &first, false, false));
+        assert_eq!(first.peek_value("b"), Some("1".to_string()));
+        assert!(first.copy_with_options("c", "b", &first, true, false));
+        assert_eq!(first.peek_value("b"), Some("3".to_string()));
+        assert_eq!(first.get_meta("b").unwrap().expiry, None);
+        assert!(!first
+            .tracking
+            .lock()
+            .unwrap()
+            .contains(&(expiry, "b".to_string())));
+
+        assert!(!first.copy_with_options("missing", "d", &second, true, false));
+        assert!(!first.copy_with_options("a", "a", &first, true, false));
+        // the source is left untouched
+        assert_eq!(first.get_meta("a").unwrap().expiry, Some(expiry));
+    }
+
+    #[test]
+    fn test_concurrent_copies_do_not_deadlock() {
+        let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
+        let config = Arc::new(ServerConfig::new(99).unwrap());
+        let first = Arc::new(new_db(0, cleanup_needed.clone(), config.clone()));
+        let second = Arc::new(new_db(1, cleanup_needed, config));
+        first.set_kv("a", "1", None);
+        second.set_kv("b", "2", None);
+
+        // copies in both directions and within a database, at the same time
+        let pairs = [
+            (first.clone(), second.clone(), "a"),
+            (second.clone(), first.clone(), "b"),
+            (first.clone(), first.clone(), "a"),
+            (second.clone(), second.clone(), "b"),
+        ];
+        let (done, finished) = std::sync::mpsc::channel();
+        for (source, dest, key) in pairs {
+            let done = done.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    source.copy_with_options(key, &format!("copy{}", i % 8), &dest, true, false);
+                }
+                done.send(()).unwrap();
+            });
+        }
+        for _ in 0..4 {
+            finished
+                .recv_timeout(Duration::from_secs(5))
+                .expect("the copies did not complete within 5 seconds");
+        }
+        assert_eq!(first.peek_value("a"), Some("1".to_string()));
+        assert_eq!(second.peek_value("b"), Some("2".to_string()));
+        assert!(first.peek_value("copy0").is_some());
+    }
+
+    #[test]
+    fn test_get_or_load() {
+        let state = new_state();
+        state.set_kv("cached", "value", None);
+        let value = state.get_or_load("cached", |_| panic!("cached keys must not be loaded"));
+        assert_eq!(value, "value");
+
+        let value = state.get_or_load("missing", |key| (format!("loaded {}", key), None));
+        assert_eq!(value, "loaded missing");
+        assert_eq!(
+            state.get_value_by_key("missing"),
+            Some("loaded missing".to_string())
+        );
+        assert_eq!(state.data().size(), 2);
+
+        let ttl = Some(Duration::from_secs(60));
+        state.get_or_load("expiring", |key| (key.to_string(), ttl));
+        assert!(state.key_expiration("expiring").is_some());
+    }
+
+    #[test]
+    fn test_incr_by() {
+        let state = Arc::new(new_state());
+        assert_eq!(state.incr_by("counter", 5).unwrap(), 5);
+        assert_eq!(state.incr_by("counter", -7).unwrap(), -2);
+        state.set_kv("text", "abc", None);
+        assert!(state.incr_by("text", 1).is_err());
+        state.set_kv("max", &i64::MAX.to_string(), None);
+        assert!(state.incr_by("max", 1).is_err());
+        assert_eq!(state.peek_value("max"), Some(i64::MAX.to_string()));
+
+        // concurrent increments are not lost
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        state.incr_by("hits", 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(state.peek_value("hits"), Some("8000".to_string()));
+    }
+
+    #[test]
+    fn test_get_or_load_loads_once_under_contention() {
+        let state = Arc::new(new_state());
+        let loads = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                let loads = loads.clone();
+                thread::spawn(move || {
+                    state.get_or_load("hot", |_| {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        ("value".to_string(), None)
+                    })
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "value");
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_get_or_load_panicking_loader() {
+        let state = new_state();
+        let loaded = panic::catch_unwind(AssertUnwindSafe(|| {
+            state.get_or_load("key", |_| panic!("the backing store is down"))
+        }));
+        assert!(loaded.is_err());
+        // nothing is stored nor left locked
+        assert!(state.is_available());
+        assert_eq!(state.peek_value("key"), None);
+        assert!(state.loading.lock().unwrap().is_empty());
+        let value = state.get_or_load("key", |_| ("value".to_string(), None));
+        assert_eq!(value, "value");
+    }
+
+    #[test]
+    fn test_get_or_load_loader_uses_the_cache() {
+        let state = new_state();
+        state.set_kv("other", "1", None);
+        // the storage is not locked while the loader runs
+        let value = state.get_or_load("key", |key| {
+            state.set_kv(key, "set meanwhile", None);
+            (state.get_value_by_key("other").unwrap(), None)
+        });
+        assert_eq!(value, "set meanwhile");
+        assert_eq!(state.peek_value("key"), Some("set meanwhile".to_string()));
+    }
+
+    #[test]
+    fn test_eviction_survives_panicking_callback() {
+        let state = new_state();
+        for i in 0..3 {
+            let key = format!("key{}", i);
+            state.set_kv(&key, "value", Some(Duration::from_millis(10)));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        state.set_eviction_callback(Arc::new(|_, _| panic!("callback failure")));
+        state.evict_expired_keys();
+
+        assert_eq!(state.data().size(), 0);
+        assert!(state.tracking.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_swap() {
+        let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
+        let config = Arc::new(ServerConfig::new(99).unwrap());
+        let first = new_db(0, cleanup_needed.clone(), config.clone());
+        let second = new_db(1, cleanup_needed, config);
+        first.set_kv("a", "1", None);
+        second.set_kv("b", "2", None);
+        track(&first, "a", Instant::now());
+
+        second.swap(&first);
+        assert_eq!(first.get_value_by_key("b"), Some("2".to_string()));
+        assert_eq!(first.get_value_by_key("a"), None);
+        assert_eq!(second.get_value_by_key("a"), Some("1".to_string()));
+        assert!(first.tracking.lock().unwrap().is_empty());
+        assert_eq!(second.tracking.lock().unwrap().len(), 1);
+
+        // swapping a database with itself changes nothing
+        first.swap(&first);
+        assert_eq!(first.get_value_by_key("b"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn test_move_key() {
+        let cleanup_needed = Arc::new((Mutex::new(false), Condvar::new()));
+        let config = Arc::new(ServerConfig::new(99).unwrap());
+        let first = new_db(0, cleanup_needed.clone(), config.clone());
+        let second = new_db(1, cleanup_needed, config);
+        first.set_kv("a", "1", None);
+        first.get_value_by_key("a");
+        second.set_kv("b", "2", None);
+        first.set_kv("b", "3", None);
+
+        assert!(first.move_key("a", &second));
+        assert_eq!(first.peek_value("a"), None);
+        assert_eq!(second.peek_value("a"), Some("1".to_string()));
+        // the metadata moves with the key
+        assert_eq!(second.get_meta("a").unwrap().access_count, 1);
+        assert_eq!((first.data().size(), second.data().size()), (1, 2));
+
+        // existing keys are not overwritten, missing keys are not moved
+        assert!(!first.move_key("b", &second));
+        assert_eq!(second.peek_value("b"), Some("2".to_string()));
+        assert!(!second.move_key("missing", &first));
+        assert!(second.move_key("a", &first));
+        assert_eq!(first.peek_value("a"), Some("1".to_string()));
+    }
+}
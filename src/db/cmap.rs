@@ -1,44 +1,190 @@
 use crate::db;
+use crate::db::storage::UpdateFn;
+use crate::db::StorageBackend;
 use rustc_hash::FxHashMap;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::io;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Instant;
 
+/// KeyMeta holds bookkeeping information about a key, used for introspection commands.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyMeta {
+    pub last_access: Instant,
+    pub created_at: Instant,
+    // number of times the key was read or overwritten since its creation
+    pub access_count: u64,
+    pub expiry: Option<Instant>,
+}
+
+impl KeyMeta {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            last_access: now,
+            created_at: now,
+            access_count: 0,
+            expiry: None,
+        }
+    }
+
+    fn touch(&mut self) {
+        self.last_access = Instant::now();
+        self.access_count += 1;
+    }
+}
+
+/// Number of reads of a bucket after which its hot keys are computed again.
+const HOT_KEYS_PERIOD: u64 = 10_000;
+/// Number of hot keys kept by bucket.
+const MAX_HOT_KEYS_PER_BUCKET: usize = 16;
+/// Default number of reads during a period which makes a key hot, 1% of the reads of its bucket.
+pub const DEFAULT_HOT_KEY_THRESHOLD: usize = 100;
+
 pub struct Bucket {
     storage: FxHashMap<String, String>,
+    // Metadata of the keys in storage. Both maps always hold the same keys.
+    meta: FxHashMap<String, KeyMeta>,
     _eviction_state: BinaryHeap<(Instant, String)>,
+    // Reads of the current period, in total and by key.
+    accesses: u64,
+    key_accesses: FxHashMap<String, u64>,
+    // Keys read at least the hot key threshold during the last period, the most read first.
+    hot_keys: Vec<(String, u64)>,
 }
 
 impl Bucket {
     fn new(capacity: usize) -> Bucket {
         Bucket {
             storage: FxHashMap::default(),
+            meta: FxHashMap::default(),
             _eviction_state: BinaryHeap::with_capacity(capacity),
+            accesses: 0,
+            key_accesses: FxHashMap::default(),
+            hot_keys: Vec::new(),
         }
     }
 
-    fn get_value_by_key(&self, key: &str) -> Option<&String> {
+    /// get_value_by_key returns the value of a key and counts the read. Every HOT_KEYS_PERIOD
+    /// reads, the keys read at least `hot_key_threshold` times become the hot keys of the bucket.
+    fn get_value_by_key(&mut self, key: &str, hot_key_threshold: usize) -> Option<&String> {
+        if let Some(meta) = self.meta.get_mut(key) {
+            meta.touch();
+            // the key is only copied the first time it is read during the period
+            match self.key_accesses.get_mut(key) {
+                Some(count) => *count += 1,
+                None => {
+                    self.key_accesses.insert(key.to_string(), 1);
+                }
+            }
+            self.accesses += 1;
+            if self.accesses >= HOT_KEYS_PERIOD {
+                self.update_hot_keys(hot_key_threshold);
+            }
+        }
         self.storage.get(key)
     }
 
+    fn update_hot_keys(&mut self, hot_key_threshold: usize) {
+        let mut hot_keys: Vec<(String, u64)> = self
+            .key_accesses
+            .drain()
+            .filter(|(_, count)| *count >= hot_key_threshold as u64)
+            .collect();
+        hot_keys.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hot_keys.truncate(MAX_HOT_KEYS_PER_BUCKET);
+        self.hot_keys = hot_keys;
+        self.accesses = 0;
+    }
+
     fn add_entry_or_update(&mut self, key: String, value: String) -> Option<String> {
         // self._eviction_state.push((Instant::now(), key.clone()));
+        match self.meta.get_mut(&key) {
+            Some(meta) => meta.touch(),
+            None => {
+                self.meta.insert(key.clone(), KeyMeta::new());
+            }
+        }
         self.storage.insert(key, value)
     }
 
-    fn remove_entry(&mut self, key: &str) -> usize {
-        if self.storage.remove(key).is_some() {
-            return 1;
+    /// append_to_entry adds a suffix to the value of a key, creating the key when it is absent.
+    /// It returns the length of the new value and whether the key was created.
+    /// The value is left unchanged when it would get longer than `max_len`: None is returned.
+    fn append_to_entry(
+        &mut self,
+        key: &str,
+        suffix: &str,
+        max_len: usize,
+    ) -> Option<(usize, bool)> {
+        let len = self.storage.get(key).map_or(0, String::len);
+        if len + suffix.len() > max_len {
+            return None;
         }
-        0
+        Some(match self.storage.get_mut(key) {
+            Some(value) => {
+                value.push_str(suffix);
+                if let Some(meta) = self.meta.get_mut(key) {
+                    meta.touch();
+                }
+                (value.len(), false)
+            }
+            None => {
+                self.add_entry_or_update(key.to_string(), suffix.to_string());
+                (suffix.len(), true)
+            }
+        })
+    }
+
+    fn remove_entry(&mut self, key: &str) -> Option<(String, String)> {
+        self.meta.remove(key);
+        self.forget_accesses(key);
+        self.storage.remove_entry(key)
+    }
+
+    /// forget_accesses drops the reads counted for a key which left the bucket, so that it is
+    /// not reported as hot anymore.
+    fn forget_accesses(&mut self, key: &str) {
+        self.key_accesses.remove(key);
+        self.hot_keys.retain(|(hot_key, _)| hot_key != key);
+    }
+
+    fn peek_value(&self, key: &str) -> Option<&String> {
+        self.storage.get(key)
+    }
+
+    fn get_meta(&self, key: &str) -> Option<KeyMeta> {
+        self.meta.get(key).copied()
+    }
+
+    fn set_expiry(&mut self, key: &str, expiry: Option<Instant>) -> Option<Instant> {
+        let meta = self.meta.get_mut(key)?;
+        std::mem::replace(&mut meta.expiry, expiry)
     }
 
     fn contains_key(&self, key: &str) -> bool {
         self.storage.contains_key(key)
     }
+
+    fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// take_entry removes a key and returns its value along with its metadata.
+    fn take_entry(&mut self, key: &str) -> Option<(String, String, KeyMeta)> {
+        let meta = self.meta.remove(key)?;
+        self.forget_accesses(key);
+        let (key, value) = self.storage.remove_entry(key)?;
+        Some((key, value, meta))
+    }
+
+    /// put_entry inserts a key with existing metadata, as taken from another bucket.
+    fn put_entry(&mut self, key: String, value: String, meta: KeyMeta) {
+        self.meta.insert(key.clone(), meta);
+        self.storage.insert(key, value);
+    }
 }
 
 pub struct CMap {
@@ -46,6 +192,8 @@ pub struct CMap {
     // shard size should be a power of two
     shard_count: usize,
     size: AtomicUsize,
+    // Number of reads during a period which makes a key hot.
+    hot_key_threshold: usize,
 }
 
 impl Debug for CMap {
@@ -95,9 +243,30 @@ impl CMap {
             shards,
             shard_count,
             size: Default::default(),
+            hot_key_threshold: DEFAULT_HOT_KEY_THRESHOLD,
         })
     }
 
+    /// with_hot_key_threshold sets the number of reads, out of the HOT_KEYS_PERIOD reads of a
+    /// bucket, which makes a key hot.
+    pub fn with_hot_key_threshold(mut self, hot_key_threshold: usize) -> Self {
+        self.hot_key_threshold = hot_key_threshold;
+        self
+    }
+
+    /// hot_keys returns up to `top_k` keys read the most during the last period of their bucket,
+    /// with their number of reads. Keys under the hot key threshold are not returned.
+    pub fn hot_keys(&self, top_k: usize) -> Vec<(String, u64)> {
+        let mut hot_keys: Vec<(String, u64)> = self
+            .apply_mut_fn_shards(|bucket| bucket.hot_keys.clone())
+            .into_iter()
+            .flatten()
+            .collect();
+        hot_keys.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hot_keys.truncate(top_k);
+        hot_keys
+    }
+
     pub fn set_kv(&self, key: &str, value: &str) {
         let previous_value = self
             .get_shard_by_key(key)
@@ -110,24 +279,139 @@ impl CMap {
         };
     }
 
+    /// set_entries stores several keys, without expiration time, and returns the previous
+    /// expiration time of each key. The shards of all the keys are locked during the writes, so
+    /// no reader sees some of the keys written and not the others. They are locked in index
+    /// order, so concurrent calls cannot deadlock.
+    pub fn set_entries(&self, pairs: &[(String, String)]) -> Vec<Option<Instant>> {
+        let indexes: Vec<usize> = pairs
+            .iter()
+            .map(|(key, _)| self.get_shard_index(key))
+            .collect();
+        let mut locked = indexes.clone();
+        locked.sort_unstable();
+        locked.dedup();
+        let mut buckets: HashMap<usize, MutexGuard<Bucket>> = locked
+            .into_iter()
+            .map(|index| (index, self.shards[index].lock().unwrap()))
+            .collect();
+        pairs
+            .iter()
+            .zip(indexes)
+            .map(|((key, value), index)| {
+                let bucket = buckets.get_mut(&index).unwrap();
+                if bucket
+                    .add_entry_or_update(key.clone(), value.clone())
+                    .is_none()
+                {
+                    self.size.fetch_add(1, Ordering::SeqCst);
+                }
+                bucket.set_expiry(key, None)
+            })
+            .collect()
+    }
+
+    /// get_or_insert_with returns the value of a key, or stores and returns the value built by `f`
+    /// when the key is absent. The boolean is true when `f` was called.
+    /// The shard stays locked while `f` runs, so concurrent callers for keys of the same shard
+    /// wait for the value to be built instead of building it again.
+    pub fn get_or_insert_with<F: FnOnce() -> String>(&self, key: &str, f: F) -> (String, bool) {
+        let shard = self.get_shard_by_key(key);
+        let mut bucket = shard.lock().unwrap();
+        if let Some(value) = bucket.get_value_by_key(key, self.hot_key_threshold) {
+            return (value.clone(), false);
+        }
+        let value = f();
+        bucket.add_entry_or_update(key.to_string(), value.clone());
+        self.size.fetch_add(1, Ordering::SeqCst);
+        (value, true)
+    }
+
+    /// replace_persist stores the value of a key, makes it persistent, and returns its previous
+    /// value and expiration time, under one shard lock.
+    pub fn replace_persist(&self, key: &str, value: &str) -> (Option<String>, Option<Instant>) {
+        let shard = self.get_shard_by_key(key);
+        let mut shard = shard.lock().unwrap();
+        let previous_value = shard.add_entry_or_update(key.to_string(), value.to_string());
+        let previous_expiry = shard.set_expiry(key, None);
+        drop(shard);
+        if previous_value.is_none() {
+            self.size.fetch_add(1, Ordering::SeqCst);
+        }
+        (previous_value, previous_expiry)
+    }
+
+    /// update_value replaces the value of a key with the one returned by `f`, given the current
+    /// value. The key is left as it is when `f` returns None. The shard stays locked from the read
+    /// to the write, so concurrent updates are not lost. It returns true when the key was written.
+    pub fn update_value<F: FnOnce(Option<&str>) -> Option<String>>(&self, key: &str, f: F) -> bool {
+        let shard = self.get_shard_by_key(key);
+        let mut bucket = shard.lock().unwrap();
+        let Some(value) = f(bucket.peek_value(key).map(String::as_str)) else {
+            return false;
+        };
+        if bucket.add_entry_or_update(key.to_string(), value).is_none() {
+            self.size.fetch_add(1, Ordering::SeqCst);
+        }
+        true
+    }
+
+    /// append_value adds a suffix to the value of a key and returns the length of the new value,
+    /// or None when it would be longer than `max_len`. The shard stays locked from the read to the
+    /// write, so concurrent appends are not lost and cannot get past the limit together.
+    pub fn append_value(&self, key: &str, suffix: &str, max_len: usize) -> Option<usize> {
+        let (len, created) = self
+            .get_shard_by_key(key)
+            .lock()
+            .unwrap()
+            .append_to_entry(key, suffix, max_len)?;
+        if created {
+            self.size.fetch_add(1, Ordering::SeqCst);
+        }
+        Some(len)
+    }
+
     pub fn get_value(&self, key: &str) -> Option<String> {
         self.get_shard_by_key(key)
             .lock()
             .unwrap()
-            .get_value_by_key(key)
+            .get_value_by_key(key, self.hot_key_threshold)
             .cloned()
     }
 
+    /// peek_value returns the value of a key without counting it as an access in the key metadata.
+    pub fn peek_value(&self, key: &str) -> Option<String> {
+        self.get_shard_by_key(key)
+            .lock()
+            .unwrap()
+            .peek_value(key)
+            .cloned()
+    }
+
+    /// get_meta returns the metadata of a key. Reading the metadata does not count as an access.
+    pub fn get_meta(&self, key: &str) -> Option<KeyMeta> {
+        self.get_shard_by_key(key).lock().unwrap().get_meta(key)
+    }
+
     /// del_entries remove entries and return a vector of shards where the deletion happened with the count of items deleted.
     /// This method could be more simple, but we want to group keys to avoid locking/de-locking the same shared many times.
     pub fn del_entries(&self, keys: &Vec<String>) -> HashMap<usize, usize> {
+        self.remove_entries(keys)
+            .into_iter()
+            .map(|(shard_id, entries)| (shard_id, entries.len()))
+            .collect()
+    }
+
+    /// remove_entries works like del_entries but hands the removed key-value pairs back to the caller,
+    /// grouped by the shard they were removed from.
+    pub fn remove_entries(&self, keys: &Vec<String>) -> HashMap<usize, Vec<(String, String)>> {
         let mut ans = HashMap::new();
         let shard_key_mapping = self.get_shard_key_mapping(keys);
 
         for (shard_id, keys) in shard_key_mapping {
-            let count = self.delete_shard_entries(shard_id, keys);
-            if count > 0 {
-                ans.insert(shard_id, count);
+            let entries = self.remove_shard_entries(shard_id, keys);
+            if !entries.is_empty() {
+                ans.insert(shard_id, entries);
             }
         }
 
@@ -147,19 +431,25 @@ impl CMap {
         shard_key_mapping
     }
 
-    /// delete_shard_entries to delete entries from a shard and return the count of deleted items
-    fn delete_shard_entries(&self, shard_id: usize, keys: HashSet<String>) -> usize {
-        let mut count = 0;
+    /// remove_shard_entries to delete entries from a shard and return the deleted items
+    fn remove_shard_entries(
+        &self,
+        shard_id: usize,
+        keys: HashSet<String>,
+    ) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
 
         if let Some(shard) = self.get_shard_by_index(shard_id) {
             let mut shard = shard.lock().unwrap();
             for key in keys {
-                count += shard.remove_entry(&key)
+                if let Some(entry) = shard.remove_entry(&key) {
+                    entries.push(entry);
+                }
             }
         }
-        self.size.fetch_sub(count, Ordering::SeqCst);
+        self.size.fetch_sub(entries.len(), Ordering::SeqCst);
 
-        count
+        entries
     }
 
     pub fn size(&self) -> usize {
@@ -178,6 +468,144 @@ impl CMap {
     }
 }
 
+impl StorageBackend for CMap {
+    fn get(&self, key: &str) -> Option<String> {
+        self.get_value(key)
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        self.set_kv(key, value)
+    }
+
+    fn del(&self, keys: &[&str]) -> HashMap<usize, usize> {
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        self.del_entries(&keys)
+    }
+
+    fn size(&self) -> usize {
+        CMap::size(self)
+    }
+
+    fn keys_with_meta(&self) -> Vec<(String, Option<KeyMeta>)> {
+        // each bucket is walked once, where paging through scan would walk it from its start again
+        // for every page
+        let mut entries = Vec::with_capacity(self.size());
+        for shard in self.shards.iter() {
+            let bucket = shard.lock().unwrap();
+            entries.extend(
+                bucket
+                    .meta
+                    .iter()
+                    .map(|(key, meta)| (key.clone(), Some(*meta))),
+            );
+        }
+        entries
+    }
+
+    fn scan(&self, cursor: usize, count: usize) -> (usize, Vec<String>) {
+        // The high 32 bits of the cursor hold the shard to scan, the low ones the position in it.
+        let count = count.max(1);
+        let mut shard_index = cursor >> 32;
+        let mut position = cursor & 0xFFFF_FFFF;
+        let mut keys = Vec::with_capacity(count);
+        while shard_index < self.shard_count && keys.len() < count {
+            let bucket = self.shards[shard_index].lock().unwrap();
+            let batch: Vec<String> = bucket
+                .storage
+                .keys()
+                .skip(position)
+                .take(count - keys.len())
+                .cloned()
+                .collect();
+            position += batch.len();
+            keys.extend(batch);
+            if position < bucket.len() {
+                return ((shard_index << 32) | position, keys);
+            }
+            shard_index += 1;
+            position = 0;
+        }
+        let cursor = if shard_index < self.shard_count {
+            shard_index << 32
+        } else {
+            0
+        };
+        (cursor, keys)
+    }
+
+    fn peek(&self, key: &str) -> Option<String> {
+        self.peek_value(key)
+    }
+
+    fn meta(&self, key: &str) -> Option<KeyMeta> {
+        self.get_meta(key)
+    }
+
+    fn set_expiry(&self, key: &str, expiry: Option<Instant>) -> Option<Instant> {
+        self.get_shard_by_key(key)
+            .lock()
+            .unwrap()
+            .set_expiry(key, expiry)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.get_shard_by_key(key).lock().unwrap().contains_key(key)
+    }
+
+    fn get_or_insert_with(
+        &self,
+        key: &str,
+        init: Box<dyn FnOnce() -> String + '_>,
+    ) -> (String, bool) {
+        CMap::get_or_insert_with(self, key, init)
+    }
+
+    fn replace_persist(&self, key: &str, value: &str) -> (Option<String>, Option<Instant>) {
+        CMap::replace_persist(self, key, value)
+    }
+
+    fn set_many(&self, pairs: &[(String, String)]) -> Vec<Option<Instant>> {
+        self.set_entries(pairs)
+    }
+
+    fn update(&self, key: &str, f: UpdateFn<'_>) -> bool {
+        self.update_value(key, f)
+    }
+
+    fn append(&self, key: &str, suffix: &str, max_len: usize) -> Option<usize> {
+        self.append_value(key, suffix, max_len)
+    }
+
+    fn remove_entries(&self, keys: &[String]) -> HashMap<usize, Vec<(String, String)>> {
+        CMap::remove_entries(self, &keys.to_vec())
+    }
+
+    fn hot_keys(&self, top_k: usize) -> Vec<(String, u64)> {
+        CMap::hot_keys(self, top_k)
+    }
+
+    fn take(&self, key: &str) -> Option<(String, Option<KeyMeta>)> {
+        let (_, value, meta) = self.get_shard_by_key(key).lock().unwrap().take_entry(key)?;
+        self.size.fetch_sub(1, Ordering::SeqCst);
+        Some((value, Some(meta)))
+    }
+
+    fn put(&self, key: &str, value: &str, meta: Option<KeyMeta>) {
+        let shard = self.get_shard_by_key(key);
+        let mut bucket = shard.lock().unwrap();
+        let is_new = !bucket.contains_key(key);
+        match meta {
+            Some(meta) => bucket.put_entry(key.to_string(), value.to_string(), meta),
+            None => {
+                bucket.add_entry_or_update(key.to_string(), value.to_string());
+            }
+        }
+        if is_new {
+            self.size.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,4 +650,221 @@ mod tests {
 
         assert_eq!(cmap.size(), 0);
     }
+
+    // bucket_size only sizes the buckets up front, they grow past it. The size counter is updated
+    // outside the bucket locks, so it is checked never to wrap while keys come and go concurrently.
+    #[test]
+    fn test_size_under_concurrent_updates() {
+        let cmap = Arc::new(CMap::new(4, 16).unwrap());
+        for i in 0..64 {
+            cmap.set_kv(&format!("key{}", i), "value");
+        }
+        assert_eq!(cmap.size(), 64);
+
+        let writers: Vec<_> = (0..4)
+            .map(|thread_id| {
+                let cmap = cmap.clone();
+                std::thread::spawn(move || {
+                    for i in 0..1000 {
+                        let key = format!("key{}", (thread_id * 250 + i) % 1064);
+                        cmap.set_kv(&key, "other");
+                        if i % 3 == 0 {
+                            cmap.del_entries(&vec![key]);
+                        }
+                    }
+                })
+            })
+            .collect();
+        let reader = {
+            let cmap = cmap.clone();
+            std::thread::spawn(move || {
+                for _ in 0..10_000 {
+                    assert!(cmap.size() <= usize::MAX / 2, "size wrapped around");
+                }
+            })
+        };
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        reader.join().unwrap();
+
+        let stored = (0..1064)
+            .filter(|i| cmap.peek_value(&format!("key{}", i)).is_some())
+            .count();
+        assert_eq!(cmap.size(), stored);
+        assert!(cmap.size() > 64);
+    }
+
+    #[test]
+    fn test_keys_with_meta() {
+        let cmap = CMap::new(4, 16).unwrap();
+        for i in 0..100 {
+            cmap.set_kv(&format!("key{}", i), "value");
+        }
+        let expiry = Instant::now() + std::time::Duration::from_secs(60);
+        cmap.set_expiry("key7", Some(expiry));
+        let mut entries = cmap.keys_with_meta();
+        assert_eq!(entries.len(), 100);
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.dedup_by(|a, b| a.0 == b.0);
+        assert_eq!(entries.len(), 100);
+        let (_, meta) = entries.iter().find(|(key, _)| key == "key7").unwrap();
+        assert_eq!(meta.unwrap().expiry, Some(expiry));
+    }
+
+    #[test]
+    fn test_replace_persist() {
+        let cmap = CMap::new(4, 16).unwrap();
+        assert_eq!(cmap.replace_persist("key", "a"), (None, None));
+        let expiry = Instant::now() + std::time::Duration::from_secs(60);
+        cmap.set_expiry("key", Some(expiry));
+        assert_eq!(
+            cmap.replace_persist("key", "b"),
+            (Some("a".to_string()), Some(expiry))
+        );
+        assert_eq!(cmap.meta("key").unwrap().expiry, None);
+        assert_eq!(cmap.peek_value("key"), Some("b".to_string()));
+        assert_eq!(cmap.size(), 1);
+    }
+
+    #[test]
+    fn test_append_value() {
+        let cmap = Arc::new(CMap::new(4, 16).unwrap());
+        assert_eq!(cmap.append_value("key", "Hello", 11), Some(5));
+        assert_eq!(cmap.append_value("key", " World", 11), Some(11));
+        assert_eq!(cmap.append_value("key", "!", 11), None);
+        assert_eq!(cmap.peek_value("key"), Some("Hello World".to_string()));
+        assert_eq!(cmap.append_value("other", "too long", 4), None);
+        assert_eq!(cmap.size(), 1);
+
+        // concurrent appends are not lost
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cmap = cmap.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        cmap.append_value("log", "ab", usize::MAX);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(cmap.peek_value("log").unwrap().len(), 800);
+        assert_eq!(cmap.size(), 2);
+    }
+
+    #[test]
+    fn test_set_entries() {
+        let cmap = CMap::new(4, 16).unwrap();
+        let pairs: Vec<(String, String)> = (0..20)
+            .map(|i| (format!("key{}", i), i.to_string()))
+            .collect();
+        let expiry = Instant::now();
+        cmap.set_kv("key3", "old");
+        cmap.shards[cmap.get_shard_index("key3")]
+            .lock()
+            .unwrap()
+            .set_expiry("key3", Some(expiry));
+
+        let previous = cmap.set_entries(&pairs);
+        assert_eq!(previous[3], Some(expiry));
+        assert_eq!(previous.iter().flatten().count(), 1);
+        assert_eq!(cmap.size(), 20);
+        assert_eq!(cmap.peek_value("key3"), Some("3".to_string()));
+        assert!(cmap.get_meta("key3").unwrap().expiry.is_none());
+    }
+
+    #[test]
+    fn test_update_value() {
+        let cmap = CMap::new(4, 16).unwrap();
+        assert!(cmap.update_value("key", |current| {
+            assert_eq!(current, None);
+            Some("1".to_string())
+        }));
+        assert!(cmap.update_value("key", |current| Some(format!("{}2", current.unwrap()))));
+        assert!(!cmap.update_value("key", |_| None));
+        assert_eq!(cmap.peek_value("key"), Some("12".to_string()));
+        assert_eq!(cmap.size(), 1);
+    }
+
+    #[test]
+    fn test_key_meta() {
+        let cmap = CMap::new(4, 100).unwrap();
+        assert!(cmap.get_meta("key").is_none());
+
+        cmap.set_kv("key", "value");
+        let meta = cmap.get_meta("key").unwrap();
+        assert_eq!(meta.access_count, 0);
+        assert!(meta.expiry.is_none());
+
+        cmap.get_value("key");
+        cmap.set_kv("key", "other value");
+        let updated = cmap.get_meta("key").unwrap();
+        assert_eq!(updated.access_count, 2);
+        assert_eq!(updated.created_at, meta.created_at);
+        assert!(updated.last_access >= meta.last_access);
+
+        // missing keys have no metadata to update
+        cmap.get_value("missing");
+        assert!(cmap.get_meta("missing").is_none());
+
+        cmap.del_entries(&vec!["key".to_string()]);
+        assert!(cmap.get_meta("key").is_none());
+    }
+
+    #[test]
+    fn test_hot_keys() {
+        let cmap = CMap::new(1, 16).unwrap().with_hot_key_threshold(1000);
+        cmap.set_kv("hot", "value");
+        cmap.set_kv("warm", "value");
+        cmap.set_kv("cold", "value");
+        for _ in 0..8999 {
+            cmap.get_value("hot");
+        }
+        for _ in 0..1000 {
+            cmap.get_value("warm");
+        }
+        // misses are not counted
+        cmap.get_value("missing");
+        assert!(cmap.hot_keys(10).is_empty());
+
+        // the 10 000th read ends the period
+        cmap.get_value("cold");
+        assert_eq!(
+            cmap.hot_keys(10),
+            vec![("hot".to_string(), 8999), ("warm".to_string(), 1000)]
+        );
+        assert_eq!(cmap.hot_keys(1), vec![("hot".to_string(), 8999)]);
+
+        // removed keys are not hot anymore, and their reads are not counted
+        cmap.get_value("warm");
+        cmap.del_entries(&vec!["hot".to_string(), "warm".to_string()]);
+        assert!(cmap.hot_keys(10).is_empty());
+        assert!(cmap.shards[0].lock().unwrap().key_accesses.is_empty());
+    }
+
+    #[test]
+    fn test_scan() {
+        let cmap = CMap::new(4, 100).unwrap();
+        for i in 0..25 {
+            cmap.set_kv(&format!("key{}", i), "value");
+        }
+        let mut scanned = Vec::new();
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = StorageBackend::scan(&cmap, cursor, 7);
+            assert!(keys.len() <= 7);
+            scanned.extend(keys);
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        scanned.sort();
+        let mut want: Vec<String> = (0..25).map(|i| format!("key{}", i)).collect();
+        want.sort();
+        assert_eq!(scanned, want);
+    }
 }
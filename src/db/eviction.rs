@@ -0,0 +1,203 @@
+//! Eviction policies, choosing the keys removed by the background eviction job when expiring keys
+//! was not enough to bring a database back under its eviction threshold.
+//! The policy is set with the `maxmemory-policy` parameter and can be changed at runtime.
+
+use crate::db::KeyMeta;
+use rand::seq::SliceRandom;
+use std::fmt::Debug;
+use std::time::Instant;
+
+/// Eviction selects the keys to remove from a database.
+pub trait Eviction: Debug + Send + Sync {
+    /// name returns the `maxmemory-policy` value of the policy.
+    fn name(&self) -> &'static str;
+
+    /// select returns up to `count` keys to evict among `candidates`. Backends which do not keep
+    /// key metadata give candidates without meta.
+    fn select(&self, candidates: Vec<(String, Option<KeyMeta>)>, count: usize) -> Vec<String>;
+}
+
+/// from_policy returns the eviction policy named by a `maxmemory-policy` value.
+pub fn from_policy(name: &str) -> Option<Box<dyn Eviction>> {
+    let policy: Box<dyn Eviction> = match name {
+        "noeviction" => Box::new(NoEviction),
+        "allkeys-lru" => Box::new(Lru { volatile: false }),
+        "volatile-lru" => Box::new(Lru { volatile: true }),
+        "allkeys-lfu" => Box::new(Lfu { volatile: false }),
+        "volatile-lfu" => Box::new(Lfu { volatile: true }),
+        "allkeys-random" => Box::new(RandomEviction { volatile: false }),
+        "volatile-random" => Box::new(RandomEviction { volatile: true }),
+        "volatile-ttl" => Box::new(VolatileTtl),
+        _ => return None,
+    };
+    Some(policy)
+}
+
+/// volatile_only keeps the candidates with an expiration time.
+fn volatile_only(candidates: &mut Vec<(String, Option<KeyMeta>)>) {
+    candidates.retain(|(_, meta)| meta.is_some_and(|meta| meta.expiry.is_some()));
+}
+
+/// NoEviction never evicts a key: only the expired keys are removed.
+#[derive(Debug)]
+pub struct NoEviction;
+
+impl Eviction for NoEviction {
+    fn name(&self) -> &'static str {
+        "noeviction"
+    }
+
+    fn select(&self, _: Vec<(String, Option<KeyMeta>)>, _: usize) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Lru evicts the keys accessed the least recently.
+#[derive(Debug)]
+pub struct Lru {
+    volatile: bool,
+}
+
+impl Eviction for Lru {
+    fn name(&self) -> &'static str {
+        if self.volatile {
+            "volatile-lru"
+        } else {
+            "allkeys-lru"
+        }
+    }
+
+    fn select(&self, mut candidates: Vec<(String, Option<KeyMeta>)>, count: usize) -> Vec<String> {
+        if self.volatile {
+            volatile_only(&mut candidates);
+        }
+        candidates.sort_by_key(|(_, meta)| meta.map(|meta| meta.last_access));
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+/// Lfu evicts the keys accessed the least often since their creation.
+#[derive(Debug)]
+pub struct Lfu {
+    volatile: bool,
+}
+
+impl Eviction for Lfu {
+    fn name(&self) -> &'static str {
+        if self.volatile {
+            "volatile-lfu"
+        } else {
+            "allkeys-lfu"
+        }
+    }
+
+    fn select(&self, mut candidates: Vec<(String, Option<KeyMeta>)>, count: usize) -> Vec<String> {
+        if self.volatile {
+            volatile_only(&mut candidates);
+        }
+        candidates.sort_by_key(|(_, meta)| meta.map(|meta| meta.access_count));
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+/// RandomEviction evicts random keys.
+#[derive(Debug)]
+pub struct RandomEviction {
+    volatile: bool,
+}
+
+impl Eviction for RandomEviction {
+    fn name(&self) -> &'static str {
+        if self.volatile {
+            "volatile-random"
+        } else {
+            "allkeys-random"
+        }
+    }
+
+    fn select(&self, mut candidates: Vec<(String, Option<KeyMeta>)>, count: usize) -> Vec<String> {
+        if self.volatile {
+            volatile_only(&mut candidates);
+        }
+        candidates.shuffle(&mut rand::thread_rng());
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+/// VolatileTtl evicts the keys expiring the soonest.
+#[derive(Debug)]
+pub struct VolatileTtl;
+
+impl Eviction for VolatileTtl {
+    fn name(&self) -> &'static str {
+        "volatile-ttl"
+    }
+
+    fn select(&self, mut candidates: Vec<(String, Option<KeyMeta>)>, count: usize) -> Vec<String> {
+        volatile_only(&mut candidates);
+        candidates
+            .sort_by_key(|(_, meta)| meta.and_then(|meta| meta.expiry).unwrap_or(Instant::now()));
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn candidate(
+        key: &str,
+        age: u64,
+        access_count: u64,
+        ttl: Option<u64>,
+    ) -> (String, Option<KeyMeta>) {
+        let now = Instant::now();
+        let meta = KeyMeta {
+            last_access: now - Duration::from_secs(age),
+            created_at: now - Duration::from_secs(age),
+            access_count,
+            expiry: ttl.map(|ttl| now + Duration::from_secs(ttl)),
+        };
+        (key.to_string(), Some(meta))
+    }
+
+    #[test]
+    fn test_policies() {
+        let candidates = vec![
+            candidate("old", 30, 5, None),
+            candidate("recent", 1, 1, Some(100)),
+            candidate("middle", 10, 9, Some(10)),
+        ];
+        let select = |name: &str, count: usize| {
+            let policy = from_policy(name).unwrap();
+            assert_eq!(policy.name(), name);
+            policy.select(candidates.clone(), count)
+        };
+        assert!(select("noeviction", 3).is_empty());
+        assert_eq!(select("allkeys-lru", 2), ["old", "middle"]);
+        assert_eq!(select("volatile-lru", 3), ["middle", "recent"]);
+        assert_eq!(select("allkeys-lfu", 2), ["recent", "old"]);
+        assert_eq!(select("volatile-lfu", 1), ["recent"]);
+        assert_eq!(select("volatile-ttl", 1), ["middle"]);
+        assert_eq!(select("allkeys-random", 5).len(), 3);
+        assert_eq!(select("volatile-random", 5).len(), 2);
+        assert!(from_policy("nope").is_none());
+    }
+}
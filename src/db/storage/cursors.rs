@@ -0,0 +1,36 @@
+//! Scan cursors of the storage backends iterating their keys in order.
+
+use std::collections::{HashMap, VecDeque};
+
+// Number of scan cursors remembered. Older cursors are forgotten, so scans left unfinished by
+// clients do not use memory forever.
+const MAX_SCAN_CURSORS: usize = 1024;
+
+/// ScanCursors maps the integer cursors of StorageBackend::scan to the raw key a scan resumes
+/// from, for backends iterating their keys in order.
+#[derive(Default)]
+pub(crate) struct ScanCursors {
+    last_id: usize,
+    positions: HashMap<usize, Vec<u8>>,
+    order: VecDeque<usize>,
+}
+
+impl ScanCursors {
+    /// save remembers the key a scan resumes from and returns the cursor to hand to the client.
+    pub(crate) fn save(&mut self, key: Vec<u8>) -> usize {
+        // 0 means the scan is complete, it is never handed out as a cursor
+        self.last_id = self.last_id.wrapping_add(1).max(1);
+        if self.order.len() == MAX_SCAN_CURSORS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.positions.remove(&oldest);
+            }
+        }
+        self.order.push_back(self.last_id);
+        self.positions.insert(self.last_id, key);
+        self.last_id
+    }
+
+    pub(crate) fn get(&self, cursor: usize) -> Option<Vec<u8>> {
+        self.positions.get(&cursor).cloned()
+    }
+}
@@ -0,0 +1,184 @@
+//! Storage backends hold the keys and values of a database.
+//! CMap, an in-memory sharded map, is the default backend. Other backends are behind features.
+
+#[cfg(any(feature = "rocksdb", feature = "sled"))]
+mod cursors;
+#[cfg(feature = "rocksdb")]
+mod rocksdb_backend;
+#[cfg(feature = "rocksdb")]
+pub use rocksdb_backend::RocksDbBackend;
+#[cfg(feature = "sled")]
+mod sled_backend;
+#[cfg(feature = "sled")]
+pub use sled_backend::SledBackend;
+
+use crate::db::KeyMeta;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::Instant;
+
+/// UpdateFn builds the new value of a key from its current value, see StorageBackend::update.
+pub type UpdateFn<'a> = Box<dyn FnOnce(Option<&str>) -> Option<String> + 'a>;
+
+/// StorageBackend is the key-value store behind a State. Commands only go through State, so a
+/// new backend can be used without changing them.
+/// Only the first five methods are required. The others have default implementations built on
+/// them, which backends should override when they can do better, for instance atomically.
+pub trait StorageBackend: Debug + Send + Sync {
+    /// get returns the value of a key. Backends keeping key metadata count it as an access.
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// set stores the value of a key, replacing the previous one.
+    fn set(&self, key: &str, value: &str);
+
+    /// del removes keys and returns the number of keys removed per shard.
+    /// Backends without shards report all the keys under shard 0.
+    fn del(&self, keys: &[&str]) -> HashMap<usize, usize>;
+
+    /// size returns the number of keys stored.
+    fn size(&self) -> usize;
+
+    /// scan returns up to `count` keys starting at `cursor`, and the cursor to continue from.
+    /// Scans start at cursor 0 and are complete when the returned cursor is 0 again.
+    /// Keys added or removed during a scan may be missed or returned twice.
+    fn scan(&self, cursor: usize, count: usize) -> (usize, Vec<String>);
+
+    /// peek returns the value of a key without counting it as an access.
+    fn peek(&self, key: &str) -> Option<String> {
+        self.get(key)
+    }
+
+    /// meta returns the metadata of a key. Backends which do not keep metadata return None.
+    fn meta(&self, _key: &str) -> Option<KeyMeta> {
+        None
+    }
+
+    /// set_expiry sets the expiration time of a key, None making it persistent, and returns the
+    /// previous one. Backends which do not keep metadata ignore it and return None: their keys
+    /// are only expired by the eviction job.
+    fn set_expiry(&self, _key: &str, _expiry: Option<Instant>) -> Option<Instant> {
+        None
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// get_or_insert_with returns the value of a key, or stores and returns the value built by
+    /// `init` when the key is absent. The boolean is true when `init` was called.
+    /// The default implementation is not atomic: concurrent callers may all call `init`.
+    fn get_or_insert_with(
+        &self,
+        key: &str,
+        init: Box<dyn FnOnce() -> String + '_>,
+    ) -> (String, bool) {
+        if let Some(value) = self.get(key) {
+            return (value, false);
+        }
+        let value = init();
+        self.set(key, &value);
+        (value, true)
+    }
+
+    /// replace_persist stores the value of a key, makes it persistent, and returns its previous
+    /// value and expiration time.
+    /// The default implementation is not atomic: a concurrent write may happen in between.
+    fn replace_persist(&self, key: &str, value: &str) -> (Option<String>, Option<Instant>) {
+        let previous = self.peek(key);
+        self.set(key, value);
+        (previous, self.set_expiry(key, None))
+    }
+
+    /// append adds `suffix` at the end of the value of a key, creating the key when it is absent,
+    /// and returns the length of the new value in bytes. When the new value would be longer than
+    /// `max_len`, the key is left unchanged and None is returned.
+    /// The default implementation is not atomic: concurrent appends to a key may be lost.
+    fn append(&self, key: &str, suffix: &str, max_len: usize) -> Option<usize> {
+        let mut value = self.peek(key).unwrap_or_default();
+        if value.len() + suffix.len() > max_len {
+            return None;
+        }
+        value.push_str(suffix);
+        self.set(key, &value);
+        Some(value.len())
+    }
+
+    /// set_many stores several keys and makes them persistent. It returns the previous expiration
+    /// time of each key, in the order of `pairs`.
+    /// Backends should write the keys atomically, so that no reader sees some of them written
+    /// and not the others. The default implementation writes them one by one.
+    fn set_many(&self, pairs: &[(String, String)]) -> Vec<Option<Instant>> {
+        pairs
+            .iter()
+            .map(|(key, value)| {
+                self.set(key, value);
+                self.set_expiry(key, None)
+            })
+            .collect()
+    }
+
+    /// update replaces the value of a key with the one returned by `f`, which gets the current
+    /// value, or None when the key is absent. The key is left as it is when `f` returns None.
+    /// It returns true when the key was written.
+    /// The default implementation is not atomic: concurrent updates of a key may be lost.
+    fn update(&self, key: &str, f: UpdateFn<'_>) -> bool {
+        let Some(value) = f(self.peek(key).as_deref()) else {
+            return false;
+        };
+        self.set(key, &value);
+        true
+    }
+
+    /// remove_entries works like del but hands the removed key-value pairs back to the caller,
+    /// grouped by the shard they were removed from.
+    fn remove_entries(&self, keys: &[String]) -> HashMap<usize, Vec<(String, String)>> {
+        let entries: Vec<(String, String)> = keys
+            .iter()
+            .filter_map(|key| Some((key.clone(), self.peek(key)?)))
+            .collect();
+        let removed: Vec<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        self.del(&removed);
+        if entries.is_empty() {
+            HashMap::new()
+        } else {
+            HashMap::from([(0, entries)])
+        }
+    }
+
+    /// hot_keys returns up to `top_k` keys among the most read recently, with their number of
+    /// reads. Backends which do not count reads return no key.
+    fn hot_keys(&self, _top_k: usize) -> Vec<(String, u64)> {
+        Vec::new()
+    }
+
+    /// keys_with_meta returns every key along with its metadata, for the eviction job to choose
+    /// from. The default implementation pages through scan.
+    fn keys_with_meta(&self) -> Vec<(String, Option<KeyMeta>)> {
+        let mut entries = Vec::with_capacity(self.size());
+        let mut cursor = 0;
+        loop {
+            let (next, keys) = self.scan(cursor, 1024);
+            entries.extend(keys.into_iter().map(|key| {
+                let meta = self.meta(&key);
+                (key, meta)
+            }));
+            if next == 0 {
+                return entries;
+            }
+            cursor = next;
+        }
+    }
+
+    /// take removes a key and returns its value along with its metadata, if the backend keeps it.
+    fn take(&self, key: &str) -> Option<(String, Option<KeyMeta>)> {
+        let meta = self.meta(key);
+        let value = self.peek(key)?;
+        self.del(&[key]);
+        Some((value, meta))
+    }
+
+    /// put stores a key taken from another backend, keeping its metadata when there is some.
+    fn put(&self, key: &str, value: &str, _meta: Option<KeyMeta>) {
+        self.set(key, value);
+    }
+}
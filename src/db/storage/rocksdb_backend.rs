@@ -0,0 +1,149 @@
+//! RocksDB storage backend, enabled with the `rocksdb` feature.
+//! Keys survive restarts, at the cost of going through RocksDB on every access.
+
+use crate::db::storage::cursors::ScanCursors;
+use crate::db::StorageBackend;
+use rocksdb::{Direction, IteratorMode, DB};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::error;
+
+/// RocksDbBackend stores the keys of a database in RocksDB.
+/// It does not keep key metadata, so introspection commands like OBJECT FREQ find none.
+pub struct RocksDbBackend {
+    db: Arc<DB>,
+    cursors: Mutex<ScanCursors>,
+}
+
+impl RocksDbBackend {
+    /// open opens the RocksDB database stored in `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, rocksdb::Error> {
+        Ok(Self {
+            db: Arc::new(DB::open_default(path)?),
+            cursors: Mutex::new(ScanCursors::default()),
+        })
+    }
+
+    /// db returns the underlying RocksDB database, for instance to flush it or to back it up.
+    pub fn db(&self) -> &Arc<DB> {
+        &self.db
+    }
+}
+
+impl Debug for RocksDbBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RocksDb{{ path: {:?} }}", self.db.path())
+    }
+}
+
+impl StorageBackend for RocksDbBackend {
+    fn get(&self, key: &str) -> Option<String> {
+        match self.db.get(key) {
+            Ok(value) => value.map(|value| String::from_utf8_lossy(&value).into_owned()),
+            Err(err) => {
+                log_error("unable to read key from rocksdb", err);
+                None
+            }
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        if let Err(err) = self.db.put(key, value) {
+            log_error("unable to write key to rocksdb", err);
+        }
+    }
+
+    fn del(&self, keys: &[&str]) -> HashMap<usize, usize> {
+        let mut deleted = 0;
+        for key in keys {
+            // RocksDB deletes missing keys without complaining, so keys are checked first to
+            // only count the ones which existed.
+            if !self.contains(key) {
+                continue;
+            }
+            match self.db.delete(key) {
+                Ok(()) => deleted += 1,
+                Err(err) => log_error("unable to delete key from rocksdb", err),
+            }
+        }
+        if deleted == 0 {
+            HashMap::new()
+        } else {
+            HashMap::from([(0, deleted)])
+        }
+    }
+
+    fn size(&self) -> usize {
+        // RocksDB does not keep an exact count of its keys, only an estimate.
+        match self.db.property_int_value("rocksdb.estimate-num-keys") {
+            Ok(count) => count.unwrap_or(0) as usize,
+            Err(err) => {
+                log_error("unable to read the number of keys from rocksdb", err);
+                0
+            }
+        }
+    }
+
+    fn scan(&self, cursor: usize, count: usize) -> (usize, Vec<String>) {
+        let count = count.max(1);
+        let start = match cursor {
+            0 => None,
+            cursor => match self.cursors.lock().unwrap().get(cursor) {
+                Some(key) => Some(key),
+                // unknown or forgotten cursor
+                None => return (0, Vec::new()),
+            },
+        };
+        let mode = match &start {
+            Some(key) => IteratorMode::From(key, Direction::Forward),
+            None => IteratorMode::Start,
+        };
+        let mut keys = Vec::with_capacity(count);
+        for item in self.db.iterator(mode) {
+            let key = match item {
+                Ok((key, _)) => key,
+                Err(err) => {
+                    log_error("unable to scan keys from rocksdb", err);
+                    break;
+                }
+            };
+            if keys.len() == count {
+                // the next scan resumes from the first key not returned
+                let cursor = self.cursors.lock().unwrap().save(key.into_vec());
+                return (cursor, keys);
+            }
+            keys.push(String::from_utf8_lossy(&key).into_owned());
+        }
+        (0, keys)
+    }
+}
+
+fn log_error(message: &str, err: rocksdb::Error) {
+    error!(error_message = err.to_string(), message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rocksdb_backend() {
+        let path = std::env::temp_dir().join(format!("htcache-rocksdb-{}", std::process::id()));
+        let backend = RocksDbBackend::open(&path).unwrap();
+        for key in ["a", "b", "c", "d", "e"] {
+            backend.set(key, &key.to_uppercase());
+        }
+        assert_eq!(backend.get("c"), Some("C".to_string()));
+        assert_eq!(backend.del(&["c", "missing"]), HashMap::from([(0, 1)]));
+        assert_eq!(backend.get("c"), None);
+
+        let (cursor, keys) = backend.scan(0, 3);
+        assert_eq!(keys, ["a", "b", "d"]);
+        assert_eq!(backend.scan(cursor, 3), (0, vec!["e".to_string()]));
+
+        drop(backend);
+        DB::destroy(&rocksdb::Options::default(), &path).unwrap();
+    }
+}
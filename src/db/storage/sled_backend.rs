@@ -0,0 +1,164 @@
+//! sled storage backend, enabled with the `sled` feature.
+//! sled is an embedded database written in Rust, which makes it a lighter durable option than
+//! RocksDB.
+
+use crate::db::storage::cursors::ScanCursors;
+use crate::db::StorageBackend;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::error;
+
+/// SledBackend stores the keys of a database in sled. Keys and values are stored as raw bytes.
+/// sled trees are safe to share between threads, so no lock is taken around them.
+/// It does not keep key metadata, so introspection commands like OBJECT FREQ find none.
+pub struct SledBackend {
+    db: sled::Db,
+    // sled only counts its keys by iterating over them, so the count is maintained here.
+    size: AtomicUsize,
+    cursors: Mutex<ScanCursors>,
+}
+
+impl SledBackend {
+    /// open opens the sled database stored in `path`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self {
+            size: AtomicUsize::new(db.len()),
+            db,
+            cursors: Mutex::new(ScanCursors::default()),
+        })
+    }
+
+    /// flush writes the pending changes to disk. sled also flushes periodically on its own,
+    /// calling flush makes sure that the latest writes survive a crash.
+    pub fn flush(&self) -> io::Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+impl Debug for SledBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sled{{ size: {} }}", self.size.load(Ordering::SeqCst))
+    }
+}
+
+impl StorageBackend for SledBackend {
+    fn get(&self, key: &str) -> Option<String> {
+        match self.db.get(key) {
+            Ok(value) => value.map(|value| String::from_utf8_lossy(&value).into_owned()),
+            Err(err) => {
+                log_error("unable to read key from sled", err);
+                None
+            }
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) {
+        match self.db.insert(key, value) {
+            Ok(None) => {
+                self.size.fetch_add(1, Ordering::SeqCst);
+            }
+            Ok(Some(_)) => {}
+            Err(err) => log_error("unable to write key to sled", err),
+        }
+    }
+
+    fn del(&self, keys: &[&str]) -> HashMap<usize, usize> {
+        let mut deleted = 0;
+        for key in keys {
+            match self.db.remove(key) {
+                Ok(Some(_)) => deleted += 1,
+                Ok(None) => {}
+                Err(err) => log_error("unable to delete key from sled", err),
+            }
+        }
+        self.size.fetch_sub(deleted, Ordering::SeqCst);
+        if deleted == 0 {
+            HashMap::new()
+        } else {
+            HashMap::from([(0, deleted)])
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.size.load(Ordering::SeqCst)
+    }
+
+    fn scan(&self, cursor: usize, count: usize) -> (usize, Vec<String>) {
+        let count = count.max(1);
+        let start = match cursor {
+            0 => Vec::new(),
+            cursor => match self.cursors.lock().unwrap().get(cursor) {
+                Some(key) => key,
+                // unknown or forgotten cursor
+                None => return (0, Vec::new()),
+            },
+        };
+        let mut keys = Vec::with_capacity(count);
+        for item in self.db.range(start..) {
+            let key = match item {
+                Ok((key, _)) => key,
+                Err(err) => {
+                    log_error("unable to scan keys from sled", err);
+                    break;
+                }
+            };
+            if keys.len() == count {
+                // the next scan resumes from the first key not returned
+                let cursor = self.cursors.lock().unwrap().save(key.to_vec());
+                return (cursor, keys);
+            }
+            keys.push(String::from_utf8_lossy(&key).into_owned());
+        }
+        (0, keys)
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.db.contains_key(key).unwrap_or_else(|err| {
+            log_error("unable to read key from sled", err);
+            false
+        })
+    }
+}
+
+fn log_error(message: &str, err: sled::Error) {
+    error!(error_message = err.to_string(), message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sled_backend() {
+        let path = std::env::temp_dir().join(format!("htcache-sled-{}", std::process::id()));
+        let backend = SledBackend::open(&path).unwrap();
+        for key in ["a", "b", "c", "d", "e"] {
+            backend.set(key, &key.to_uppercase());
+        }
+        backend.set("a", "A");
+        assert_eq!(backend.size(), 5);
+        assert_eq!(backend.get("c"), Some("C".to_string()));
+        assert_eq!(backend.del(&["c", "missing"]), HashMap::from([(0, 1)]));
+        assert_eq!(backend.get("c"), None);
+        assert_eq!(backend.size(), 4);
+
+        let (cursor, keys) = backend.scan(0, 3);
+        assert_eq!(keys, ["a", "b", "d"]);
+        assert_eq!(backend.scan(cursor, 3), (0, vec!["e".to_string()]));
+
+        backend.flush().unwrap();
+        drop(backend);
+        // the keys survive a restart
+        let backend = SledBackend::open(&path).unwrap();
+        assert_eq!(backend.size(), 4);
+        assert_eq!(backend.get("e"), Some("E".to_string()));
+        drop(backend);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -10,6 +10,8 @@ pub enum FrameError {
     EOF,
     Encoding(io::Error),
     InvalidFrame,
+    // A bulk string announcing more than MAX_BULK_SIZE bytes.
+    InvalidBulkLength,
     InvalidType,
     Incomplete,
     StringFromUTF8(FromUtf8Error),
@@ -25,6 +27,7 @@ impl Display for FrameError {
         match self {
             FrameError::Encoding(err) => write!(f, "error encoding RESP frame: {}", err),
             FrameError::InvalidFrame => write!(f, "RESP frame is malformed"),
+            FrameError::InvalidBulkLength => write!(f, "invalid bulk length"),
             FrameError::InvalidType => write!(f, "wrong RESP frame type, needed another type here"),
             FrameError::EOF => write!(f, "file reached EOF"),
             FrameError::UnexpectedEOF => write!(f, "connection abruptly closed"),
@@ -150,3 +153,107 @@ impl Display for DatabaseError {
     }
 }
 impl std::error::Error for DatabaseError {}
+
+#[derive(Debug)]
+pub enum CircuitBreakerError {
+    Open,
+    Failed,
+}
+
+impl Display for CircuitBreakerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            CircuitBreakerError::Open => write!(f, "circuit breaker is open, operation rejected"),
+            CircuitBreakerError::Failed => write!(f, "storage operation failed"),
+        }
+    }
+}
+impl std::error::Error for CircuitBreakerError {}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    UnknownParameter(String),
+    InvalidValue { name: String, reason: String },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ConfigError::UnknownParameter(name) => {
+                write!(f, "unknown configuration parameter '{}'", name)
+            }
+            ConfigError::InvalidValue { name, reason } => {
+                write!(f, "invalid value for '{}': {}", name, reason)
+            }
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug)]
+pub enum AclError {
+    InvalidModifier(String),
+    DefaultUserDeletion,
+    CommandDenied { username: String, command: String },
+    KeyDenied(String),
+    AuthFailed,
+}
+
+impl Display for AclError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            AclError::InvalidModifier(modifier) => {
+                write!(
+                    f,
+                    "Error in ACL SETUSER modifier '{}': Syntax error",
+                    modifier
+                )
+            }
+            AclError::DefaultUserDeletion => {
+                write!(f, "The 'default' user cannot be removed")
+            }
+            AclError::CommandDenied { username, command } => write!(
+                f,
+                "User {} has no permissions to run the '{}' command",
+                username, command
+            ),
+            AclError::KeyDenied(_) => write!(f, "No permissions to access a key"),
+            AclError::AuthFailed => {
+                write!(f, "invalid username-password pair or user is disabled.")
+            }
+        }
+    }
+}
+impl std::error::Error for AclError {}
+
+#[derive(Debug)]
+pub enum FunctionError {
+    InvalidCode(String),
+    LibraryExists(String),
+    FunctionExists(String),
+    UnknownImplementation(String),
+    LibraryNotFound,
+    FunctionNotFound,
+}
+
+impl Display for FunctionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            FunctionError::InvalidCode(reason) => {
+                write!(f, "Error compiling function: {}", reason)
+            }
+            FunctionError::LibraryExists(name) => {
+                write!(f, "Library '{}' already exists", name)
+            }
+            FunctionError::FunctionExists(name) => {
+                write!(f, "Function {} already exists", name)
+            }
+            FunctionError::UnknownImplementation(name) => {
+                write!(f, "Unknown function implementation '{}'", name)
+            }
+            FunctionError::LibraryNotFound => write!(f, "Library not found"),
+            FunctionError::FunctionNotFound => write!(f, "Function not found"),
+        }
+    }
+}
+impl std::error::Error for FunctionError {}
@@ -6,6 +6,8 @@ use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+#[cfg(feature = "async-tokio")]
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
 use tracing::debug;
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
@@ -31,7 +33,7 @@ impl Ord for Frame {
             (Frame::Map(a), Frame::Map(b)) => a.cmp(b),
             (Frame::Null, Frame::Null) => Ordering::Equal,
             (Frame::Boolean(a), Frame::Boolean(b)) => a.cmp(b),
-            _ => panic!("Can't compare different Frame variants"),
+            _ => self.variant_rank().cmp(&other.variant_rank()),
         }
     }
 }
@@ -43,6 +45,21 @@ impl PartialOrd for Frame {
 }
 
 impl Frame {
+    /// variant_rank orders frames of different variants, so a collection mixing them can be sorted:
+    /// Integer < Bulk < Simple < Error < Boolean < Null < Array < Map.
+    fn variant_rank(&self) -> u8 {
+        match self {
+            Frame::Integer(_) => 0,
+            Frame::Bulk(_) => 1,
+            Frame::Simple(_) => 2,
+            Frame::Error(_) => 3,
+            Frame::Boolean(_) => 4,
+            Frame::Null => 5,
+            Frame::Array(_) => 6,
+            Frame::Map(_) => 7,
+        }
+    }
+
     /// array returns an empty array of frames
     pub fn array() -> Frame {
         Frame::Array(vec![])
@@ -152,6 +169,19 @@ impl Frame {
 /// Keep in mind that the buffer might be partial and manage those cases.
 /// Errors are generally malformed frames.
 pub fn decode<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
+    decode_nested(rd, 0)
+}
+
+/// Maximum size of the content of a bulk string, 512mb as in Redis. Larger sizes are rejected
+/// before the content is allocated, so a client cannot exhaust the memory with a single header.
+pub const MAX_BULK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Maximum number of arrays and maps nested in a frame. Deeper frames are rejected, so that a
+/// crafted input cannot overflow the stack of the recursive decoder.
+pub const MAX_DEPTH: usize = 64;
+
+/// decode_nested decodes a frame held by `depth` arrays or maps.
+fn decode_nested<T: Read>(rd: &mut BufReader<T>, depth: usize) -> Result<Frame, FrameError> {
     let tag = get_byte(rd)?;
     match tag {
         // Simple String
@@ -196,9 +226,9 @@ pub fn decode<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
             }
         }
         // Array
-        b'*' => decode_array(rd),
+        b'*' => decode_array(rd, depth),
         // Map
-        b'%' => decode_map(rd),
+        b'%' => decode_map(rd, depth),
         _ => Err(FrameError::InvalidType),
     }
 }
@@ -248,38 +278,38 @@ fn get_simple_string<T: Read>(rd: &mut BufReader<T>) -> Result<String, FrameErro
     Ok(String::from_utf8_lossy(&bytes[..bytes_read - 2]).to_string())
 }
 
-/// find_crlf_bulk finds the position of the next crlf. It sets the position of CRLF, cursor at LF if found or an error if not.
-/// Unlike .find_crlf_simple, this bytes can contain CR or LF in the middle. The io cursor is not modified by this function.
-///
+/// get_bulk_string reads the size of a bulk string, then exactly that many bytes followed by CRLF.
+/// The content is read by size, not up to a delimiter, so it can hold CR and LF. A content
+/// shorter than its size is never accepted: the bytes which follow it are read as content, and the
+/// CRLF check fails on them.
 fn get_bulk_string<T: Read>(rd: &mut BufReader<T>) -> Result<String, FrameError> {
     // read the size first
     let bulk_size = get_simple_string(rd)?;
     let content_size: usize = bulk_size.parse()?;
-
-    let mut data = Vec::with_capacity(content_size + 2);
-    let bytes_read = rd.read_until(LF, &mut data)?;
-
-    if bytes_read == 0 {
-        debug!("reached EOF while reading frame");
-        return Err(FrameError::EOF);
+    if content_size > MAX_BULK_SIZE {
+        debug!("bulk string too large: {} bytes", content_size);
+        return Err(FrameError::InvalidBulkLength);
     }
 
-    if bytes_read < 2 {
-        debug!("no enough data to decode a frame");
-        return Err(FrameError::Incomplete);
-    }
+    // the content is read by size, as it can hold CR and LF
+    let mut data = vec![0; content_size + 2];
+    rd.read_exact(&mut data)?;
 
-    if bytes_read != content_size + 2 {
+    if data[content_size..] != [CR, LF] {
         return Err(FrameError::InvalidFrame);
     }
 
     // We have choosen to not check if we have valid utf8 for performance
-    Ok(String::from_utf8_lossy(&data[..bytes_read - 2]).to_string())
+    Ok(String::from_utf8_lossy(&data[..content_size]).to_string())
 }
 
 /// decode_array decodes a frame Array from a reader.
 /// The tag identifying the frame is considered to be already read.
-fn decode_array<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
+fn decode_array<T: Read>(rd: &mut BufReader<T>, depth: usize) -> Result<Frame, FrameError> {
+    if depth >= MAX_DEPTH {
+        debug!("frame nested too deeply");
+        return Err(FrameError::InvalidFrame);
+    }
     // Read the length first
     let array_length = get_simple_string(rd)?;
     let array_length = array_length.parse()?;
@@ -287,7 +317,7 @@ fn decode_array<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
     let mut arr = Frame::array();
 
     for _ in 0..array_length {
-        let fr = decode(rd)?;
+        let fr = decode_nested(rd, depth + 1)?;
         arr.push_back(fr)?;
     }
 
@@ -296,7 +326,11 @@ fn decode_array<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
 
 /// decode_map decodes a frame map from a reader.
 /// The tag identifying the frame is considered to be already read.
-fn decode_map<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
+fn decode_map<T: Read>(rd: &mut BufReader<T>, depth: usize) -> Result<Frame, FrameError> {
+    if depth >= MAX_DEPTH {
+        debug!("frame nested too deeply");
+        return Err(FrameError::InvalidFrame);
+    }
     // Read the length first
     let map_length = get_simple_string(rd)?;
     let map_length = map_length.parse()?;
@@ -304,8 +338,173 @@ fn decode_map<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
     let mut map = Frame::map();
 
     for _ in 0..map_length {
-        let key = decode(rd)?;
-        let value = decode(rd)?;
+        let key = decode_nested(rd, depth + 1)?;
+        let value = decode_nested(rd, depth + 1)?;
+        map.add_map_frame(key, value)?;
+    }
+
+    Ok(map)
+}
+
+/// decode_async is the asynchronous version of `decode`, for readers driven by tokio.
+/// It follows `decode` step by step, so both return the same frames and errors for the same input.
+#[cfg(feature = "async-tokio")]
+pub async fn decode_async<T: AsyncBufRead + Unpin>(rd: &mut T) -> Result<Frame, FrameError> {
+    decode_nested_async(rd, 0).await
+}
+
+/// decode_nested_async is the asynchronous version of `decode_nested`.
+#[cfg(feature = "async-tokio")]
+async fn decode_nested_async<T: AsyncBufRead + Unpin>(
+    rd: &mut T,
+    depth: usize,
+) -> Result<Frame, FrameError> {
+    let tag = get_byte_async(rd).await?;
+    match tag {
+        // Simple String
+        b'+' => {
+            let content = get_simple_string_async(rd).await?;
+            Ok(Frame::Simple(content))
+        }
+        // Error
+        b'-' => {
+            let content = get_simple_string_async(rd).await?;
+            Ok(Frame::Error(content))
+        }
+        // Integer
+        b':' => {
+            let content_string = get_simple_string_async(rd).await?;
+            let content = content_string.parse()?;
+            Ok(Frame::Integer(content))
+        }
+        // Bulk
+        b'$' => {
+            let content = get_bulk_string_async(rd).await?;
+            Ok(Frame::Bulk(content))
+        }
+        // Bool
+        b'#' => {
+            let content = get_simple_string_async(rd).await?;
+            if content == *"t" {
+                Ok(Frame::Boolean(true))
+            } else if content == *"f" {
+                Ok(Frame::Boolean(false))
+            } else {
+                Err(FrameError::InvalidFrame)
+            }
+        }
+        // Nil frame
+        b'_' => {
+            let content = get_simple_string_async(rd).await?;
+            if content == *"" {
+                Ok(Frame::Null)
+            } else {
+                Err(FrameError::InvalidFrame)
+            }
+        }
+        // Array
+        b'*' => decode_array_async(rd, depth).await,
+        // Map
+        b'%' => decode_map_async(rd, depth).await,
+        _ => Err(FrameError::InvalidType),
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+async fn get_byte_async<T: AsyncBufRead + Unpin>(rd: &mut T) -> Result<u8, FrameError> {
+    let mut byte = [1];
+    rd.read_exact(&mut byte).await?;
+    Ok(byte[0])
+}
+
+/// get_simple_string_async is the asynchronous version of `get_simple_string`.
+#[cfg(feature = "async-tokio")]
+async fn get_simple_string_async<T: AsyncBufRead + Unpin>(
+    rd: &mut T,
+) -> Result<String, FrameError> {
+    let mut bytes = vec![];
+    let bytes_read = rd.read_until(LF, &mut bytes).await?;
+
+    if bytes_read == 0 {
+        debug!("reached EOF while reading frame");
+        return Err(FrameError::EOF);
+    }
+
+    if bytes_read < 2 {
+        debug!("no enough data to decode a frame");
+        return Err(FrameError::Incomplete);
+    }
+
+    if bytes[bytes_read - 2] != CR {
+        debug!("found an non-delimiting LF in a simple frame");
+        return Err(FrameError::InvalidFrame);
+    }
+
+    Ok(String::from_utf8_lossy(&bytes[..bytes_read - 2]).to_string())
+}
+
+/// get_bulk_string_async is the asynchronous version of `get_bulk_string`.
+#[cfg(feature = "async-tokio")]
+async fn get_bulk_string_async<T: AsyncBufRead + Unpin>(rd: &mut T) -> Result<String, FrameError> {
+    let bulk_size = get_simple_string_async(rd).await?;
+    let content_size: usize = bulk_size.parse()?;
+    if content_size > MAX_BULK_SIZE {
+        debug!("bulk string too large: {} bytes", content_size);
+        return Err(FrameError::InvalidBulkLength);
+    }
+
+    let mut data = vec![0; content_size + 2];
+    rd.read_exact(&mut data).await?;
+
+    if data[content_size..] != [CR, LF] {
+        return Err(FrameError::InvalidFrame);
+    }
+
+    Ok(String::from_utf8_lossy(&data[..content_size]).to_string())
+}
+
+/// decode_array_async is the asynchronous version of `decode_array`.
+/// Nested frames are decoded in a boxed future, as an async function cannot hold itself.
+#[cfg(feature = "async-tokio")]
+async fn decode_array_async<T: AsyncBufRead + Unpin>(
+    rd: &mut T,
+    depth: usize,
+) -> Result<Frame, FrameError> {
+    if depth >= MAX_DEPTH {
+        debug!("frame nested too deeply");
+        return Err(FrameError::InvalidFrame);
+    }
+    let array_length = get_simple_string_async(rd).await?;
+    let array_length = array_length.parse()?;
+
+    let mut arr = Frame::array();
+
+    for _ in 0..array_length {
+        let fr = Box::pin(decode_nested_async(rd, depth + 1)).await?;
+        arr.push_back(fr)?;
+    }
+
+    Ok(arr)
+}
+
+/// decode_map_async is the asynchronous version of `decode_map`.
+#[cfg(feature = "async-tokio")]
+async fn decode_map_async<T: AsyncBufRead + Unpin>(
+    rd: &mut T,
+    depth: usize,
+) -> Result<Frame, FrameError> {
+    if depth >= MAX_DEPTH {
+        debug!("frame nested too deeply");
+        return Err(FrameError::InvalidFrame);
+    }
+    let map_length = get_simple_string_async(rd).await?;
+    let map_length = map_length.parse()?;
+
+    let mut map = Frame::map();
+
+    for _ in 0..map_length {
+        let key = Box::pin(decode_nested_async(rd, depth + 1)).await?;
+        let value = Box::pin(decode_nested_async(rd, depth + 1)).await?;
         map.add_map_frame(key, value)?;
     }
 
@@ -315,13 +514,8 @@ fn decode_map<T: Read>(rd: &mut BufReader<T>) -> Result<Frame, FrameError> {
 impl Display for Frame {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let frame_as_bytes = self.encode();
-        // we can use unwrapping because bytes converted from frame will always
-        // have valid utf8 chars
-        write!(
-            f,
-            "{}",
-            String::from_utf8(frame_as_bytes).unwrap_or("invalid frame".to_string())
-        )
+        // as when decoding, invalid utf8 is replaced instead of failing the whole frame
+        write!(f, "{}", String::from_utf8_lossy(&frame_as_bytes))
     }
 }
 
@@ -608,3 +802,209 @@ impl Display for Frame {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_mixed_variants() {
+        let mut frames = vec![
+            Frame::map(),
+            Frame::Array(vec![Frame::Integer(1)]),
+            Frame::Null,
+            Frame::Boolean(true),
+            Frame::Error("ERR".to_string()),
+            Frame::Simple("OK".to_string()),
+            Frame::Bulk("b".to_string()),
+            Frame::Integer(2),
+            Frame::Bulk("a".to_string()),
+            Frame::Integer(1),
+        ];
+        frames.sort();
+        assert_eq!(
+            frames,
+            vec![
+                Frame::Integer(1),
+                Frame::Integer(2),
+                Frame::Bulk("a".to_string()),
+                Frame::Bulk("b".to_string()),
+                Frame::Simple("OK".to_string()),
+                Frame::Error("ERR".to_string()),
+                Frame::Boolean(true),
+                Frame::Null,
+                Frame::Array(vec![Frame::Integer(1)]),
+                Frame::map(),
+            ]
+        );
+        // maps can now use keys of different variants
+        let mut map = Frame::map();
+        map.add_map_frame(Frame::Bulk("a".to_string()), Frame::Null)
+            .unwrap();
+        map.add_map_frame(Frame::Integer(1), Frame::Null).unwrap();
+        assert_eq!(map.encode(), b"%2\r\n:1\r\n_\r\n$1\r\na\r\n_\r\n");
+    }
+
+    #[test]
+    fn test_display_binary_bulk_in_map() {
+        // decoding replaces invalid utf8, so binary payloads reach the frames this way
+        let binary = String::from_utf8_lossy(b"\x00\xff\r\n\xfe").to_string();
+        let mut map = Frame::map();
+        map.add_map_frame(Frame::Bulk("key".to_string()), Frame::Bulk(binary.clone()))
+            .unwrap();
+        assert_eq!(
+            map.to_string(),
+            format!("%1\r\n$3\r\nkey\r\n${}\r\n{}\r\n", binary.len(), binary)
+        );
+        assert_ne!(map.to_string(), "invalid frame");
+    }
+
+    #[test]
+    fn test_decode_bulk_with_crlf() {
+        let decode_bytes = |bytes: &[u8]| decode(&mut BufReader::new(bytes));
+        // a CRLF in the content is data, not a separator
+        assert_eq!(
+            decode_bytes(b"$9\r\nhello\r\nwo\r\n").unwrap(),
+            Frame::Bulk("hello\r\nwo".to_string())
+        );
+        // 10 bytes are announced but only 9 are sent before the CRLF: the decoder waits for the
+        // missing bytes instead of stopping at the CRLF
+        assert!(matches!(
+            decode_bytes(b"$10\r\nhello\r\nwo\r\n"),
+            Err(FrameError::EOF)
+        ));
+        // with a frame after it, the content runs into that frame and the CRLF is not found
+        assert!(matches!(
+            decode_bytes(b"$10\r\nhello\r\nwo\r\n+OK\r\n"),
+            Err(FrameError::InvalidFrame)
+        ));
+    }
+
+    #[test]
+    fn test_decode_bulk_too_large() {
+        // only the header is sent: the size must be rejected without waiting for the content
+        let header = format!("${}\r\n", 2u64 << 30);
+        assert!(matches!(
+            decode(&mut BufReader::new(header.as_bytes())),
+            Err(FrameError::InvalidBulkLength)
+        ));
+    }
+
+    /// nested returns the encoding of `depth` arrays nested in each other around an integer.
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bytes = b"*1\r\n".repeat(depth);
+        bytes.extend_from_slice(b":1\r\n");
+        bytes
+    }
+
+    #[test]
+    fn test_decode_max_depth() {
+        let frame = decode(&mut BufReader::new(nested(MAX_DEPTH).as_slice())).unwrap();
+        assert!(matches!(frame, Frame::Array(_)));
+        assert!(matches!(
+            decode(&mut BufReader::new(nested(MAX_DEPTH + 1).as_slice())),
+            Err(FrameError::InvalidFrame)
+        ));
+        // far too deep for the stack if the depth was not checked
+        assert!(matches!(
+            decode(&mut BufReader::new(nested(100_000).as_slice())),
+            Err(FrameError::InvalidFrame)
+        ));
+        let map = b"%1\r\n+key\r\n".repeat(MAX_DEPTH + 1);
+        assert!(matches!(
+            decode(&mut BufReader::new(map.as_slice())),
+            Err(FrameError::InvalidFrame)
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "async-tokio"))]
+mod async_tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// decode_both decodes the same bytes with `decode` and `decode_async`.
+    /// FrameError cannot be compared, so the results are compared through their debug output.
+    fn decode_both(bytes: &[u8]) -> (String, String) {
+        let sync = decode(&mut BufReader::new(bytes));
+        let mut reader = tokio::io::BufReader::new(bytes);
+        let not_sync = tokio_test::block_on(decode_async(&mut reader));
+        (format!("{:?}", sync), format!("{:?}", not_sync))
+    }
+
+    fn random_string(rng: &mut StdRng, alphabet: &[u8]) -> String {
+        let len = rng.gen_range(0..12);
+        (0..len)
+            .map(|_| alphabet[rng.gen_range(0..alphabet.len())] as char)
+            .collect()
+    }
+
+    fn random_frame(rng: &mut StdRng, depth: u32) -> Frame {
+        let text = b"abcXYZ019 _-";
+        let kinds = if depth == 0 { 6 } else { 8 };
+        match rng.gen_range(0..kinds) {
+            0 => Frame::Simple(random_string(rng, text)),
+            1 => Frame::Error(random_string(rng, text)),
+            2 => Frame::Integer(rng.gen()),
+            3 => Frame::Bulk(random_string(rng, b"ab\r\n$*")),
+            4 => Frame::Boolean(rng.gen()),
+            5 => Frame::Null,
+            6 => Frame::Array(
+                (0..rng.gen_range(0..4))
+                    .map(|_| random_frame(rng, depth - 1))
+                    .collect(),
+            ),
+            _ => Frame::Map(
+                (0..rng.gen_range(0..4))
+                    .map(|_| (random_frame(rng, 0), random_frame(rng, depth - 1)))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_decode_async_fixtures() {
+        let fixtures: &[&[u8]] = &[
+            b"+OK\r\n",
+            b"-ERR failure\r\n",
+            b":-42\r\n",
+            b"$5\r\nhel\nl\r\n",
+            b"#t\r\n",
+            b"_\r\n",
+            b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n",
+            b"%1\r\n+key\r\n:1\r\n",
+            b"",
+            b"+OK\n",
+            b"\n",
+            b":abc\r\n",
+            b"#x\r\n",
+            b"$3\r\nab\r\n",
+            b"*2\r\n+a\r\n",
+            b"!oops\r\n",
+            &b"*1\r\n".repeat(MAX_DEPTH + 1),
+            b"$2147483648\r\n",
+        ];
+        for fixture in fixtures {
+            let (sync, not_sync) = decode_both(fixture);
+            assert_eq!(sync, not_sync, "{:?}", String::from_utf8_lossy(fixture));
+        }
+    }
+
+    #[test]
+    fn test_decode_async_random_frames() {
+        let mut rng = StdRng::seed_from_u64(0x5EED);
+        for _ in 0..500 {
+            let frame = random_frame(&mut rng, 3);
+            let bytes = frame.encode();
+            let (sync, not_sync) = decode_both(&bytes);
+            assert_eq!(sync, format!("{:?}", Ok::<_, FrameError>(&frame)));
+            assert_eq!(sync, not_sync);
+
+            // a frame cut anywhere must fail the same way with both decoders
+            let cut = rng.gen_range(0..bytes.len());
+            let (sync, not_sync) = decode_both(&bytes[..cut]);
+            assert_eq!(sync, not_sync, "{:?} cut at {}", frame, cut);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Libraries of functions loaded with FUNCTION LOAD and called with FCALL.
+//!
+//! Functions cannot run arbitrary code. A library is a JSON document naming the library and
+//! binding each of its functions to one of the built-in implementations:
+//!
+//! ```json
+//! {"name": "mylib", "functions": {"myecho": "echo", "shout": "upper"}}
+//! ```
+
+use crate::error::FunctionError;
+use crate::frame::Frame;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, RwLock};
+
+/// Function is called by FCALL with the keys of the call followed by its arguments.
+pub type Function = Arc<dyn Fn(Vec<Frame>) -> Frame + Send + Sync>;
+
+/// Names of the implementations a library function can be bound to.
+pub const BUILTIN_FUNCTIONS: &[&str] = &["concat", "echo", "len", "lower", "sum", "upper"];
+
+/// FunctionLibrary holds the loaded libraries and their functions, indexed by library name.
+/// Function names are unique across all the libraries.
+#[derive(Default)]
+pub struct FunctionLibrary {
+    libraries: RwLock<HashMap<String, HashMap<String, Function>>>,
+}
+
+impl FunctionLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// load parses a library and registers its functions. It returns the name of the library.
+    /// An existing library with the same name is only replaced when `replace` is true.
+    pub fn load(&self, code: &str, replace: bool) -> Result<String, FunctionError> {
+        let (name, functions) = parse_library(code)?;
+        let mut libraries = self.libraries.write().unwrap();
+        if !replace && libraries.contains_key(&name) {
+            return Err(FunctionError::LibraryExists(name));
+        }
+        // the functions of the replaced library do not conflict with the new ones
+        let conflict = libraries
+            .iter()
+            .filter(|(library, _)| **library != name)
+            .flat_map(|(_, existing)| existing.keys())
+            .find(|function| functions.contains_key(*function));
+        if let Some(function) = conflict {
+            return Err(FunctionError::FunctionExists(function.clone()));
+        }
+        libraries.insert(name.clone(), functions);
+        Ok(name)
+    }
+
+    /// delete removes a library and its functions.
+    pub fn delete(&self, name: &str) -> Result<(), FunctionError> {
+        match self.libraries.write().unwrap().remove(name) {
+            Some(_) => Ok(()),
+            None => Err(FunctionError::LibraryNotFound),
+        }
+    }
+
+    /// list returns the names of the loaded libraries, sorted.
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.libraries.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// call runs a function with the keys and the arguments of FCALL.
+    pub fn call(&self, name: &str, keys_and_args: Vec<Frame>) -> Result<Frame, FunctionError> {
+        let function = self
+            .libraries
+            .read()
+            .unwrap()
+            .values()
+            .find_map(|functions| functions.get(name).cloned())
+            .ok_or(FunctionError::FunctionNotFound)?;
+        // the lock is released, so a slow function does not block the other clients
+        Ok(function(keys_and_args))
+    }
+}
+
+impl Debug for FunctionLibrary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FunctionLibrary{{ libraries: {:?} }}", self.list())
+    }
+}
+
+/// parse_library reads the name and the functions of a library from its JSON code.
+fn parse_library(code: &str) -> Result<(String, HashMap<String, Function>), FunctionError> {
+    let invalid = |reason: &str| FunctionError::InvalidCode(reason.to_string());
+    let library: serde_json::Value =
+        serde_json::from_str(code).map_err(|err| FunctionError::InvalidCode(err.to_string()))?;
+    let name = library
+        .get("name")
+        .and_then(|name| name.as_str())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| invalid("missing library name"))?;
+    let bindings = library
+        .get("functions")
+        .and_then(|functions| functions.as_object())
+        .filter(|functions| !functions.is_empty())
+        .ok_or_else(|| invalid("no functions registered"))?;
+    let mut functions = HashMap::with_capacity(bindings.len());
+    for (function, implementation) in bindings {
+        let implementation = implementation
+            .as_str()
+            .ok_or_else(|| invalid("function implementations must be strings"))?;
+        let builtin = builtin(implementation)
+            .ok_or_else(|| FunctionError::UnknownImplementation(implementation.to_string()))?;
+        functions.insert(function.clone(), builtin);
+    }
+    Ok((name.to_string(), functions))
+}
+
+/// builtin returns the implementation with the given name, among BUILTIN_FUNCTIONS.
+fn builtin(name: &str) -> Option<Function> {
+    let function: Function = match name {
+        "concat" => Arc::new(|frames| {
+            Frame::Bulk(frames.iter().map(frame_str).collect::<Vec<_>>().concat())
+        }),
+        "echo" => Arc::new(Frame::Array),
+        "len" => Arc::new(|frames| Frame::Integer(frames.len() as i64)),
+        "lower" => Arc::new(|frames| map_bulks(frames, str::to_lowercase)),
+        "sum" => Arc::new(|frames| {
+            let numbers: Result<Vec<i64>, _> = frames
+                .iter()
+                .map(|frame| frame_str(frame).parse())
+                .collect();
+            match numbers.map(|numbers| numbers.into_iter().try_fold(0i64, i64::checked_add)) {
+                Ok(Some(sum)) => Frame::Integer(sum),
+                Ok(None) => Frame::Error("ERR increment or decrement would overflow".to_string()),
+                Err(_) => Frame::Error("ERR value is not an integer or out of range".to_string()),
+            }
+        }),
+        "upper" => Arc::new(|frames| map_bulks(frames, str::to_uppercase)),
+        _ => return None,
+    };
+    Some(function)
+}
+
+fn frame_str(frame: &Frame) -> &str {
+    match frame {
+        Frame::Bulk(value) | Frame::Simple(value) => value,
+        _ => "",
+    }
+}
+
+fn map_bulks(frames: Vec<Frame>, f: fn(&str) -> String) -> Frame {
+    Frame::Array(
+        frames
+            .iter()
+            .map(|frame| Frame::Bulk(f(frame_str(frame))))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulks(values: &[&str]) -> Vec<Frame> {
+        values.iter().map(|v| Frame::Bulk(v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_function_library() {
+        let library = FunctionLibrary::new();
+        let code = r#"{"name": "mylib", "functions": {"myecho": "echo", "total": "sum"}}"#;
+        assert_eq!(library.load(code, false).unwrap(), "mylib");
+        assert!(matches!(
+            library.load(code, false),
+            Err(FunctionError::LibraryExists(_))
+        ));
+        assert!(library.load(code, true).is_ok());
+        assert!(matches!(
+            library.load(r#"{"name": "other", "functions": {"total": "len"}}"#, false),
+            Err(FunctionError::FunctionExists(_))
+        ));
+        assert!(matches!(
+            library.load(r#"{"name": "other", "functions": {"f": "eval"}}"#, false),
+            Err(FunctionError::UnknownImplementation(_))
+        ));
+        assert!(matches!(
+            library.load("not json", false),
+            Err(FunctionError::InvalidCode(_))
+        ));
+
+        assert_eq!(
+            library.call("myecho", bulks(&["a", "b"])).unwrap(),
+            Frame::Array(bulks(&["a", "b"]))
+        );
+        assert_eq!(
+            library.call("total", bulks(&["1", "2", "-5"])).unwrap(),
+            Frame::Integer(-2)
+        );
+        assert_eq!(library.list(), ["mylib"]);
+
+        library.delete("mylib").unwrap();
+        assert!(library.list().is_empty());
+        assert!(matches!(
+            library.call("myecho", vec![]),
+            Err(FunctionError::FunctionNotFound)
+        ));
+        assert!(matches!(
+            library.delete("mylib"),
+            Err(FunctionError::LibraryNotFound)
+        ));
+    }
+}
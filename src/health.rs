@@ -0,0 +1,150 @@
+//! HTTP health checks, served on a port of their own for orchestrators and load balancers.
+//!
+//! `GET /health` answers as long as the process runs. `GET /ready` only answers 200 once the server
+//! accepts connections, and as long as every database can be used: a panic while a database was
+//! being written leaves it poisoned, and every command using it would fail.
+//! The HTTP parsing is minimal: only the request line is read, and the connection is closed after
+//! the response.
+
+use crate::db::State;
+use serde_json::json;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Port the health checks are served on by default.
+pub const DEFAULT_HEALTH_PORT: u16 = 8080;
+
+// Health checks are served one at a time, so a slow client cannot hold the others for long.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// HealthServer answers the HTTP health checks of the server.
+#[derive(Debug)]
+pub struct HealthServer {
+    listener: TcpListener,
+    started_at: Instant,
+    databases: Arc<Vec<Arc<State>>>,
+    // Set once the server accepts connections.
+    accepting: Arc<AtomicBool>,
+}
+
+impl HealthServer {
+    /// bind listens for health checks on `addr`. The server is not ready until `accepting` is set.
+    pub fn bind(
+        addr: SocketAddr,
+        databases: Arc<Vec<Arc<State>>>,
+        accepting: Arc<AtomicBool>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            started_at: Instant::now(),
+            databases,
+            accepting,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// spawn serves the health checks in a dedicated thread.
+    pub fn spawn(self: Arc<Self>) -> io::Result<JoinHandle<()>> {
+        thread::Builder::new()
+            .name("htcache-health".to_string())
+            .spawn(move || self.serve())
+    }
+
+    fn serve(&self) {
+        for stream in self.listener.incoming() {
+            let result = stream.and_then(|stream| self.handle(stream));
+            if let Err(err) = result {
+                debug!(error_message = err.to_string(), "health check failed");
+            }
+        }
+    }
+
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = self.respond(parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let body = body.to_string();
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    /// respond returns the status line and the body answering a request.
+    fn respond(&self, method: &str, path: &str) -> (&'static str, serde_json::Value) {
+        match (method, path) {
+            ("GET", "/health") => (
+                "200 OK",
+                json!({"status": "ok", "uptime_seconds": self.started_at.elapsed().as_secs()}),
+            ),
+            ("GET", "/ready") if self.is_ready() => ("200 OK", json!({"status": "ready"})),
+            ("GET", "/ready") => ("503 Service Unavailable", json!({"status": "not ready"})),
+            ("GET", _) => ("404 Not Found", json!({"error": "not found"})),
+            _ => (
+                "405 Method Not Allowed",
+                json!({"error": "method not allowed"}),
+            ),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.accepting.load(Ordering::SeqCst) && self.databases.iter().all(|db| db.is_available())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+    use std::io::Read;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_health_server() {
+        let session = test_session();
+        let accepting = Arc::new(AtomicBool::new(false));
+        let health = HealthServer::bind(
+            "127.0.0.1:0".parse().unwrap(),
+            session.databases().clone(),
+            accepting.clone(),
+        )
+        .unwrap();
+        let addr = health.local_addr().unwrap();
+        Arc::new(health).spawn().unwrap();
+
+        let response = get(addr, "/health");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(
+            response.ends_with(r#"{"status":"ok","uptime_seconds":0}"#),
+            "{}",
+            response
+        );
+        let response = get(addr, "/ready");
+        assert!(response.starts_with("HTTP/1.1 503 "), "{}", response);
+        accepting.store(true, Ordering::SeqCst);
+        let response = get(addr, "/ready");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(get(addr, "/nope").starts_with("HTTP/1.1 404 "));
+    }
+}
@@ -0,0 +1,58 @@
+//! Building blocks of the INFO command.
+//! Every component exposing statistics implements `InfoCollector` for the section it owns.
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// InfoCollector is implemented by the components reporting a section of the INFO command.
+pub trait InfoCollector {
+    /// section returns the name of the section, in lower case, as requested by `INFO <section>`.
+    fn section(&self) -> &'static str;
+
+    /// collect returns the fields of the section in the order they should be displayed.
+    /// Field names are usually static, but can be built for sections with a field per item.
+    fn collect(&self) -> Vec<(Cow<'static, str>, String)>;
+}
+
+/// format_section renders a section the way Redis does: a `# Title` header and `field:value` lines.
+pub fn format_section(collector: &dyn InfoCollector) -> String {
+    let section = collector.section();
+    let mut title = section.to_string();
+    if let Some(first) = title.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    let mut text = format!("# {}\r\n", title);
+    for (field, value) in collector.collect() {
+        // writing to a String cannot fail
+        let _ = write!(text, "{}:{}\r\n", field, value);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    impl InfoCollector for Fixed {
+        fn section(&self) -> &'static str {
+            "clients"
+        }
+
+        fn collect(&self) -> Vec<(Cow<'static, str>, String)> {
+            vec![
+                ("connected_clients".into(), "2".to_string()),
+                ("maxclients".into(), "10".to_string()),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_format_section() {
+        assert_eq!(
+            format_section(&Fixed),
+            "# Clients\r\nconnected_clients:2\r\nmaxclients:10\r\n"
+        );
+    }
+}
@@ -1,7 +1,28 @@
+pub mod acl;
+pub mod acl_log;
+pub mod admin_socket;
+pub mod circuit_breaker;
+pub mod clients;
+pub mod cluster;
+pub mod command_stats;
+pub mod config;
 pub mod connection;
 pub mod error;
 pub mod frame;
+#[cfg(feature = "commands-scripting")]
+pub mod functions;
+pub mod glob;
+pub mod health;
+pub mod info;
+pub mod middleware;
+pub mod pidfile;
+pub mod replication;
+pub mod scripting;
 pub mod server;
+pub mod session;
+pub mod shutdown;
+pub mod signals;
+pub mod sniffer;
 pub mod threadpool;
 
 pub mod cmd;
@@ -1,9 +1,24 @@
-use htcache::server;
+use htcache::{health, server, signals};
+use std::sync::Arc;
 
 /// main is a placeholder for testing the application for now
 pub fn main() -> std::io::Result<()> {
     tracing_subscriber::fmt::try_init().expect("unable to initialize logging");
-    let server = server::create_server("127.0.0.1".to_string(), 6379, 100, 10000000, 32, 80)?;
+    let mut server = server::create_server(
+        "127.0.0.1".to_string(),
+        6379,
+        100,
+        10000000,
+        32,
+        80,
+        511,
+        None,
+    )?;
+    server.enable_health(health::DEFAULT_HEALTH_PORT)?;
+    let server = Arc::new(server);
+    signals::install_signal_handlers(server.clone())?;
     server.listen();
+    // listen returns once SHUTDOWN or SIGTERM is received
+    server.wait_for_drain(std::time::Duration::from_secs(10));
     Ok(())
 }
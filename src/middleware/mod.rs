@@ -0,0 +1,145 @@
+//! Middlewares intercept the commands of a connection before and after they run.
+//!
+//! The connection parses a command, then calls `before` on every middleware, in order, with the
+//! keys of the command. The first middleware returning an error frame stops the command: the frame
+//! is sent to the client instead of the reply. Once the command ran, `after` is called with its
+//! reply.
+
+use crate::frame::Frame;
+use crate::session::Session;
+use std::fmt::Debug;
+use tracing::debug;
+
+mod rate_limit;
+pub use rate_limit::RateLimiter;
+mod wal;
+pub use wal::WalMiddleware;
+
+pub trait Middleware: Debug + Send + Sync {
+    /// before is called before a command runs. `frames` holds the whole command, name included,
+    /// and `keys` the keys it accesses. They are None when the command could not be parsed, it
+    /// is then replied the parsing error.
+    fn before(
+        &self,
+        _session: &Session,
+        _cmd_name: &str,
+        _frames: &[Frame],
+        _keys: Option<&[&str]>,
+    ) -> Result<(), Frame> {
+        Ok(())
+    }
+
+    /// after is called with the reply of a command which ran, before the reply is sent.
+    fn after(&self, _session: &Session, _cmd_name: &str, _frames: &[Frame], _response: &Frame) {}
+}
+
+/// default_middlewares returns the middlewares used by the server when none is configured:
+/// the request logger followed by the ACL enforcer.
+pub fn default_middlewares() -> Vec<Box<dyn Middleware>> {
+    vec![Box::new(RequestLogger), Box::new(AclEnforcer)]
+}
+
+/// RequestLogger logs the commands received and their replies at debug level.
+#[derive(Debug)]
+pub struct RequestLogger;
+
+impl Middleware for RequestLogger {
+    fn before(
+        &self,
+        session: &Session,
+        cmd_name: &str,
+        frames: &[Frame],
+        _: Option<&[&str]>,
+    ) -> Result<(), Frame> {
+        debug!(
+            client_id = session.client_id(),
+            args = frames.len() - 1,
+            "received command {}",
+            cmd_name
+        );
+        Ok(())
+    }
+
+    fn after(&self, session: &Session, cmd_name: &str, _: &[Frame], response: &Frame) {
+        match response {
+            Frame::Error(message) => debug!(
+                client_id = session.client_id(),
+                error_message = message,
+                "command {} failed",
+                cmd_name
+            ),
+            _ => debug!(client_id = session.client_id(), "command {} done", cmd_name),
+        }
+    }
+}
+
+/// AclEnforcer rejects the commands, and the keys, the user of the session is not allowed to use.
+/// Commands which cannot be parsed go through, so the client gets the parsing error.
+#[derive(Debug)]
+pub struct AclEnforcer;
+
+impl Middleware for AclEnforcer {
+    fn before(
+        &self,
+        session: &Session,
+        cmd_name: &str,
+        _: &[Frame],
+        keys: Option<&[&str]>,
+    ) -> Result<(), Frame> {
+        let Some(keys) = keys else {
+            return Ok(());
+        };
+        session.check_permission(cmd_name, keys).map_err(|err| {
+            debug!(error_message = err.to_string(), "command denied by ACL");
+            Frame::Error(format!("NOPERM {}", err))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{run_command, test_session, Acl};
+
+    fn frames(args: &[&str]) -> Vec<Frame> {
+        args.iter()
+            .map(|arg| Frame::Bulk(arg.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_acl_enforcer() {
+        let mut session = test_session();
+        run_command::<Acl>(
+            &mut session,
+            &[
+                "ACL", "SETUSER", "alice", "on", ">secret", "+get", "~user:*",
+            ],
+        );
+        session.authenticate("alice", "secret").unwrap();
+        assert!(AclEnforcer
+            .before(
+                &session,
+                "GET",
+                &frames(&["GET", "user:1"]),
+                Some(&["user:1"])
+            )
+            .is_ok());
+        assert!(matches!(
+            AclEnforcer.before(&session, "GET", &frames(&["GET", "admin"]), Some(&["admin"])),
+            Err(Frame::Error(e)) if e.starts_with("NOPERM")
+        ));
+        assert!(AclEnforcer
+            .before(
+                &session,
+                "SET",
+                &frames(&["SET", "user:1", "v"]),
+                Some(&["user:1"])
+            )
+            .is_err());
+        // the parsing error is replied by the command
+        assert!(AclEnforcer
+            .before(&session, "SET", &frames(&["SET"]), None)
+            .is_ok());
+    }
+}
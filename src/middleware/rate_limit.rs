@@ -0,0 +1,96 @@
+use crate::frame::Frame;
+use crate::middleware::Middleware;
+use crate::session::Session;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Above this number of clients tracked, the clients whose window is over are forgotten.
+const MAX_IDLE_BUCKETS: usize = 1024;
+
+/// RateLimiter limits the number of commands each client runs in a time window. The window of a
+/// client starts with its first command, and the commands above the limit are rejected until the
+/// window is over.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    // Start of the current window and number of commands run in it, by client id.
+    buckets: Mutex<HashMap<u64, (Instant, usize)>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Middleware for RateLimiter {
+    fn before(
+        &self,
+        session: &Session,
+        _: &str,
+        _: &[Frame],
+        _: Option<&[&str]>,
+    ) -> Result<(), Frame> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, (started_at, _)| now.duration_since(*started_at) < self.window);
+        }
+        let (started_at, count) = buckets.entry(session.client_id()).or_insert((now, 0));
+        if now.duration_since(*started_at) >= self.window {
+            *started_at = now;
+            *count = 0;
+        }
+        *count += 1;
+        if *count > self.limit {
+            return Err(Frame::Error("ERR rate limit exceeded".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+
+    #[test]
+    fn test_rate_limiter() {
+        let session = test_session();
+        let other_session = Session::new(
+            session.databases().clone(),
+            session.clients().register().unwrap(),
+            session.server().clone(),
+        );
+        let limiter = RateLimiter::new(100, Duration::from_secs(1));
+        let ping = vec![Frame::Bulk("PING".to_string())];
+        let rejected = (0..110)
+            .filter(|_| limiter.before(&session, "PING", &ping, Some(&[])).is_err())
+            .count();
+        assert_eq!(rejected, 10);
+        // clients have their own limit
+        assert!(limiter
+            .before(&other_session, "PING", &ping, Some(&[]))
+            .is_ok());
+        assert_eq!(
+            limiter.before(&session, "PING", &ping, Some(&[])),
+            Err(Frame::Error("ERR rate limit exceeded".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let session = test_session();
+        let limiter = RateLimiter::new(1, Duration::from_millis(50));
+        assert!(limiter.before(&session, "PING", &[], Some(&[])).is_ok());
+        assert!(limiter.before(&session, "PING", &[], Some(&[])).is_err());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.before(&session, "PING", &[], Some(&[])).is_ok());
+    }
+}
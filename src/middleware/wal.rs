@@ -0,0 +1,262 @@
+use crate::cmd::{self, docs::COMMAND_DOCS};
+use crate::error::FrameError;
+use crate::frame::{self, Frame};
+use crate::middleware::Middleware;
+use crate::session::Session;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, warn};
+
+/// WalMiddleware appends the commands changing the data to a write-ahead log, once they ran
+/// successfully and before their reply is sent, so they can be replayed when the server restarts.
+/// Commands are stored as RESP arrays, and a SELECT is added when the database changes. Only the
+/// commands flagged as `write` in COMMAND_DOCS are logged: the keys written by scripts and
+/// functions are not. Relative expiration times are logged as Unix times, so a replayed key
+/// expires when the original one did.
+#[derive(Debug)]
+pub struct WalMiddleware {
+    file: Mutex<WalFile>,
+    // When set, every entry is synced to the disk before the reply of the command is sent.
+    fsync_on_write: bool,
+}
+
+#[derive(Debug)]
+struct WalFile {
+    file: File,
+    // Database selected by the entries written so far, None until the first entry.
+    db_id: Option<usize>,
+}
+
+impl WalMiddleware {
+    /// open opens the log at `path` for appending, creating it if needed.
+    pub fn open(path: impl AsRef<Path>, fsync_on_write: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(WalFile { file, db_id: None }),
+            fsync_on_write,
+        })
+    }
+
+    /// replay runs the commands of the log at `path` within the session, and returns the number of
+    /// commands run. A missing log has no command. The replay stops at the first corrupted entry,
+    /// like the last one of a log whose write was interrupted.
+    pub fn replay(path: impl AsRef<Path>, session: &mut Session) -> io::Result<usize> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let mut reader = BufReader::new(file);
+        let mut replayed = 0;
+        loop {
+            let frame = match frame::decode(&mut reader) {
+                Ok(frame) => frame,
+                Err(FrameError::EOF) => break,
+                Err(err) => {
+                    warn!(
+                        error_message = err.to_string(),
+                        replayed, "stopping the replay of a corrupted write-ahead log"
+                    );
+                    break;
+                }
+            };
+            let Ok((cmd_name, frames)) = cmd::parse_frame(frame) else {
+                continue;
+            };
+            cmd::execute(&cmd_name, &frames, session);
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    fn append(&self, db_id: usize, frames: &[Frame]) -> io::Result<()> {
+        let mut wal = self.file.lock().unwrap();
+        let mut entry = Vec::new();
+        if wal.db_id != Some(db_id) {
+            let select = vec![
+                Frame::Bulk("SELECT".to_string()),
+                Frame::Bulk(db_id.to_string()),
+            ];
+            entry.extend(Frame::Array(select).encode());
+        }
+        entry.extend(Frame::Array(frames.to_vec()).encode());
+        // the file is opened for appending, so the entry is written at once at the end of the file
+        wal.file.write_all(&entry)?;
+        if self.fsync_on_write {
+            wal.file.sync_all()?;
+        }
+        wal.db_id = Some(db_id);
+        Ok(())
+    }
+}
+
+impl Middleware for WalMiddleware {
+    fn after(&self, session: &Session, cmd_name: &str, frames: &[Frame], response: &Frame) {
+        // failed commands changed nothing
+        if !is_write_command(cmd_name) || matches!(response, Frame::Error(_)) {
+            return;
+        }
+        let absolute = with_absolute_expiry(cmd_name, frames);
+        let frames = absolute.as_deref().unwrap_or(frames);
+        if let Err(err) = self.append(session.db().db_id(), frames) {
+            error!(
+                error_message = err.to_string(),
+                "unable to write to the write-ahead log"
+            );
+        }
+    }
+}
+
+/// with_absolute_expiry rewrites the commands setting a relative expiration time, which would
+/// start again when the log is replayed: `SET ... EX|PX` gets `PXAT`, and EXPIRE and PEXPIRE
+/// become PEXPIREAT. It returns None for the other commands.
+fn with_absolute_expiry(cmd_name: &str, frames: &[Frame]) -> Option<Vec<Frame>> {
+    match cmd_name {
+        "SET" => {
+            let (position, millis) = frames.iter().enumerate().skip(3).find_map(|(i, frame)| {
+                let Frame::Bulk(option) = frame else {
+                    return None;
+                };
+                Some((i, relative_millis(option, frames.get(i + 1)?)?))
+            })?;
+            let mut frames = frames.to_vec();
+            frames[position] = Frame::Bulk("PXAT".to_string());
+            frames[position + 1] = Frame::Bulk(unix_millis_in(millis).to_string());
+            Some(frames)
+        }
+        "EXPIRE" | "PEXPIRE" => {
+            let [_, key, time] = frames else {
+                return None;
+            };
+            let unit = if cmd_name == "EXPIRE" { "EX" } else { "PX" };
+            let millis = relative_millis(unit, time)?;
+            Some(vec![
+                Frame::Bulk("PEXPIREAT".to_string()),
+                key.clone(),
+                Frame::Bulk(unix_millis_in(millis).to_string()),
+            ])
+        }
+        _ => None,
+    }
+}
+
+/// relative_millis reads a time given in seconds with `unit` EX, or in milliseconds with PX, and
+/// returns it in milliseconds. Negative times count as zero. It returns None for other units.
+fn relative_millis(unit: &str, time: &Frame) -> Option<i64> {
+    let Frame::Bulk(time) = time else {
+        return None;
+    };
+    let time = || time.parse::<i64>().ok().map(|time| time.max(0));
+    match unit.to_uppercase().as_str() {
+        "EX" => time().map(|seconds| seconds.saturating_mul(1000)),
+        "PX" => time(),
+        _ => None,
+    }
+}
+
+/// unix_millis_in returns the Unix time, in milliseconds, in `millis` milliseconds from now.
+fn unix_millis_in(millis: i64) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    i64::try_from(now.as_millis())
+        .unwrap_or(i64::MAX)
+        .saturating_add(millis)
+}
+
+fn is_write_command(cmd_name: &str) -> bool {
+    COMMAND_DOCS
+        .iter()
+        .find(|doc| doc.name.eq_ignore_ascii_case(cmd_name))
+        .is_some_and(|doc| doc.flags.contains(&"write"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::test_session;
+    use std::time::Duration;
+
+    fn frames(args: &[&str]) -> Vec<Frame> {
+        args.iter()
+            .map(|arg| Frame::Bulk(arg.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_wal_replay() {
+        let path = std::env::temp_dir().join(format!("htcache-wal-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut session = test_session();
+        let wal = WalMiddleware::open(&path, true).unwrap();
+        let run = |session: &mut Session, args: &[&str]| {
+            let (cmd_name, frames) = cmd::parse_frame(Frame::Array(frames(args))).unwrap();
+            let response = cmd::execute(&cmd_name, &frames, session).unwrap();
+            wal.after(session, &cmd_name, &frames, &response);
+        };
+        run(&mut session, &["SET", "a", "one\r\nline"]);
+        run(&mut session, &["GET", "a"]);
+        // fails, so it is not logged
+        run(&mut session, &["INCR", "a"]);
+        run(&mut session, &["SELECT", "2"]);
+        run(&mut session, &["SET", "b", "two"]);
+        run(&mut session, &["DEL", "b"]);
+        run(&mut session, &["SET", "c", "three"]);
+        run(&mut session, &["EXPIRE", "c", "100"]);
+        run(&mut session, &["SET", "d", "four", "px", "100000"]);
+
+        let mut restarted = test_session();
+        // 6 writes and 2 SELECT
+        assert_eq!(WalMiddleware::replay(&path, &mut restarted).unwrap(), 8);
+        let databases = restarted.databases();
+        assert_eq!(
+            databases[0].get_value_by_key("a"),
+            Some("one\r\nline".to_string())
+        );
+        assert_eq!(databases[2].get_value_by_key("b"), None);
+        assert_eq!(
+            databases[2].get_value_by_key("c"),
+            Some("three".to_string())
+        );
+
+        // the keys expire when the original ones do, not 100s after the replay
+        for key in ["c", "d"] {
+            let expiry = databases[2].key_expiration(key).unwrap();
+            let original = session.databases()[2].key_expiration(key).unwrap();
+            let drift = expiry.max(original) - expiry.min(original);
+            assert!(drift < Duration::from_millis(50), "{:?}", drift);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(WalMiddleware::replay(&path, &mut restarted).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_with_absolute_expiry() {
+        let now = unix_millis_in(0);
+        let unix_millis = |frame: &Frame| match frame {
+            Frame::Bulk(time) => time.parse::<i64>().unwrap() - now,
+            frame => panic!("unexpected frame {:?}", frame),
+        };
+
+        let set = with_absolute_expiry("SET", &frames(&["SET", "k", "v", "NX", "ex", "10"]));
+        let set = set.unwrap();
+        assert_eq!(set[..5], frames(&["SET", "k", "v", "NX", "PXAT"])[..]);
+        assert!((10_000..10_100).contains(&unix_millis(&set[5])));
+
+        let expire = with_absolute_expiry("PEXPIRE", &frames(&["PEXPIRE", "k", "500"])).unwrap();
+        assert_eq!(expire[..2], frames(&["PEXPIREAT", "k"])[..]);
+        assert!((500..600).contains(&unix_millis(&expire[2])));
+
+        let expire = with_absolute_expiry("EXPIRE", &frames(&["EXPIRE", "k", "-1"])).unwrap();
+        assert!((0..100).contains(&unix_millis(&expire[2])));
+
+        assert!(with_absolute_expiry("SET", &frames(&["SET", "k", "v", "PXAT", "1"])).is_none());
+        assert!(with_absolute_expiry("SET", &frames(&["SET", "k", "v"])).is_none());
+        assert!(with_absolute_expiry("DEL", &frames(&["DEL", "k"])).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+//! PID file written for process supervisors and init scripts.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, ErrorKind, Read, Seek, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+/// PidFile holds the id of the server process in a file, removed when the PidFile is dropped.
+/// The file stays locked meanwhile, so that two instances cannot both own it.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl PidFile {
+    /// create writes the id of the current process to `path`. It fails if another instance holds
+    /// the lock of the file, or if the file holds the id of a process still running. A file left
+    /// by a process which stopped is replaced.
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => return Err(already_running()),
+                Err(TryLockError::Error(err)) => return Err(err),
+            }
+            // The instance which held the lock may have removed the file before releasing it:
+            // the lock is then held on a file nobody else sees, so start again.
+            let is_current = match (fs::metadata(&path), file.metadata()) {
+                (Ok(at_path), Ok(locked)) => {
+                    at_path.dev() == locked.dev() && at_path.ino() == locked.ino()
+                }
+                _ => false,
+            };
+            if !is_current {
+                continue;
+            }
+
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            if let Ok(pid) = content.trim().parse::<u32>() {
+                if pid != std::process::id() && is_running(pid) {
+                    return Err(already_running());
+                }
+            }
+            file.set_len(0)?;
+            file.rewind()?;
+            writeln!(file, "{}", std::process::id())?;
+            return Ok(Self { path, file });
+        }
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // removed before the lock is released, along with the file
+        let _ = fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+fn already_running() -> io::Error {
+    io::Error::new(ErrorKind::AddrInUse, "Another instance is running")
+}
+
+/// is_running tells whether a process exists. Without procfs, processes are assumed to be running,
+/// so a stale file has to be removed by hand rather than risking two instances.
+fn is_running(pid: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        PathBuf::from(format!("/proc/{}", pid)).exists()
+    } else {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pidfile() {
+        let path = std::env::temp_dir().join(format!("htcache-{}.pid", std::process::id()));
+        let pidfile = PidFile::create(&path).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n", std::process::id())
+        );
+        // the lock is held while the file exists, even by the same process
+        let err = PidFile::create(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        drop(pidfile);
+        assert!(!path.exists());
+
+        // pid 1 always runs
+        fs::write(&path, "1\n").unwrap();
+        let err = PidFile::create(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        // a pid above the maximum of Linux never runs
+        fs::write(&path, "4294967295\n").unwrap();
+        let pidfile = PidFile::create(&path).unwrap();
+        drop(pidfile);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_pidfile_concurrent_creation() {
+        let path = std::env::temp_dir().join(format!("htcache-race-{}.pid", std::process::id()));
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (path, barrier) = (path.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    PidFile::create(path)
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        drop(results);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,89 @@
+//! Book-keeping of the replicas connected to the server.
+//! Replication itself is not implemented yet, so the list of replicas stays empty in practice.
+
+use parking_lot::{Condvar, Mutex};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// ReplicaInfo describes a replica connected to the server.
+#[derive(Debug, Clone)]
+pub struct ReplicaInfo {
+    pub addr: SocketAddr,
+    /// Replication offset last acknowledged by the replica.
+    pub ack_offset: u64,
+}
+
+/// Replicas is the list of the replicas connected to the server. WAIT blocks on its condition
+/// variable, which is notified every time a replica is added.
+#[derive(Debug, Default)]
+pub struct Replicas {
+    list: Mutex<Vec<ReplicaInfo>>,
+    added: Condvar,
+}
+
+impl Replicas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add registers a replica and wakes up the connections blocked in WAIT.
+    pub fn add(&self, replica: ReplicaInfo) {
+        self.list.lock().push(replica);
+        self.added.notify_all();
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// wait blocks until at least `numreplicas` replicas are connected or until the timeout
+    /// expires. A `None` timeout blocks until enough replicas are connected.
+    /// It returns the number of connected replicas.
+    pub fn wait(&self, numreplicas: usize, timeout: Option<Duration>) -> usize {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut list = self.list.lock();
+        while list.len() < numreplicas {
+            match deadline {
+                Some(deadline) => {
+                    if self.added.wait_until(&mut list, deadline).timed_out() {
+                        break;
+                    }
+                }
+                None => self.added.wait(&mut list),
+            }
+        }
+        list.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_wait() {
+        let replicas = Arc::new(Replicas::new());
+        assert_eq!(replicas.wait(0, None), 0);
+
+        let start = Instant::now();
+        assert_eq!(replicas.wait(1, Some(Duration::from_millis(30))), 0);
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        let connecting = replicas.clone();
+        let replica = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            connecting.add(ReplicaInfo {
+                addr: "127.0.0.1:6380".parse().unwrap(),
+                ack_offset: 0,
+            });
+        });
+        assert_eq!(replicas.wait(1, None), 1);
+        replica.join().unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+//! Lua scripting for EVAL, EVALSHA and the SCRIPT commands.
+//!
+//! Running scripts needs the `lua` feature. Scripts run in a fresh Lua state with only the base,
+//! table, string and math libraries, so they cannot reach the file system or the operating
+//! system. They are stopped once they run longer than `lua-time-limit` or allocate more than
+//! `lua-memory-limit`. They access the keys through `redis.call` and `redis.pcall`, which run commands
+//! within the session of the client, checked by the `AclEnforcer` middleware.
+
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::RwLock;
+
+/// ScriptCache holds the scripts loaded with SCRIPT LOAD or run with EVAL, indexed by their SHA1
+/// in lower case hexadecimal.
+#[derive(Debug, Default)]
+pub struct ScriptCache {
+    scripts: RwLock<HashMap<String, String>>,
+}
+
+impl ScriptCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// insert caches a script and returns its SHA1.
+    pub fn insert(&self, script: &str) -> String {
+        let sha = sha1_hex(script);
+        self.scripts
+            .write()
+            .unwrap()
+            .insert(sha.clone(), script.to_string());
+        sha
+    }
+
+    /// get returns the script with the given SHA1. The SHA1 is not case-sensitive.
+    pub fn get(&self, sha: &str) -> Option<String> {
+        self.scripts
+            .read()
+            .unwrap()
+            .get(&sha.to_lowercase())
+            .cloned()
+    }
+
+    pub fn contains(&self, sha: &str) -> bool {
+        self.scripts
+            .read()
+            .unwrap()
+            .contains_key(&sha.to_lowercase())
+    }
+
+    /// flush removes all the cached scripts.
+    pub fn flush(&self) {
+        self.scripts.write().unwrap().clear();
+    }
+}
+
+/// sha1_hex returns the SHA1 of a script in lower case hexadecimal, as used by EVALSHA.
+pub fn sha1_hex(script: &str) -> String {
+    let mut hex = String::with_capacity(40);
+    for byte in Sha1::digest(script.as_bytes()) {
+        // writing to a String cannot fail
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+#[cfg(feature = "lua")]
+pub use lua::{compile, eval};
+
+#[cfg(feature = "lua")]
+mod lua {
+    use crate::cmd::{self, parse_frame};
+    use crate::frame::Frame;
+    use crate::middleware::{AclEnforcer, Middleware};
+    use crate::session::Session;
+    use mlua::{HookTriggers, Lua, LuaOptions, MultiValue, StdLib, Table, Value};
+    use std::cell::RefCell;
+    use std::fmt::{Display, Formatter};
+    use std::time::{Duration, Instant};
+
+    const SCRIPT_NAME: &str = "user_script";
+
+    // Number of Lua instructions between two checks of the time limit of a script.
+    const TIME_CHECK_PERIOD: u32 = 1000;
+
+    // Commands which cannot be called by scripts, mostly to prevent scripts from calling scripts.
+    const FORBIDDEN_COMMANDS: &[&str] =
+        &["EVAL", "EVALSHA", "SCRIPT", "FCALL", "FUNCTION", "SHUTDOWN"];
+
+    /// ReplyError is an error reply of a command called with redis.call. It stops the script and
+    /// is returned to the client as it is.
+    #[derive(Debug)]
+    struct ReplyError(String);
+
+    impl Display for ReplyError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl std::error::Error for ReplyError {}
+
+    /// compile checks that a script is valid Lua, without running it.
+    pub fn compile(script: &str) -> Result<(), String> {
+        let lua = sandbox().map_err(|err| first_line(&err))?;
+        lua.load(script)
+            .set_name(SCRIPT_NAME)
+            .into_function()
+            .map(|_| ())
+            .map_err(|err| format!("ERR Error compiling script: {}", first_line(&err)))
+    }
+
+    /// eval runs a script within the session of the client and returns its reply.
+    /// The keys and the arguments are available to the script in the KEYS and ARGV tables.
+    pub fn eval(script: &str, keys: &[String], args: &[String], session: &mut Session) -> Frame {
+        let config = session.db().config().clone();
+        let session = RefCell::new(session);
+        let result = sandbox().and_then(|lua| {
+            limit(&lua, config.lua_time_limit(), config.lua_memory_limit())?;
+            lua.globals()
+                .set("KEYS", lua.create_sequence_from(keys.iter().cloned())?)?;
+            lua.globals()
+                .set("ARGV", lua.create_sequence_from(args.iter().cloned())?)?;
+            lua.scope(|scope| {
+                let redis = lua.create_table()?;
+                redis.set(
+                    "call",
+                    scope.create_function(|lua, args: MultiValue| {
+                        match call(&mut session.borrow_mut(), args)? {
+                            Frame::Error(message) => {
+                                Err(mlua::Error::external(ReplyError(message)))
+                            }
+                            frame => frame_to_lua(lua, frame),
+                        }
+                    })?,
+                )?;
+                redis.set(
+                    "pcall",
+                    scope.create_function(|lua, args: MultiValue| {
+                        frame_to_lua(lua, call(&mut session.borrow_mut(), args)?)
+                    })?,
+                )?;
+                redis.set(
+                    "status_reply",
+                    lua.create_function(|lua, status: String| reply_table(lua, "ok", status))?,
+                )?;
+                redis.set(
+                    "error_reply",
+                    lua.create_function(|lua, error: String| reply_table(lua, "err", error))?,
+                )?;
+                lua.globals().set("redis", redis)?;
+                let value: Value = lua.load(script).set_name(SCRIPT_NAME).eval()?;
+                Ok(lua_to_frame(value))
+            })
+        });
+        result.unwrap_or_else(|err| error_frame(&err))
+    }
+
+    /// sandbox creates a Lua state without the libraries giving access to the system.
+    fn sandbox() -> mlua::Result<Lua> {
+        let lua = Lua::new_with(
+            StdLib::TABLE | StdLib::STRING | StdLib::MATH,
+            LuaOptions::default(),
+        )?;
+        // the base library can still read files, and load compiles code out of the script
+        for name in ["dofile", "loadfile", "load"] {
+            lua.globals().set(name, Value::Nil)?;
+        }
+        Ok(lua)
+    }
+
+    /// limit stops the scripts run by a Lua state once they run longer than `time_limit` or
+    /// allocate more than `memory_limit` bytes. 0 bytes is no memory limit.
+    fn limit(lua: &Lua, time_limit: Option<Duration>, memory_limit: usize) -> mlua::Result<()> {
+        lua.set_memory_limit(memory_limit)?;
+        if let Some(time_limit) = time_limit {
+            let deadline = Instant::now() + time_limit;
+            lua.set_hook(
+                HookTriggers::new().every_nth_instruction(TIME_CHECK_PERIOD),
+                move |_, _| {
+                    if Instant::now() < deadline {
+                        return Ok(());
+                    }
+                    Err(mlua::Error::runtime(
+                        "script killed after running longer than lua-time-limit",
+                    ))
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// call runs the command given to redis.call or redis.pcall and returns its reply.
+    fn call(session: &mut Session, args: MultiValue) -> mlua::Result<Frame> {
+        let mut frames = Vec::with_capacity(args.len());
+        for arg in args {
+            let arg = match arg {
+                Value::String(value) => value.to_string_lossy().into_owned(),
+                Value::Integer(value) => value.to_string(),
+                Value::Number(value) => value.to_string(),
+                _ => {
+                    return Ok(Frame::Error(
+                        "ERR Lua redis lib command arguments must be strings or integers"
+                            .to_string(),
+                    ))
+                }
+            };
+            frames.push(Frame::Bulk(arg));
+        }
+        let Ok((cmd_name, frames)) = parse_frame(Frame::Array(frames)) else {
+            return Ok(Frame::Error(
+                "ERR Please specify at least one argument for this redis lib call".to_string(),
+            ));
+        };
+        if FORBIDDEN_COMMANDS.contains(&cmd_name.as_str()) {
+            return Ok(Frame::Error(
+                "ERR This Redis command is not allowed from script".to_string(),
+            ));
+        }
+        let command = match cmd::parse(&cmd_name, &frames) {
+            Ok(command) => command,
+            Err(err) => return Ok(Frame::Error(err.to_string())),
+        };
+        if let Err(frame) = AclEnforcer.before(session, &cmd_name, &frames, Some(&command.keys())) {
+            return Ok(frame);
+        }
+        Ok(cmd::apply(&cmd_name, command.as_ref(), session).unwrap_or(Frame::Null))
+    }
+
+    fn reply_table<'lua>(
+        lua: &'lua Lua,
+        field: &str,
+        message: String,
+    ) -> mlua::Result<Table<'lua>> {
+        let table = lua.create_table()?;
+        table.set(field, message)?;
+        Ok(table)
+    }
+
+    /// frame_to_lua converts a command reply to a Lua value, the way Redis does.
+    fn frame_to_lua(lua: &Lua, frame: Frame) -> mlua::Result<Value<'_>> {
+        Ok(match frame {
+            Frame::Integer(value) => Value::Integer(value),
+            Frame::Bulk(value) => Value::String(lua.create_string(&value)?),
+            Frame::Simple(value) => Value::Table(reply_table(lua, "ok", value)?),
+            Frame::Error(value) => Value::Table(reply_table(lua, "err", value)?),
+            Frame::Null => Value::Boolean(false),
+            Frame::Boolean(value) => Value::Boolean(value),
+            Frame::Array(frames) => {
+                let table = lua.create_table_with_capacity(frames.len(), 0)?;
+                for frame in frames {
+                    table.push(frame_to_lua(lua, frame)?)?;
+                }
+                Value::Table(table)
+            }
+            Frame::Map(fields) => {
+                let table = lua.create_table_with_capacity(0, fields.len())?;
+                for (key, value) in fields {
+                    table.set(frame_to_lua(lua, key)?, frame_to_lua(lua, value)?)?;
+                }
+                Value::Table(table)
+            }
+        })
+    }
+
+    /// lua_to_frame converts the value returned by a script to a reply, the way Redis does.
+    fn lua_to_frame(value: Value) -> Frame {
+        match value {
+            Value::Boolean(true) => Frame::Integer(1),
+            Value::Integer(value) => Frame::Integer(value),
+            // numbers are truncated to integers
+            Value::Number(value) => Frame::Integer(value as i64),
+            Value::String(value) => Frame::Bulk(value.to_string_lossy().into_owned()),
+            Value::Table(table) => {
+                if let Ok(Some(status)) = table.get::<_, Option<String>>("ok") {
+                    return Frame::Simple(status);
+                }
+                if let Ok(Some(error)) = table.get::<_, Option<String>>("err") {
+                    return Frame::Error(error);
+                }
+                // arrays stop at the first nil
+                Frame::Array(
+                    table
+                        .sequence_values::<Value>()
+                        .map_while(Result::ok)
+                        .map(lua_to_frame)
+                        .collect(),
+                )
+            }
+            _ => Frame::Null,
+        }
+    }
+
+    /// error_frame converts the error which stopped a script to a reply. Errors replied by
+    /// redis.call are returned as they are.
+    fn error_frame(err: &mlua::Error) -> Frame {
+        match err {
+            mlua::Error::CallbackError { cause, .. } => error_frame(cause),
+            mlua::Error::ExternalError(external) => match external.downcast_ref::<ReplyError>() {
+                Some(ReplyError(message)) => Frame::Error(message.clone()),
+                None => Frame::Error(format!("ERR Error running script: {}", first_line(err))),
+            },
+            _ => Frame::Error(format!("ERR Error running script: {}", first_line(err))),
+        }
+    }
+
+    /// first_line keeps the first line of an error, replies cannot hold line breaks.
+    fn first_line(err: &mlua::Error) -> String {
+        err.to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
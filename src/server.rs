@@ -1,27 +1,55 @@
+use crate::admin_socket::AdminSocket;
+use crate::clients::{ClientRegistration, ClientRegistry};
+use crate::cluster::Node;
+use crate::config::{ENABLE_DEBUG_COMMAND, SNIFFER_DIR, TCP_BACKLOG};
 use crate::connection::Connection;
-use crate::db::State;
 use crate::error::{FrameError, HandleCommandError};
+use crate::frame::Frame;
+use crate::health::HealthServer;
+use crate::middleware::{self, Middleware, WalMiddleware};
+use crate::pidfile::PidFile;
+use crate::session::{ServerState, Session};
+use crate::shutdown::Shutdown;
 use crate::{db, threadpool};
+use socket2::{Domain, Protocol, Socket, Type};
 use std::fmt::Debug;
 use std::io;
-use std::net::{TcpListener, TcpStream};
-use std::sync::Arc;
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{debug, error, info};
 
 #[derive(Debug)]
 pub struct Server {
     thread_pool: threadpool::ThreadPool,
-    tcp_listener: TcpListener,
+    // Taken by listen, and closed when the server shuts down.
+    tcp_listener: Mutex<Option<TcpListener>>,
+    local_addr: SocketAddr,
     cache: db::Cache,
-    // @ TODO: uncomment and implement
-    // max_connection: AtomicUsize,
-    // is_shutdown: AtomicBool,
+    clients: Arc<ClientRegistry>,
+    state: ServerState,
+    middlewares: Arc<Vec<Box<dyn Middleware>>>,
+    health: Option<Arc<HealthServer>>,
+    admin_socket: Option<Arc<AdminSocket>>,
+    // Set once the server accepts connections, for the readiness check.
+    accepting: Arc<AtomicBool>,
+    // Removes the PID file when the server is dropped.
+    _pidfile: Option<PidFile>,
 }
 
 /// `create_server` return a Result instead of the actual type.
 /// It is required in this case because creating a new server requires
 ///  preparing threads that it will use to process the requests.
 /// And, creating threads is likely to fail for reasons related to the OS.
+/// `tcp_backlog` is the size of the queue of connections waiting to be accepted, reported by
+/// `CONFIG GET tcp-backlog`. The OS silently clamps it to its own limit, which is
+/// `/proc/sys/net/core/somaxconn` on Linux.
+/// With a `pidfile`, the id of the process is written to it, and the server refuses to start if
+/// another instance runs with the same file.
+#[allow(clippy::too_many_arguments)]
 pub fn create_server(
     server_ip: String,
     server_port: u16,
@@ -29,41 +57,205 @@ pub fn create_server(
     cache_capacity: usize,
     shard_count: usize,
     eviction_threshold: u8,
+    tcp_backlog: i32,
+    pidfile: Option<String>,
 ) -> io::Result<Server> {
+    let pidfile = pidfile.map(PidFile::create).transpose()?;
+    let cache = db::create_cache(cache_capacity, shard_count, eviction_threshold)?;
+    let config = cache.databases()[0].config().clone();
+    config
+        .set_at_startup(&[(TCP_BACKLOG, &tcp_backlog.to_string())])
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
     let ip = format!("{}:{}", server_ip, server_port);
-    let tcp_listener = TcpListener::bind(ip)?;
+    let tcp_listener = bind(&ip, config.tcp_backlog())?;
     let thread_pool = crate::threadpool::ThreadPool::new(worker_count)?;
+    let local_addr = tcp_listener.local_addr()?;
+    let state = ServerState::new(Node::new(local_addr)).with_thread_pool(thread_pool.counters());
 
     info!("htcache server initialized");
-    let cache = db::create_cache(cache_capacity, shard_count, eviction_threshold)?;
 
     Ok(Server {
         thread_pool,
-        tcp_listener,
+        tcp_listener: Mutex::new(Some(tcp_listener)),
+        local_addr,
+        clients: Arc::new(ClientRegistry::new(config)),
         cache,
+        state,
+        middlewares: Arc::new(middleware::default_middlewares()),
+        health: None,
+        admin_socket: None,
+        accepting: Arc::new(AtomicBool::new(false)),
+        _pidfile: pidfile,
     })
 }
 
+/// bind creates the listener of the server. Unlike `TcpListener::bind`, which uses a fixed
+/// backlog, the size of the queue of pending connections is given.
+fn bind(addr: &str, backlog: i32) -> io::Result<TcpListener> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to listen to"))?;
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    // as TcpListener::bind does, so the server can restart while old connections are in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(backlog)?;
+    Ok(socket.into())
+}
+
 impl Server {
+    /// add_middleware appends a middleware to the ones intercepting the commands of every connection.
+    /// Middlewares are added before the server listens.
+    pub fn add_middleware(&mut self, middleware: Box<dyn Middleware>) {
+        Arc::get_mut(&mut self.middlewares)
+            .expect("middlewares are added before the server listens")
+            .push(middleware);
+    }
+
+    /// enable_wal replays the write-ahead log at `path`, then logs the commands changing the data
+    /// to it. It returns the number of commands replayed. With `fsync_on_write`, every command is
+    /// synced to the disk before its reply is sent.
+    pub fn enable_wal(
+        &mut self,
+        path: impl AsRef<Path>,
+        fsync_on_write: bool,
+    ) -> io::Result<usize> {
+        let registration = self.clients.register().ok_or(io::Error::other(
+            "max number of clients reached, unable to replay the write-ahead log",
+        ))?;
+        let mut session = self.new_session(registration);
+        let replayed = WalMiddleware::replay(&path, &mut session)?;
+        info!(replayed, "write-ahead log replayed");
+        self.add_middleware(Box::new(WalMiddleware::open(path, fsync_on_write)?));
+        Ok(replayed)
+    }
+
+    /// enable_health serves the HTTP health checks on `port`, on the address of the server.
+    /// They start being answered when the server listens.
+    pub fn enable_health(&mut self, port: u16) -> io::Result<()> {
+        let addr = SocketAddr::new(self.local_addr.ip(), port);
+        let health = HealthServer::bind(addr, self.cache.databases(), self.accepting.clone())?;
+        self.health = Some(Arc::new(health));
+        Ok(())
+    }
+
+    /// enable_debug_command allows the DEBUG command, which is refused by default. With a
+    /// `sniffer_dir`, DEBUG SNIFFER writes its files to this directory, clients only choosing
+    /// the name of the file.
+    pub fn enable_debug_command(&mut self, sniffer_dir: Option<PathBuf>) -> io::Result<()> {
+        let sniffer_dir = match sniffer_dir {
+            Some(dir) if !dir.is_dir() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("sniffer directory {} does not exist", dir.display()),
+                ))
+            }
+            Some(dir) => dir.to_str().map(str::to_string).ok_or(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the sniffer directory is not valid UTF-8",
+            ))?,
+            None => String::new(),
+        };
+        self.cache.databases()[0]
+            .config()
+            .set_at_startup(&[(ENABLE_DEBUG_COMMAND, "yes"), (SNIFFER_DIR, &sniffer_dir)])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+    }
+
+    /// enable_admin_socket accepts the admin commands on a Unix socket created at `path`.
+    /// They start being answered when the server listens.
+    pub fn enable_admin_socket(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let admin_socket = AdminSocket::bind(
+            path,
+            self.cache.databases(),
+            self.clients.clone(),
+            self.state.clone(),
+        )?;
+        self.admin_socket = Some(Arc::new(admin_socket));
+        Ok(())
+    }
+
+    fn new_session(&self, registration: ClientRegistration) -> Session {
+        Session::new(self.cache.databases(), registration, self.state.clone())
+    }
+
+    /// local_addr returns the address the server listens to, useful when it was bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// shutdown stops the server from accepting connections. The connections close once their
+    /// current command is done, right away for the idle ones, use wait_for_drain to wait for them.
+    pub fn shutdown(&self) {
+        self.state.shutdown().request();
+    }
+
+    /// wait_for_drain blocks until all the connections closed, or until the timeout is over.
+    /// It returns true when all the connections closed.
+    pub fn wait_for_drain(&self, timeout: Duration) -> bool {
+        self.state.shutdown().drain().wait(timeout)
+    }
+
     /// listen listens to incoming connections and process them. Each connection is processed in
     /// a separate thread.
     /// We started with our own implementation of a thread pool.
     /// We then, moved to tokio green threads.
+    /// It returns when the server shuts down, after closing the listener.
     pub fn listen(&self) {
         // show server's info to the user
         info!("{:?}", self);
+        let Some(tcp_listener) = self.tcp_listener.lock().unwrap().take() else {
+            error!("the server already listened");
+            return;
+        };
+        let shutdown = self.state.shutdown();
+        shutdown.listening(self.local_addr);
+        if let Some(health) = &self.health {
+            if let Err(e) = health.clone().spawn() {
+                log_error("unable to start the health server", e);
+            }
+        }
+        if let Some(admin_socket) = &self.admin_socket {
+            if let Err(e) = admin_socket.clone().spawn() {
+                log_error("unable to start the admin socket", e);
+            }
+        }
+        self.accepting.store(true, Ordering::SeqCst);
         info!("htcache server ready for new connections");
-        loop {
-            let conn_string = self.tcp_listener.accept();
+        // a shutdown requested before the address was recorded did not wake the listener up
+        while !shutdown.is_requested() {
+            let conn_string = tcp_listener.accept();
+            if shutdown.is_requested() {
+                break;
+            }
             match conn_string {
-                Ok((socket, addr)) => {
+                Ok((mut socket, addr)) => {
                     debug!("new connection established: {}", addr);
                     // Process each socket in parallel.
                     // Each connection needs to read and update the state so create a shared reference of the state
                     // and share it to the process_socket function.
-                    let db = self.cache.db();
+                    let Some(registration) = self.clients.register() else {
+                        debug!(
+                            "rejecting connection {}: max number of clients reached",
+                            addr
+                        );
+                        let _ = socket.write_all(b"-ERR max number of clients reached\r\n");
+                        continue;
+                    };
+                    let session = self.new_session(registration);
+                    let middlewares = self.middlewares.clone();
+                    let in_flight = match shutdown.register(&socket) {
+                        Ok(in_flight) => in_flight,
+                        Err(e) => {
+                            log_error("unable to register the connection", e);
+                            continue;
+                        }
+                    };
                     self.thread_pool.execute(move || {
-                        process_socket(socket, db);
+                        process_socket(socket, session, middlewares);
+                        drop(in_flight);
                     });
                 }
                 Err(e) => {
@@ -71,14 +263,17 @@ impl Server {
                 }
             }
         }
+        self.accepting.store(false, Ordering::SeqCst);
+        info!("htcache server stopped accepting connections");
     }
 }
 
-fn process_socket(socket: TcpStream, db: Arc<State>) {
-    let conn = Connection::new(socket, db);
+fn process_socket(socket: TcpStream, session: Session, middlewares: Arc<Vec<Box<dyn Middleware>>>) {
+    let shutdown = session.shutdown().clone();
+    let conn = Connection::new(socket, session, middlewares);
     match conn {
         Ok(mut conn) => {
-            process_commands(&mut conn);
+            process_commands(&mut conn, &shutdown);
         }
         Err(e) => {
             log_error("failed to create connection object", e);
@@ -86,9 +281,13 @@ fn process_socket(socket: TcpStream, db: Arc<State>) {
     }
 }
 
-fn process_commands(conn: &mut Connection) {
+fn process_commands(conn: &mut Connection, shutdown: &Shutdown) {
     loop {
         match conn.handle_command() {
+            Ok(_) if shutdown.is_requested() => {
+                debug!("closing connection, the server is shutting down");
+                break;
+            }
             Ok(_) => {}
             Err(HandleCommandError::Frame(FrameError::EOF)) => {
                 debug!(
@@ -97,13 +296,28 @@ fn process_commands(conn: &mut Connection) {
                 );
                 break;
             }
-            Err(e) => {
+            Err(HandleCommandError::Frame(FrameError::Encoding(e))) => {
+                debug!(
+                    error_message = e.to_string(),
+                    "closing connection, unable to read from it"
+                );
+                break;
+            }
+            Err(HandleCommandError::Frame(e)) => {
+                // the rest of the input cannot be split into frames any more, so as Redis does,
+                // the error is replied and the connection closed
                 debug!(
-                    // Internal error, log but don't send to a client.
                     error_message = e.to_string(),
-                    "error processing command  frame or name"
+                    "closing connection on a protocol error"
                 );
+                let reply = Frame::Error(format!("ERR Protocol error: {}", e));
+                if let Err(e) = conn.write_frame(&reply) {
+                    log_error("failed to send the protocol error to the client", e);
+                }
+                break;
             }
+            // the frame was read whole, the client can send the next one
+            Err(e) => conn.send_error(&e),
         };
     }
 }
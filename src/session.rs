@@ -0,0 +1,239 @@
+use crate::acl::{Acl, DEFAULT_USER};
+use crate::acl_log::AclLogEntry;
+use crate::clients::{ClientRegistration, ClientRegistry};
+use crate::cluster::Node;
+use crate::command_stats::CommandStats;
+use crate::db::State;
+use crate::error::AclError;
+#[cfg(feature = "commands-scripting")]
+use crate::functions::FunctionLibrary;
+use crate::replication::Replicas;
+use crate::scripting::ScriptCache;
+use crate::shutdown::Shutdown;
+use crate::sniffer::SnifferSlot;
+use crate::threadpool::ThreadPoolCounters;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// ServerState holds the server-wide registries shared by all the sessions.
+#[derive(Debug, Clone)]
+pub struct ServerState {
+    acl: Arc<Acl>,
+    // Replicas connected to this server, used by WAIT. Empty until replication is implemented.
+    replicas: Arc<Replicas>,
+    node: Arc<Node>,
+    #[cfg(feature = "commands-scripting")]
+    functions: Arc<FunctionLibrary>,
+    scripts: Arc<ScriptCache>,
+    command_stats: Arc<CommandStats>,
+    shutdown: Arc<Shutdown>,
+    thread_pool: ThreadPoolCounters,
+}
+
+impl ServerState {
+    /// new creates empty registries for the server described by `node`.
+    pub fn new(node: Node) -> Self {
+        Self {
+            acl: Arc::new(Acl::new()),
+            replicas: Arc::new(Replicas::new()),
+            node: Arc::new(node),
+            #[cfg(feature = "commands-scripting")]
+            functions: Arc::new(FunctionLibrary::new()),
+            scripts: Arc::new(ScriptCache::new()),
+            command_stats: Arc::new(CommandStats::new()),
+            shutdown: Arc::new(Shutdown::new()),
+            thread_pool: ThreadPoolCounters::default(),
+        }
+    }
+
+    /// with_thread_pool reports the counters of the pool running the connections in INFO.
+    pub fn with_thread_pool(mut self, counters: ThreadPoolCounters) -> Self {
+        self.thread_pool = counters;
+        self
+    }
+
+    /// shutdown returns the state of the server shutdown.
+    pub fn shutdown(&self) -> &Arc<Shutdown> {
+        &self.shutdown
+    }
+}
+
+/// Session is the context of a client connection handed to the commands.
+/// It gives access to the database used by the client and to the server-wide registries.
+pub struct Session {
+    // All the databases of the server, indexed by database id.
+    databases: Arc<Vec<Arc<State>>>,
+    // The database the commands of the session operate on.
+    db: Arc<State>,
+    // Keeps the client counted as connected for as long as the session lives.
+    registration: ClientRegistration,
+    server: ServerState,
+    // None until the client uses AUTH, commands then run as the default user.
+    authenticated_user: Option<String>,
+    // The last commands received from the client, oldest first.
+    command_history: VecDeque<String>,
+    // Set by DEBUG SNIFFER to copy the bytes of the connection to a file.
+    sniffer: SnifferSlot,
+}
+
+impl Session {
+    /// new creates the session of a client, using the first database and authenticated as the
+    /// default user. There is always at least one database.
+    pub fn new(
+        databases: Arc<Vec<Arc<State>>>,
+        registration: ClientRegistration,
+        server: ServerState,
+    ) -> Self {
+        Self {
+            db: databases[0].clone(),
+            databases,
+            registration,
+            server,
+            authenticated_user: None,
+            command_history: VecDeque::new(),
+            sniffer: SnifferSlot::default(),
+        }
+    }
+
+    /// reset puts the session back in the state of a new one: on the first database and
+    /// authenticated as the default user. The command history and the sniffer are kept, they
+    /// describe the connection rather than the session.
+    pub fn reset(&mut self) {
+        self.db = self.databases[0].clone();
+        self.authenticated_user = None;
+    }
+
+    /// db returns the database the commands of this session operate on.
+    pub fn db(&self) -> &Arc<State> {
+        &self.db
+    }
+
+    /// select switches the session to another database. It returns false if there is no database
+    /// with this index.
+    pub fn select(&mut self, index: usize) -> bool {
+        match self.databases.get(index) {
+            Some(db) => {
+                self.db = db.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// databases returns all the databases of the server, indexed by database id.
+    pub fn databases(&self) -> &Arc<Vec<Arc<State>>> {
+        &self.databases
+    }
+
+    pub fn clients(&self) -> &Arc<ClientRegistry> {
+        self.registration.registry()
+    }
+
+    pub fn acl(&self) -> &Arc<Acl> {
+        &self.server.acl
+    }
+
+    /// replicas returns the replicas connected to the server.
+    pub fn replicas(&self) -> &Arc<Replicas> {
+        &self.server.replicas
+    }
+
+    /// node returns the description of the server as a cluster node.
+    pub fn node(&self) -> &Arc<Node> {
+        &self.server.node
+    }
+
+    /// functions returns the function libraries shared by all the clients.
+    #[cfg(feature = "commands-scripting")]
+    pub fn functions(&self) -> &Arc<FunctionLibrary> {
+        &self.server.functions
+    }
+
+    /// scripts returns the Lua scripts cached for EVALSHA, shared by all the clients.
+    pub fn scripts(&self) -> &Arc<ScriptCache> {
+        &self.server.scripts
+    }
+
+    /// command_stats returns the execution statistics of the commands run by all the clients.
+    pub fn command_stats(&self) -> &Arc<CommandStats> {
+        &self.server.command_stats
+    }
+
+    /// shutdown returns the state of the server shutdown, requested with SHUTDOWN.
+    pub fn shutdown(&self) -> &Arc<Shutdown> {
+        &self.server.shutdown
+    }
+
+    /// thread_pool returns the counters of the pool running the connections.
+    pub fn thread_pool(&self) -> &ThreadPoolCounters {
+        &self.server.thread_pool
+    }
+
+    /// server returns the server-wide registries, to create other sessions sharing them.
+    pub fn server(&self) -> &ServerState {
+        &self.server
+    }
+
+    /// client_id returns the unique id of the client of the session.
+    pub fn client_id(&self) -> u64 {
+        self.registration.id()
+    }
+
+    /// record_command adds a command to the history of the session. Only the last
+    /// `command-history-len` commands are kept.
+    pub fn record_command(&mut self, entry: String) {
+        let capacity = self.db.config().command_history_len();
+        if capacity == 0 {
+            self.command_history.clear();
+            return;
+        }
+        while self.command_history.len() >= capacity {
+            self.command_history.pop_front();
+        }
+        self.command_history.push_back(entry);
+    }
+
+    /// command_history returns the last commands received from the client, oldest first.
+    pub fn command_history(&self) -> &VecDeque<String> {
+        &self.command_history
+    }
+
+    /// sniffer returns the sniffer of the connection, enabled with DEBUG SNIFFER.
+    pub fn sniffer(&self) -> &SnifferSlot {
+        &self.sniffer
+    }
+
+    /// user returns the name of the user the commands of the session run as.
+    pub fn user(&self) -> &str {
+        self.authenticated_user.as_deref().unwrap_or(DEFAULT_USER)
+    }
+
+    /// authenticate switches the session to another user if the password is valid.
+    /// Failed attempts are recorded in the ACL log.
+    pub fn authenticate(&mut self, username: &str, password: &str) -> Result<(), AclError> {
+        match self.server.acl.get_user(username) {
+            Some(rule) if rule.enabled && rule.check_password(password) => {
+                self.authenticated_user = Some(username.to_string());
+                Ok(())
+            }
+            _ => {
+                let err = AclError::AuthFailed;
+                let entry = AclLogEntry::new(self.registration.id(), username, "auth", &err);
+                self.server.acl.log().push(entry);
+                Err(err)
+            }
+        }
+    }
+
+    /// check_permission verifies that the user of the session can run a command on the given keys.
+    /// Denied commands are recorded in the ACL log.
+    pub fn check_permission(&self, command: &str, keys: &[&str]) -> Result<(), AclError> {
+        self.server
+            .acl
+            .check(self.user(), command, keys)
+            .inspect_err(|err| {
+                let entry = AclLogEntry::new(self.registration.id(), self.user(), command, err);
+                self.server.acl.log().push(entry);
+            })
+    }
+}
@@ -0,0 +1,225 @@
+//! Graceful shutdown of the server: the listener stops accepting connections, and the connections
+//! close once their current command is done.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{self, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// DrainBarrier counts the connections in flight, so the shutdown can wait for them to close.
+#[derive(Debug, Clone)]
+pub struct DrainBarrier {
+    in_flight: Arc<AtomicUsize>,
+    // True when no connection is in flight.
+    done: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl DrainBarrier {
+    pub fn new() -> Self {
+        Self {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            done: Arc::new((Mutex::new(true), Condvar::new())),
+        }
+    }
+
+    /// enter counts a connection in flight until the returned guard is dropped.
+    pub fn enter(&self) -> DrainGuard {
+        let (done, _) = &*self.done;
+        let mut done = done.lock().unwrap();
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        *done = false;
+        DrainGuard {
+            barrier: self.clone(),
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// wait blocks until no connection is in flight, or until the timeout is over.
+    /// It returns true when all the connections closed.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let (done, drained) = &*self.done;
+        let done = drained
+            .wait_timeout_while(done.lock().unwrap(), timeout, |done| !*done)
+            .unwrap()
+            .0;
+        *done
+    }
+}
+
+impl Default for DrainBarrier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// DrainGuard stands for a connection in flight. Dropping it releases the barrier.
+#[derive(Debug)]
+pub struct DrainGuard {
+    barrier: DrainBarrier,
+}
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        let (done, drained) = &*self.barrier.done;
+        let mut done = done.lock().unwrap();
+        if self.barrier.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            *done = true;
+            drained.notify_all();
+        }
+    }
+}
+
+/// Shutdown is shared by the listener, the connections and the SHUTDOWN command to stop the server.
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    requested: AtomicBool,
+    // Address of the listener, once the server listens.
+    listener_addr: Mutex<Option<SocketAddr>>,
+    drain: DrainBarrier,
+    // Streams of the open connections, by id. Their reading side is shut down when the server stops.
+    connections: Mutex<HashMap<u64, TcpStream>>,
+    next_connection_id: AtomicU64,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// listening records the address of the listener, woken up by `request`.
+    pub fn listening(&self, addr: SocketAddr) {
+        *self.listener_addr.lock().unwrap() = Some(addr);
+    }
+
+    /// request asks the server to stop. The listener is blocked waiting for a connection, so it
+    /// is woken up with a connection of its own. Idle connections are blocked reading their next
+    /// command: the reading side of their stream is shut down, so they read the end of the stream
+    /// and close. The reply of a command being run is still sent.
+    pub fn request(&self) {
+        if self.requested.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        for stream in self.connections.lock().unwrap().values() {
+            let _ = stream.shutdown(net::Shutdown::Read);
+        }
+        if let Some(mut addr) = *self.listener_addr.lock().unwrap() {
+            if addr.ip().is_unspecified() {
+                addr.set_ip(match addr {
+                    SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                    SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+                });
+            }
+            let _ = TcpStream::connect(addr);
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// drain returns the barrier counting the connections in flight.
+    pub fn drain(&self) -> &DrainBarrier {
+        &self.drain
+    }
+
+    /// register counts a connection in flight until the returned guard is dropped, and keeps its
+    /// stream to stop the connection from waiting for commands once the server shuts down.
+    pub fn register(self: &Arc<Self>, stream: &TcpStream) -> io::Result<ConnectionGuard> {
+        let stream = stream.try_clone()?;
+        let id = self.next_connection_id.fetch_add(1, Ordering::SeqCst);
+        let in_flight = self.drain.enter();
+        let mut connections = self.connections.lock().unwrap();
+        // request sets the flag before taking the lock: either it sees this stream, or this
+        // connection sees the flag
+        if self.is_requested() {
+            let _ = stream.shutdown(net::Shutdown::Read);
+        }
+        connections.insert(id, stream);
+        Ok(ConnectionGuard {
+            shutdown: self.clone(),
+            id,
+            _in_flight: in_flight,
+        })
+    }
+}
+
+/// ConnectionGuard stands for a connection registered to the shutdown. Dropping it forgets the
+/// stream of the connection, then releases the drain barrier.
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    shutdown: Arc<Shutdown>,
+    id: u64,
+    _in_flight: DrainGuard,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.shutdown.connections.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_drain_barrier() {
+        let barrier = DrainBarrier::new();
+        assert!(barrier.wait(Duration::ZERO));
+        let first = barrier.enter();
+        let second = barrier.enter();
+        assert_eq!(barrier.in_flight(), 2);
+        assert!(!barrier.wait(Duration::from_millis(10)));
+
+        drop(first);
+        let closing = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            drop(second);
+        });
+        assert!(barrier.wait(Duration::from_secs(5)));
+        assert_eq!(barrier.in_flight(), 0);
+        closing.join().unwrap();
+    }
+
+    #[test]
+    fn test_shutdown_stops_idle_connections() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        let shutdown = Arc::new(Shutdown::new());
+        let guard = shutdown.register(&stream).unwrap();
+        assert_eq!(shutdown.drain().in_flight(), 1);
+
+        // the connection is blocked reading its next command
+        let reading = thread::spawn(move || {
+            let mut buf = [0; 16];
+            io::Read::read(&mut stream, &mut buf).unwrap()
+        });
+        thread::sleep(Duration::from_millis(20));
+        shutdown.request();
+        assert_eq!(reading.join().unwrap(), 0);
+
+        drop(guard);
+        assert!(shutdown.drain().wait(Duration::ZERO));
+        assert!(shutdown.connections.lock().unwrap().is_empty());
+        drop(client);
+    }
+
+    #[test]
+    fn test_shutdown_wakes_listener() {
+        let listener = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+        let shutdown = Arc::new(Shutdown::new());
+        shutdown.listening(listener.local_addr().unwrap());
+        let requester = shutdown.clone();
+        let requesting = thread::spawn(move || requester.request());
+        assert!(listener.accept().is_ok());
+        assert!(shutdown.is_requested());
+        requesting.join().unwrap();
+    }
+}
@@ -0,0 +1,58 @@
+//! Unix signals handled by the server.
+//! The handlers installed by signal-hook only record the signals received. A dedicated thread
+//! reads them from the `Signals` iterator and acts on them, so no code of the server runs in a
+//! signal handler and nothing has to be async-signal-safe.
+
+use crate::server::Server;
+use signal_hook::consts::{SIGHUP, SIGTERM, SIGUSR1};
+use signal_hook::iterator::Signals;
+use std::io;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tracing::info;
+
+/// install_signal_handlers handles SIGTERM, SIGHUP and SIGUSR1 in a dedicated thread.
+/// SIGTERM shuts the server down. SIGHUP and SIGUSR1 are only logged: the configuration is not
+/// read from a file, so there is nothing to reload, and there is no append-only file to rewrite.
+pub fn install_signal_handlers(server: Arc<Server>) -> io::Result<JoinHandle<()>> {
+    let mut signals = Signals::new([SIGTERM, SIGHUP, SIGUSR1])?;
+    thread::Builder::new()
+        .name("htcache-signals".to_string())
+        .spawn(move || {
+            for signal in signals.forever() {
+                match signal {
+                    SIGTERM => {
+                        info!("SIGTERM received, shutting down");
+                        server.shutdown();
+                    }
+                    SIGHUP => info!("SIGHUP received, there is no configuration file to reload"),
+                    SIGUSR1 => info!("SIGUSR1 received, there is no append-only file to rewrite"),
+                    _ => {}
+                }
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::create_server;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sigterm_shuts_down() {
+        let server =
+            Arc::new(create_server("127.0.0.1".to_string(), 0, 1, 1000, 4, 80, 511, None).unwrap());
+        install_signal_handlers(server.clone()).unwrap();
+        let (stopped, stop) = mpsc::channel();
+        let listening = server.clone();
+        thread::spawn(move || {
+            listening.listen();
+            stopped.send(()).unwrap();
+        });
+        // the signal is received whether the listener already waits for connections or not
+        signal_hook::low_level::raise(SIGTERM).unwrap();
+        assert!(stop.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+//! Protocol sniffer, enabled on a connection with `DEBUG SNIFFER ON file`.
+//!
+//! The reader and the writer of every connection are wrapped in a `TeeReader` and a `TeeWriter`,
+//! which copy the bytes going through them to the sniffer of the connection, if there is one.
+//! The file gets a line per read or write call: the time, the direction and the bytes in hex.
+//!
+//! ```text
+//! 1718030400.123456 in 2a310d0a24340d0a50494e470d0a
+//! 1718030400.123521 out 2b504f4e470d0a
+//! ```
+
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+/// Direction of the bytes recorded by a sniffer, from the point of view of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// Sniffer appends the bytes exchanged on a connection to a file.
+#[derive(Debug)]
+pub struct Sniffer {
+    file: Mutex<File>,
+}
+
+impl Sniffer {
+    /// create opens the sniffer file, appending to it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// record writes a line with the bytes of a read or write call. Failing to write to the file
+    /// is logged, it must not fail the connection.
+    pub fn record(&self, direction: Direction, bytes: &[u8]) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let direction = match direction {
+            Direction::In => "in",
+            Direction::Out => "out",
+        };
+        let mut line = format!(
+            "{}.{:06} {} ",
+            now.as_secs(),
+            now.subsec_micros(),
+            direction
+        );
+        for byte in bytes {
+            // writing to a String cannot fail
+            let _ = write!(line, "{:02x}", byte);
+        }
+        line.push('\n');
+        if let Err(err) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            error!(
+                error_message = err.to_string(),
+                "unable to write to the sniffer"
+            );
+        }
+    }
+}
+
+/// SnifferSlot holds the sniffer of a connection. It is shared by the session, where DEBUG SNIFFER
+/// sets it, and by the wrappers of the connection streams.
+#[derive(Debug, Clone, Default)]
+pub struct SnifferSlot(Arc<RwLock<Option<Sniffer>>>);
+
+impl SnifferSlot {
+    /// set replaces the sniffer of the connection, None stops sniffing.
+    pub fn set(&self, sniffer: Option<Sniffer>) {
+        *self.0.write().unwrap() = sniffer;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.read().unwrap().is_some()
+    }
+
+    fn record(&self, direction: Direction, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if let Some(sniffer) = self.0.read().unwrap().as_ref() {
+            sniffer.record(direction, bytes);
+        }
+    }
+}
+
+/// TeeReader copies the bytes read from the inner reader to the sniffer of the connection.
+#[derive(Debug)]
+pub struct TeeReader<R> {
+    inner: R,
+    slot: SnifferSlot,
+}
+
+impl<R> TeeReader<R> {
+    pub fn new(inner: R, slot: SnifferSlot) -> Self {
+        Self { inner, slot }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.slot.record(Direction::In, &buf[..read]);
+        Ok(read)
+    }
+}
+
+/// TeeWriter copies the bytes written to the inner writer to the sniffer of the connection.
+#[derive(Debug)]
+pub struct TeeWriter<W> {
+    inner: W,
+    slot: SnifferSlot,
+}
+
+impl<W> TeeWriter<W> {
+    pub fn new(inner: W, slot: SnifferSlot) -> Self {
+        Self { inner, slot }
+    }
+}
+
+impl<W: Write> Write for TeeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.slot.record(Direction::Out, &buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_tee() {
+        let path = std::env::temp_dir().join(format!("htcache-sniffer-{}", std::process::id()));
+        let slot = SnifferSlot::default();
+        let mut reader = TeeReader::new(&b"+OK\r\n"[..], slot.clone());
+        let mut writer = TeeWriter::new(Vec::new(), slot.clone());
+
+        // nothing is recorded until a sniffer is set
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf).unwrap();
+        slot.set(Some(Sniffer::create(&path).unwrap()));
+        assert!(slot.is_enabled());
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        writer.write_all(b"PING").unwrap();
+        slot.set(None);
+        writer.write_all(b"PONG").unwrap();
+        assert_eq!(writer.inner, b"PINGPONG");
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<Vec<&str>> = content
+            .lines()
+            .map(|line| line.split(' ').collect())
+            .collect();
+        assert_eq!(lines.len(), 2, "{}", content);
+        assert_eq!(lines[0][1..], ["in", "4b0d0a"]);
+        assert_eq!(lines[1][1..], ["out", "50494e47"]);
+        assert!(lines[0][0].parse::<f64>().is_ok());
+    }
+}
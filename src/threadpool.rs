@@ -1,4 +1,7 @@
+use crate::info::InfoCollector;
+use std::borrow::Cow;
 use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::{
     io, panic,
     sync::{mpsc, Arc, Mutex},
@@ -17,6 +20,7 @@ pub struct ThreadPool {
     workers: Vec<Worker>,
     sender: mpsc::Sender<Message>,
     size: usize,
+    counters: ThreadPoolCounters,
 }
 
 impl Debug for ThreadPool {
@@ -48,10 +52,11 @@ impl ThreadPool {
             ));
         }
         let (sender, receiver) = create_shared_channel();
+        let counters = ThreadPoolCounters::new(size);
 
         let mut workers = Vec::with_capacity(size);
         for i in 0..size {
-            workers.push(Worker::new(i, receiver.clone())?);
+            workers.push(Worker::new(i, receiver.clone(), counters.clone())?);
             debug!(worker_id = i, "worker created");
         }
 
@@ -59,9 +64,26 @@ impl ThreadPool {
             workers,
             sender,
             size,
+            counters,
         })
     }
 
+    /// stats returns the number of jobs run by the workers so far.
+    pub fn stats(&self) -> ThreadPoolStats {
+        self.counters.stats()
+    }
+
+    /// reset_stats sets the counters returned by `stats` back to zero.
+    pub fn reset_stats(&self) {
+        self.counters.reset();
+    }
+
+    /// counters returns a handle on the counters of the pool, which stays valid after the pool is
+    /// moved. It is used to report the statistics of the pool in INFO.
+    pub fn counters(&self) -> ThreadPoolCounters {
+        self.counters.clone()
+    }
+
     /// Executes the given closure `f` on a thread in the thread pool.
     ///
     /// # Arguments
@@ -126,10 +148,9 @@ impl Drop for ThreadPool {
     }
 }
 
-/// `Worker` is a struct that represents a worker thread. Each worker has a unique identifier assigned via the `id` field.
+/// `Worker` is a struct that represents a worker thread. Each worker has a unique identifier, used in its logs.
 #[derive(Debug)]
 struct Worker {
-    id: usize,
     thread: Option<thread::JoinHandle<()>>,
 }
 
@@ -140,32 +161,39 @@ impl Worker {
     ///
     /// * `id` - An identifier for the worker thread.
     /// * `receiver` - A shared receiver for the worker thread to receive messages from.
+    /// * `counters` - The statistics of the pool, updated as messages are processed.
     ///
     /// # Returns
     ///
     /// A `Result` containing a `Worker` if the thread was successfully created, or `Error` if the OS failed to create the thread.
-    fn new(id: usize, receiver: SharedReceiver) -> io::Result<Worker> {
-        let worker_process = move || Self::process_messages(id, &receiver);
+    fn new(
+        id: usize,
+        receiver: SharedReceiver,
+        counters: ThreadPoolCounters,
+    ) -> io::Result<Worker> {
+        let worker_process = move || Self::process_messages(id, &receiver, &counters);
         let thread = thread::Builder::new().spawn(worker_process)?;
         Ok(Worker {
-            id,
             thread: Some(thread),
         })
     }
 
-    fn process_messages(id: usize, receiver: &SharedReceiver) {
+    fn process_messages(id: usize, receiver: &SharedReceiver, counters: &ThreadPoolCounters) {
         loop {
             match receiver.get_message() {
                 Message::Task(job) => {
                     debug!("worker {} received a job", id);
                     let result = panic::catch_unwind(panic::AssertUnwindSafe(job));
+                    counters.jobs_executed.fetch_add(1, Ordering::Relaxed);
 
                     if result.is_err() {
+                        counters.jobs_panicked.fetch_add(1, Ordering::Relaxed);
                         error!("the job caused the worker {} to panic!", id);
                     }
                 }
 
                 Message::Shutdown => {
+                    counters.shutdown_received.fetch_add(1, Ordering::Relaxed);
                     debug!("graceful shutdown from worker {}", id);
                     break;
                 }
@@ -178,6 +206,68 @@ impl Worker {
     }
 }
 
+/// ThreadPoolStats is a snapshot of the counters of a `ThreadPool`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThreadPoolStats {
+    /// Number of jobs run to completion or until they panicked.
+    pub jobs_executed: u64,
+    /// Number of jobs which panicked. The panic is caught, so the worker keeps running.
+    pub jobs_panicked: u64,
+    /// Number of workers which received the shutdown message.
+    pub shutdown_received: u64,
+}
+
+/// ThreadPoolCounters are the counters shared by the workers of a pool. A worker panicking is
+/// only logged, so they are the way to notice jobs silently failing.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadPoolCounters {
+    workers: usize,
+    jobs_executed: Arc<AtomicU64>,
+    jobs_panicked: Arc<AtomicU64>,
+    shutdown_received: Arc<AtomicU64>,
+}
+
+impl ThreadPoolCounters {
+    fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            ..Self::default()
+        }
+    }
+
+    pub fn stats(&self) -> ThreadPoolStats {
+        ThreadPoolStats {
+            jobs_executed: self.jobs_executed.load(Ordering::Relaxed),
+            jobs_panicked: self.jobs_panicked.load(Ordering::Relaxed),
+            shutdown_received: self.shutdown_received.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.jobs_executed.store(0, Ordering::Relaxed);
+        self.jobs_panicked.store(0, Ordering::Relaxed);
+        self.shutdown_received.store(0, Ordering::Relaxed);
+    }
+}
+
+impl InfoCollector for ThreadPoolCounters {
+    fn section(&self) -> &'static str {
+        "server"
+    }
+
+    fn collect(&self) -> Vec<(Cow<'static, str>, String)> {
+        let stats = self.stats();
+        vec![
+            ("thread_pool_workers".into(), self.workers.to_string()),
+            (
+                "thread_pool_jobs_processed".into(),
+                stats.jobs_executed.to_string(),
+            ),
+            ("thread_pool_panics".into(), stats.jobs_panicked.to_string()),
+        ]
+    }
+}
+
 #[derive(Clone)]
 struct SharedReceiver {
     receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
@@ -234,3 +324,31 @@ enum Message {
     /// receive error or a mutex lock error (poisoned or blocking).
     Error(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut pool = ThreadPool::new(2).unwrap();
+        pool.execute(|| {});
+        pool.execute(|| panic!("job failure"));
+        let counters = pool.counters();
+        pool.shutdown();
+        assert_eq!(
+            counters.stats(),
+            ThreadPoolStats {
+                jobs_executed: 2,
+                jobs_panicked: 1,
+                shutdown_received: 2,
+            }
+        );
+        assert_eq!(
+            counters.collect()[0],
+            ("thread_pool_workers".into(), "2".to_string())
+        );
+        pool.reset_stats();
+        assert_eq!(pool.stats(), ThreadPoolStats::default());
+    }
+}
@@ -0,0 +1,29 @@
+use htcache::cmd::docs::COMMAND_DOCS;
+use htcache::server::create_server;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+
+// Every command dispatched by the server needs a CommandDoc, COMMAND COUNT reports how many there are.
+#[test]
+fn test_command_count_matches_the_docs() {
+    let server =
+        Arc::new(create_server("127.0.0.1".to_string(), 0, 2, 1000, 4, 80, 511, None).unwrap());
+    let listener = {
+        let server = server.clone();
+        thread::spawn(move || server.listen())
+    };
+
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream
+        .write_all(b"*2\r\n$7\r\nCOMMAND\r\n$5\r\nCOUNT\r\n")
+        .unwrap();
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply).unwrap();
+    assert_eq!(reply, format!(":{}\r\n", COMMAND_DOCS.len()));
+
+    drop(stream);
+    server.shutdown();
+    listener.join().unwrap();
+}
@@ -0,0 +1,59 @@
+use htcache::server::{create_server, Server};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+
+fn start_server() -> (Arc<Server>, thread::JoinHandle<()>) {
+    let server =
+        Arc::new(create_server("127.0.0.1".to_string(), 0, 2, 1000, 4, 80, 511, None).unwrap());
+    let listener = {
+        let server = server.clone();
+        thread::spawn(move || server.listen())
+    };
+    (server, listener)
+}
+
+// After a frame which cannot be decoded, the server replies a protocol error and closes the
+// connection instead of reading the rest of the input as new frames.
+#[test]
+fn test_protocol_error_closes_the_connection() {
+    let (server, listener) = start_server();
+
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    let input = b"*1\r\n".repeat(htcache::frame::MAX_DEPTH + 1);
+    stream.write_all(&input).unwrap();
+    let mut reader = BufReader::new(&stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply).unwrap();
+    assert!(reply.starts_with("-ERR Protocol error: "), "{}", reply);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty(), "{:?}", String::from_utf8_lossy(&rest));
+
+    server.shutdown();
+    listener.join().unwrap();
+}
+
+// A bulk string larger than the limit is refused from its header, and the connection closed.
+#[test]
+fn test_bulk_too_large_closes_the_connection() {
+    let (server, listener) = start_server();
+
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    let input = format!(
+        "*2\r\n$3\r\nGET\r\n${}\r\n",
+        htcache::frame::MAX_BULK_SIZE + 1
+    );
+    stream.write_all(input.as_bytes()).unwrap();
+    let mut reader = BufReader::new(&stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply).unwrap();
+    assert_eq!(reply, "-ERR Protocol error: invalid bulk length\r\n");
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty(), "{:?}", String::from_utf8_lossy(&rest));
+
+    server.shutdown();
+    listener.join().unwrap();
+}
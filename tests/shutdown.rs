@@ -0,0 +1,37 @@
+use htcache::server::create_server;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A client which sends nothing does not hold the shutdown until the drain timeout.
+#[test]
+fn test_shutdown_drains_idle_clients() {
+    let server =
+        Arc::new(create_server("127.0.0.1".to_string(), 0, 2, 1000, 4, 80, 511, None).unwrap());
+    let listener = {
+        let server = server.clone();
+        thread::spawn(move || server.listen())
+    };
+
+    let mut idle = TcpStream::connect(server.local_addr()).unwrap();
+    idle.write_all(b"*1\r\n$4\r\nPING\r\n").unwrap();
+    let mut reader = BufReader::new(&idle);
+    let mut reply = String::new();
+    reader.read_line(&mut reply).unwrap();
+    assert_eq!(reply, "+PONG\r\n");
+
+    let mut client = TcpStream::connect(server.local_addr()).unwrap();
+    client.write_all(b"*1\r\n$8\r\nSHUTDOWN\r\n").unwrap();
+    listener.join().unwrap();
+    let started_at = Instant::now();
+    assert!(server.wait_for_drain(Duration::from_secs(10)));
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+
+    // both connections were closed by the server
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(client.read(&mut [0; 16]).unwrap(), 0);
+}
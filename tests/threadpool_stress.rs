@@ -0,0 +1,37 @@
+use htcache::threadpool::ThreadPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+const TASKS: usize = 100_000;
+
+#[test]
+fn test_shutdown_from_another_thread_runs_every_task() {
+    let mut pool = ThreadPool::new(8).unwrap();
+    let done = Arc::new(AtomicUsize::new(0));
+    for _ in 0..TASKS {
+        let done = done.clone();
+        pool.execute(move || {
+            thread::sleep(Duration::from_micros(1));
+            done.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+
+    let (finished, shutdown_done) = mpsc::channel();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        pool.shutdown();
+        // shutdown only decrements the size of the pool, so calling it again does nothing
+        pool.shutdown();
+        finished.send(pool.stats()).unwrap();
+    });
+
+    let stats = shutdown_done
+        .recv_timeout(Duration::from_secs(5))
+        .expect("shutdown did not complete within 5 seconds");
+    // the shutdown messages are queued after the tasks, so no task is dropped
+    assert_eq!(done.load(Ordering::SeqCst), TASKS);
+    assert_eq!(stats.jobs_executed, TASKS as u64);
+    assert_eq!(stats.shutdown_received, 8);
+}